@@ -0,0 +1,182 @@
+// Chip profiles for the boards Tock runs on
+//
+// A profile fixes what the architecture leaves implementation defined:
+//
+// - the memory map (flash and SRAM ranges)
+// - the number of external interrupts the NVIC implements (ICTR)
+// - the number of MPU regions (MPU_TYPE.DREGION)
+// - the CPUID value
+// - whether the bit-band regions are implemented - we only turn them on for
+//   chips whose reference manual documents them
+//
+// The memory map is part of the memory flux reasons about (see
+// memory_map.rs) so a proof only holds on the profiles whose memory backs
+// the addresses it uses. Apollo3 is simulation-only: no proof can hold on it
+// (see below).
+
+use super::lang::{ExceptionNumber, MAX_EXTERNAL_INTERRUPTS};
+use super::mem::memory_map::{MemoryAttributes, MemoryMap, Region};
+use super::mem::mpu::{MPU_MAX_REGIONS, MPU_TYPE_ADDR};
+use super::mem::nvic::NVIC_NUM_IRQS_ADDR;
+use super::mem::sys_control::{CPUID_ADDR, ICTR_ADDR, VTOR_ADDR};
+use super::mem::Memory;
+
+use flux_rs::bitvec::BV32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipProfile {
+    pub name: &'static str,
+    pub memory_map: MemoryMap,
+    // number of implemented external interrupts
+    pub num_irqs: u16,
+    pub mpu_regions: u8,
+    pub cpuid: BV32,
+    // where the vector table lives out of reset
+    pub vector_table: BV32,
+}
+
+// Cortex-M4 r0p1
+const CORTEX_M4_R0P1_CPUID: BV32 = BV32::new(0x410F_C241);
+
+const fn microcontroller_map(
+    flash_start: u32,
+    flash_end: u32,
+    sram_start: u32,
+    sram_end: u32,
+    bit_band: bool,
+) -> MemoryMap {
+    MemoryMap {
+        code: Region::new(
+            BV32::new(flash_start),
+            BV32::new(flash_end),
+            MemoryAttributes::Normal,
+            true,
+        ),
+        sram: Region::new(
+            BV32::new(sram_start),
+            BV32::new(sram_end),
+            MemoryAttributes::Normal,
+            true,
+        ),
+        ram: None,
+        bit_band,
+        // the kernel's statics are linked at the start of SRAM
+        kernel_statics: BV32::new(sram_start),
+        ..MemoryMap::armv7m_default()
+    }
+}
+
+// What NRF52840.memory() puts in the reserved part of the PPB - lets proofs
+// run against the chip instead of the architectural default map
+flux_rs::defs! {
+    fn is_nrf52840_map(mem: Memory) -> bool {
+        is_microcontroller_map(mem, 0x0000_0000, 0x000F_FFFF, 0x2000_0000, 0x2003_FFFF, false)
+    }
+}
+
+// Nordic nRF52840 (nrf52840dk, clue_nrf52840)
+pub const NRF52840: ChipProfile = ChipProfile {
+    name: "nRF52840",
+    // 1MB flash, 256KB SRAM
+    memory_map: microcontroller_map(0x0000_0000, 0x000F_FFFF, 0x2000_0000, 0x2003_FFFF, false),
+    num_irqs: 48,
+    mpu_regions: 8,
+    cpuid: CORTEX_M4_R0P1_CPUID,
+    vector_table: BV32::new(0x0000_0000),
+};
+
+// Microchip SAM4L (imix, hail)
+pub const SAM4L: ChipProfile = ChipProfile {
+    name: "SAM4L",
+    // 512KB flash, 64KB SRAM
+    memory_map: microcontroller_map(0x0000_0000, 0x0007_FFFF, 0x2000_0000, 0x2000_FFFF, false),
+    num_irqs: 80,
+    mpu_regions: 8,
+    cpuid: CORTEX_M4_R0P1_CPUID,
+    vector_table: BV32::new(0x0000_0000),
+};
+
+// ST STM32F446 (nucleo_f446re)
+pub const STM32F446: ChipProfile = ChipProfile {
+    name: "STM32F446",
+    // 512KB flash, 128KB SRAM
+    //
+    // NOTE: flash is aliased at 0 when booting from it. We do not model the
+    // alias so the vector table is read from flash directly.
+    memory_map: microcontroller_map(0x0800_0000, 0x0807_FFFF, 0x2000_0000, 0x2001_FFFF, true),
+    num_irqs: 97,
+    mpu_regions: 8,
+    cpuid: CORTEX_M4_R0P1_CPUID,
+    vector_table: BV32::new(0x0800_0000),
+};
+
+// Ambiq Apollo3 (redboard artemis)
+//
+// NOTE: this profile is simulation-only. Apollo3 puts its 384KB of TCM &
+// SRAM at 0x1000_0000 - inside the architectural code region. The flux
+// predicates only know RAM in the architectural SRAM & RAM regions (that is
+// what keeps RAM apart from the PPB and flash in the proofs) so
+// is_valid_ram_addr is false for all of it. The memory is writable at
+// runtime, but every precondition that needs RAM (stacks, the kernel's
+// statics) is false on this chip and no proof says anything about it.
+pub const APOLLO3: ChipProfile = ChipProfile {
+    name: "Apollo3",
+    // 1MB flash, 384KB SRAM
+    memory_map: microcontroller_map(0x0000_0000, 0x000F_FFFF, 0x1000_0000, 0x1005_FFFF, false),
+    num_irqs: 32,
+    mpu_regions: 8,
+    cpuid: CORTEX_M4_R0P1_CPUID,
+    vector_table: BV32::new(0x0000_0000),
+};
+
+pub const CHIP_PROFILES: [ChipProfile; 4] = [NRF52840, SAM4L, STM32F446, APOLLO3];
+
+impl ChipProfile {
+    // Memory as it is out of reset on this chip - the image still has to be
+    // loaded (see Memory::load) before calling Armv7m::reset
+    pub fn memory(&self) -> Memory {
+        assert!(
+            self.num_irqs <= MAX_EXTERNAL_INTERRUPTS,
+            "{} implements more interrupts than we model",
+            self.name
+        );
+        assert!(
+            self.mpu_regions <= MPU_MAX_REGIONS,
+            "{} implements more MPU regions than we model",
+            self.name
+        );
+        let mut mem = Memory::with_map(self.memory_map);
+        mem.load(CPUID_ADDR, self.cpuid);
+        mem.load(VTOR_ADDR, self.vector_table);
+        mem.load(ICTR_ADDR, self.ictr());
+        mem.load(MPU_TYPE_ADDR, self.mpu_type());
+        mem.load(NVIC_NUM_IRQS_ADDR, BV32::from(self.num_irqs as u32));
+        // reset again now that the number of MPU regions & interrupts is known
+        mem.reset_ppb();
+        mem
+    }
+
+    // ICTR.INTLINESNUM - interrupt lines in groups of 32 (see p. B3-620 of
+    // the manual)
+    pub fn ictr(&self) -> BV32 {
+        let groups = (self.num_irqs as u32).div_ceil(32);
+        BV32::from(groups.saturating_sub(1))
+    }
+
+    // MPU_TYPE.DREGION (bits 15:8) - the MPU is unified so SEPARATE and
+    // IREGION are 0
+    pub fn mpu_type(&self) -> BV32 {
+        BV32::from((self.mpu_regions as u32) << 8)
+    }
+
+    pub fn implements_irq(&self, irq: u16) -> bool {
+        irq < self.num_irqs && irq < MAX_EXTERNAL_INTERRUPTS
+    }
+
+    pub fn implements_exception(&self, exception: ExceptionNumber) -> bool {
+        match exception {
+            ExceptionNumber::External(irq) => self.implements_irq(irq),
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::armv7m::mem::MemFault;
+
+use super::Armv7m;
+
+use flux_rs::bitvec::BV32;
+
+// Accesses checked against the MPU (see MemA in chapter B2 of the manual)
+//
+// Every load and store in insns/ goes through the checks below with the
+// privilege of the code running it: an access the MPU does not permit or an
+// unprivileged access to the PPB raises the fault instead of completing.
+// An instruction checks all of its accesses before it makes any of them, so
+// a faulting LDM, STM, PUSH or POP is abandoned without changing state (the
+// manual lets it be restarted from the start on exception return).
+//
+// Instruction fetches are checked too - a branch to an address the MPU marks
+// XN (or one the current privilege can not read) takes a MemManage fault with
+// IACCVIOL set on the instruction at the target.
+impl Armv7m {
+    // Raises the fault for a data access the current mode can not make
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@addr], bool[@write]) -> bool[data_access_ok(addr, write, old_cpu)]
+            ensures self: Armv7m { new_cpu: data_access_ok(addr, write, old_cpu) => new_cpu == old_cpu }
+    )]
+    pub(crate) fn check_data_access(&mut self, address: BV32, write: bool) -> bool {
+        let privileged = self.is_privileged();
+        if !self.mem.ppb_permits(address, privileged, write) {
+            self.take_mem_fault(MemFault::PpbPrivilege(address));
+            false
+        } else if !self.mem.mpu_permits(address, privileged, write) {
+            self.take_mem_fault(MemFault::MpuViolation(address));
+            false
+        } else {
+            true
+        }
+    }
+
+    // The four words from address up, lowest first
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@addr], bool[@write]) -> bool[data_accesses_ok_4(addr, write, old_cpu)]
+            ensures self: Armv7m { new_cpu: data_accesses_ok_4(addr, write, old_cpu) => new_cpu == old_cpu }
+    )]
+    pub(crate) fn check_data_accesses_4(&mut self, address: BV32, write: bool) -> bool {
+        self.check_data_access(address, write)
+            && self.check_data_access(address + BV32::from(0x4), write)
+            && self.check_data_access(address + BV32::from(0x8), write)
+            && self.check_data_access(address + BV32::from(0xc), write)
+    }
+
+    // Raises the fault for fetching the instruction at the pc
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu]) -> bool[fetch_ok(old_cpu)]
+            ensures self: Armv7m { new_cpu: fetch_ok(old_cpu) => new_cpu == old_cpu }
+    )]
+    pub(crate) fn check_instruction_fetch(&mut self) -> bool {
+        let privileged = self.is_privileged();
+        let pc = self.pc;
+        if !self.mem.mpu_permits_execute(pc, privileged) {
+            self.take_mem_fault(MemFault::MpuExecute(pc));
+            false
+        } else {
+            true
+        }
+    }
+
+    // Returns None when the read faulted
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@addr]) -> Option<BV32{v: word_aligned(addr) => v == get_mem_addr(addr, old_cpu.mem)}>
+            requires is_valid_read_access(addr, 4, old_cpu.mem)
+            ensures self: Armv7m { new_cpu:
+                (data_access_ok(addr, false, old_cpu) && word_aligned(addr))
+                =>
+                new_cpu == Armv7m { mem: mem_post_read_access(addr, 4, old_cpu.mem), ..old_cpu }
+            }
+    )]
+    pub fn read32_checked(&mut self, address: BV32) -> Option<BV32> {
+        if !self.check_data_access(address, false) {
+            return None;
+        }
+        match self.mem.read32(address) {
+            Ok(value) => Some(value),
+            Err(fault) => {
+                self.take_mem_fault(fault);
+                None
+            }
+        }
+    }
+
+    // Returns true when the write completed
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@addr], BV32[@val])
+            -> bool{v: v => data_access_ok(addr, true, old_cpu)}
+            requires is_valid_write_access(addr, 4, old_cpu.mem)
+            ensures self: Armv7m { new_cpu:
+                (data_access_ok(addr, true, old_cpu) && word_aligned(addr))
+                =>
+                new_cpu == Armv7m { mem: update_mem(addr, old_cpu.mem, val), ..old_cpu }
+            }
+    )]
+    pub fn write32_checked(&mut self, address: BV32, value: BV32) -> bool {
+        if !self.check_data_access(address, true) {
+            return false;
+        }
+        match self.mem.write32(address, value) {
+            Ok(()) => true,
+            Err(fault) => {
+                self.take_mem_fault(fault);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use super::Armv7m;
+
+use flux_rs::bitvec::BV32;
+
+// A shadow call stack for diagnostics
+//
+// BL & BLX push a frame and a write to the pc that lands on the return
+// address of a frame (a pop {..., pc} or bx lr) pops it along with any frame
+// called after it. Nothing reads it back into the cpu state - it only answers
+// "how did we get here" when lifted code goes wrong.
+//
+// NOTE: exception entry and return leave it alone so a handler's calls sit on
+// top of whatever it preempted.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallFrame {
+    // address of the BL or BLX
+    pub call_site: BV32,
+    // the lr it wrote without the Thumb bit
+    pub return_address: BV32,
+    pub target: BV32,
+}
+
+impl Armv7m {
+    // Innermost call last
+    #[flux_rs::trusted]
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], BV32, BV32, BV32) ensures self: Armv7m[cpu])]
+    pub(super) fn push_call(&mut self, call_site: BV32, return_address: BV32, target: BV32) {
+        self.call_stack.push(CallFrame {
+            call_site,
+            return_address,
+            target,
+        });
+    }
+
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], BV32) ensures self: Armv7m[cpu])]
+    pub(super) fn note_return(&mut self, target: BV32) {
+        if let Some(depth) = self
+            .call_stack
+            .iter()
+            .rposition(|frame| frame.return_address == target)
+        {
+            self.call_stack.truncate(depth);
+        }
+    }
+}
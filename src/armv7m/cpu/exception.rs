@@ -1,4 +1,6 @@
-use crate::armv7m::lang::{SpecialRegister, GPR};
+use crate::armv7m::lang::{ExceptionNumber, Reg, SpecialRegister, GPR};
+
+use crate::armv7m::mem::Memory;
 
 use super::{Armv7m, CPUMode, Control};
 use flux_rs::bitvec::BV32;
@@ -11,36 +13,60 @@ impl Armv7m {
             BV32[get_gpr(r2(), cpu)],
             BV32[get_gpr(r3(), cpu)],
             BV32[get_gpr(r12(), cpu)],
-            BV32[get_special_reg(lr(), cpu)],
-            BV32[get_special_reg(psr(), cpu)]
+            BV32[get_reg(lr(), cpu)],
+            BV32[cpu.pc],
+            BV32[stacked_psr(cpu)]
         )
     )]
-    fn push_stack_get_gpr_vals(&mut self) -> (BV32, BV32, BV32, BV32, BV32, BV32, BV32) {
+    fn push_stack_get_gpr_vals(&mut self) -> (BV32, BV32, BV32, BV32, BV32, BV32, BV32, BV32) {
         let r0 = self.get_value_from_general_reg(&GPR::r0());
         let r1 = self.get_value_from_general_reg(&GPR::r1());
         let r2 = self.get_value_from_general_reg(&GPR::r2());
         let r3 = self.get_value_from_general_reg(&GPR::r3());
         let r12 = self.get_value_from_general_reg(&GPR::r12());
-        let lr = self.get_value_from_special_reg(&SpecialRegister::lr());
+        let lr = self.get_value_from_reg(&Reg::lr());
+        // the return address is the instruction the exception returns to
+        let ret_addr = self.pc;
+        // bit 9 of the stacked psr records whether the frame was realigned
         let psr = self.get_value_from_special_reg(&SpecialRegister::psr());
-        (r0, r1, r2, r3, r12, lr, psr)
+        let psr = if self.frame_ptr_align() {
+            psr | BV32::from(0x200)
+        } else {
+            psr & !BV32::from(0x200)
+        };
+        (r0, r1, r2, r3, r12, lr, ret_addr, psr)
     }
 
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@cpu])
-            requires is_valid_ram_addr(get_sp(cpu.sp, cpu.mode, cpu.control) - 0x20)
             ensures self: Armv7m { new_cpu: new_cpu == Armv7m { sp: sp_post_exception_entry(cpu), ..cpu } }
     )]
     fn push_stack_update_sp(&mut self) {
-        let frame_size = BV32::from(0x20);
-        let frame_ptr = self.get_value_from_special_reg(&SpecialRegister::sp());
-        let frame_ptr = (frame_ptr - frame_size); // & !BV32::from(3);
-        self.update_special_reg_with_b32(SpecialRegister::sp(), frame_ptr);
+        let frame_ptr = self.exception_frame_ptr();
+        self.update_reg_with_b32(Reg::sp(), frame_ptr);
+    }
+
+    // With CCR.STKALIGN set the frame goes 8 byte aligned, 4 bytes further
+    // down when SP is not
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> BV32[exception_frame_ptr(cpu)])]
+    fn exception_frame_ptr(&self) -> BV32 {
+        let frame_ptr = self.get_value_from_reg(&Reg::sp()) - BV32::from(0x20);
+        if self.mem.stack_align_enabled() {
+            frame_ptr & !BV32::from(0x4)
+        } else {
+            frame_ptr
+        }
+    }
+
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[frame_ptr_align(cpu)])]
+    fn frame_ptr_align(&self) -> bool {
+        let sp = self.get_value_from_reg(&Reg::sp());
+        self.mem.stack_align_enabled() && sp & BV32::from(0x4) != BV32::from(0)
     }
 
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@cpu], BV32[@r0], BV32[@r1], BV32[@r2], BV32[@r3], BV32[@r12], BV32[@lr], BV32[@psr])
-            requires push_stack_sp_precondition(get_sp(cpu.sp, cpu.mode, cpu.control))
+        fn (self: &strg Armv7m[@cpu], BV32[@r0], BV32[@r1], BV32[@r2], BV32[@r3], BV32[@r12], BV32[@lr], BV32[@ret_addr], BV32[@psr])
+            requires push_stack_sp_precondition(get_sp(cpu.sp, cpu.mode, cpu.control), cpu.mem)
             ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
                 mem: mem_post_push_stack_write_gpr_vals(
                      get_sp(cpu.sp, cpu.mode, cpu.control),
@@ -51,6 +77,7 @@ impl Armv7m {
                      r3,
                      r12,
                      lr,
+                     ret_addr,
                      psr
                 ),
                 ..cpu
@@ -64,18 +91,17 @@ impl Armv7m {
         r3: BV32,
         r12: BV32,
         lr: BV32,
+        ret_addr: BV32,
         psr: BV32,
     ) {
-        let frame_ptr = self.get_value_from_special_reg(&SpecialRegister::sp());
+        let frame_ptr = self.get_value_from_reg(&Reg::sp());
         self.mem.write(frame_ptr, r0);
         self.mem.write(frame_ptr + BV32::from(0x4), r1);
         self.mem.write(frame_ptr + BV32::from(0x8), r2);
         self.mem.write(frame_ptr + BV32::from(0xC), r3);
         self.mem.write(frame_ptr + BV32::from(0x10), r12);
         self.mem.write(frame_ptr + BV32::from(0x14), lr);
-        // putting a dummy value for ret addr
-        self.mem.write(frame_ptr + BV32::from(0x18), BV32::from(0));
-        // TODO: Real implementation skips bit 9
+        self.mem.write(frame_ptr + BV32::from(0x18), ret_addr);
         self.mem.write(frame_ptr + BV32::from(0x1C), psr);
     }
 
@@ -90,27 +116,29 @@ impl Armv7m {
             }
     )]
     fn push_stack(&mut self) {
-        let (r0, r1, r2, r3, r12, lr, psr) = self.push_stack_get_gpr_vals();
+        let (r0, r1, r2, r3, r12, lr, ret_addr, psr) = self.push_stack_get_gpr_vals();
         self.push_stack_update_sp();
-        self.push_stack_write_gpr_vals(r0, r1, r2, r3, r12, lr, psr);
+        self.push_stack_write_gpr_vals(r0, r1, r2, r3, r12, lr, ret_addr, psr);
     }
 
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@old_cpu], u8[@exception_num])
+        fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@exception_num])
             ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
                     mode: handler_mode(),
                     control: control_post_exception_entry(old_cpu),
+                    pending_control: control_post_exception_entry(old_cpu),
                     psr: psr_post_exception_entry(old_cpu, exception_num),
                     lr: lr_post_exception_entry(old_cpu, old_cpu.control),
+                    monitor: clear_exclusive_local(old_cpu),
                     ..old_cpu
                 }
             }
     )]
-    fn exception_taken(&mut self, exception_number: u8) {
+    fn exception_taken(&mut self, exception_number: ExceptionNumber) {
         // TODO: need to forget r0 - r3, r12 somehow
 
         // set exception num in psr
-        self.psr = (self.psr & !BV32::from(0xff)) | BV32::from(exception_number as u32);
+        self.psr = (self.psr & !BV32::from(0xff)) | exception_number.to_bv32();
 
         // set link register
         self.lr = if self.mode_is_handler() {
@@ -124,51 +152,129 @@ impl Armv7m {
             BV32::from(0xFFFF_FFF9)
         };
 
+        // a pending CONTROL write takes effect - the lr above still describes
+        // the stack the frame went on
+        self.context_synchronize();
+
         // stack = main
         self.mode = CPUMode::Handler;
         self.control.spsel = false;
+        self.pending_control.spsel = false;
+
+        // ClearExclusiveLocal()
+        self.monitor.exclusive = false;
 
         // TODO: There are other ops here but I don't think they
         // matter
     }
 
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[stack_frame_in_ram(cpu)])]
+    fn stack_frame_in_ram(&self) -> bool {
+        let sp = self.get_value_from_reg(&Reg::sp());
+        self.mem.is_valid_ram_addr(sp) && self.mem.is_valid_ram_addr(self.exception_frame_ptr())
+    }
+
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[mpu_permits_stacking(cpu)])]
+    fn mpu_permits_stacking(&self) -> bool {
+        let frame = self.exception_frame_ptr();
+        let privileged = self.is_privileged();
+        self.mem.mpu_permits(frame, privileged, true)
+            && self
+                .mem
+                .mpu_permits(frame + BV32::from(0x1C), privileged, true)
+    }
+
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[sp_can_handle_exception_entry(cpu)])]
+    fn can_push_stack(&self) -> bool {
+        self.stack_frame_in_ram() && self.mpu_permits_stacking()
+    }
+
+    // Exception entry (see PushStack on p. B1-534 of the manual)
+    //
+    // If the frame can not be written the stack pointer is still moved and the
+    // exception is still taken, but stacking raises a derived exception which
+    // is taken in its place (see p. B1-553 of the manual)
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@cpu], u8[@exception_num])
-            requires sp_can_handle_exception_entry(cpu)
-            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_exception_entry(cpu, exception_num) }
+        fn (self: &strg Armv7m[@cpu], ExceptionNumber[@exception_num])
+            ensures self: Armv7m { new_cpu:
+                (sp_can_handle_exception_entry(cpu) => new_cpu == cpu_post_exception_entry(cpu, exception_num))
+                &&
+                (!sp_can_handle_exception_entry(cpu) => stacking_fault_taken(cpu, exception_num, new_cpu))
+            }
     )]
-    fn exception_entry(&mut self, exception_number: u8) {
-        self.push_stack();
-        self.exception_taken(exception_number);
+    pub(crate) fn exception_entry(&mut self, exception_number: ExceptionNumber) {
+        if self.can_push_stack() {
+            self.push_stack();
+            self.exception_taken(exception_number);
+        } else {
+            // a frame in RAM was rejected by the MPU
+            let mpu_violation = self.stack_frame_in_ram();
+            self.push_stack_update_sp();
+            self.exception_taken(exception_number);
+            // TODO: get rid of this assume - it should hold automagically
+            assume(self.psr & BV32::from(0xff) == exception_number.to_bv32());
+            self.stacking_fault(mpu_violation);
+        }
     }
 
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@cpu], BV32[@return_exec]) -> BV32[get_sp_from_isr_ret(cpu.sp, return_exec)]
-            requires is_valid_ram_addr(get_sp_from_isr_ret(cpu.sp, return_exec) + 0x20)
+            requires
+                exc_return_valid(return_exec)
+                &&
+                sp_can_handle_exception_exit(get_sp_from_isr_ret(cpu.sp, return_exec), cpu.mem)
             ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
-                    mode: thread_mode(),
-                    control: Control {
-                        spsel: return_exec != 0xFFFF_FFF9,
-                        ..cpu.control
-                    },
-                    sp: sp_post_exception_exit(cpu.sp, return_exec),
+                    mode: if exc_return_to_handler(return_exec) { handler_mode() } else { thread_mode() },
+                    control: control_post_exception_exit(cpu, return_exec),
+                    pending_control: control_post_exception_exit(cpu, return_exec),
+                    sp: sp_post_exception_exit(cpu, return_exec),
+                    monitor: clear_exclusive_local(cpu),
                     ..cpu
                 }
             }
     )]
     fn exception_exit_get_fp_update_sp(&mut self, return_exec: BV32) -> BV32 {
-        if return_exec == BV32::from(0xFFFF_FFF9) {
-            self.control.spsel = false;
-            self.mode = CPUMode::Thread;
-            let fp = self.sp.sp_main;
-            self.sp.sp_main = fp + BV32::from(0x20);
-            fp
-        } else {
+        // ClearExclusiveLocal()
+        self.monitor.exclusive = false;
+        // a pending CONTROL write takes effect
+        self.context_synchronize();
+        if return_exec == BV32::from(0xFFFF_FFFD) {
+            // Thread mode, process stack
             self.control.spsel = true;
+            self.pending_control.spsel = true;
             self.mode = CPUMode::Thread;
             let fp = self.sp.sp_process;
-            self.sp.sp_process = fp + BV32::from(0x20);
+            self.sp.sp_process = self.sp_post_frame(fp);
             fp
+        } else {
+            // Handler mode (0xFFFF_FFF1) or Thread mode (0xFFFF_FFF9), both
+            // on the main stack
+            self.control.spsel = false;
+            self.pending_control.spsel = false;
+            self.mode = if return_exec == BV32::from(0xFFFF_FFF1) {
+                CPUMode::Handler
+            } else {
+                CPUMode::Thread
+            };
+            let fp = self.sp.sp_main;
+            self.sp.sp_main = self.sp_post_frame(fp);
+            fp
+        }
+    }
+
+    // Undoes the realignment bit 9 of the stacked psr records (see PopStack in
+    // the manual). The psr is looked up untraced - unstacking reads it once.
+    #[flux_rs::sig(
+        fn (&Armv7m[@cpu], BV32[@fp]) -> BV32[if frame_was_realigned(fp, cpu.mem) { (fp + 0x20) | 0x4 } else { fp + 0x20 }]
+            requires sp_can_handle_exception_exit(fp, cpu.mem)
+    )]
+    fn sp_post_frame(&self, fp: BV32) -> BV32 {
+        let sp = fp + BV32::from(0x20);
+        let stacked_psr = self.mem.peek_word(fp + BV32::from(0x1C));
+        if self.mem.stack_align_enabled() && stacked_psr & BV32::from(0x200) != BV32::from(0) {
+            sp | BV32::from(0x4)
+        } else {
+            sp
         }
     }
 
@@ -183,22 +289,24 @@ impl Armv7m {
             BV32[get_mem_addr(fp + 0xC, cpu.mem)],
             BV32[get_mem_addr(fp + 0x10, cpu.mem)],
             BV32[get_mem_addr(fp + 0x14, cpu.mem)],
+            BV32[get_mem_addr(fp + 0x18, cpu.mem)],
             BV32[get_mem_addr(fp + 0x1C, cpu.mem)],
         )
-        requires sp_can_handle_exception_exit(fp)
+        requires sp_can_handle_exception_exit(fp, cpu.mem)
     )]
     fn exception_exit_read_regs(
         &self,
         frame_ptr: BV32,
-    ) -> (BV32, BV32, BV32, BV32, BV32, BV32, BV32) {
-        let r0 = self.mem.read(frame_ptr);
-        let r1 = self.mem.read(frame_ptr + BV32::from(0x4));
-        let r2 = self.mem.read(frame_ptr + BV32::from(0x8));
-        let r3 = self.mem.read(frame_ptr + BV32::from(0xC));
-        let r12 = self.mem.read(frame_ptr + BV32::from(0x10));
-        let lr = self.mem.read(frame_ptr + BV32::from(0x14));
-        let psr = self.mem.read(frame_ptr + BV32::from(0x1C));
-        (r0, r1, r2, r3, r12, lr, psr)
+    ) -> (BV32, BV32, BV32, BV32, BV32, BV32, BV32, BV32) {
+        let r0 = self.mem.peek(frame_ptr);
+        let r1 = self.mem.peek(frame_ptr + BV32::from(0x4));
+        let r2 = self.mem.peek(frame_ptr + BV32::from(0x8));
+        let r3 = self.mem.peek(frame_ptr + BV32::from(0xC));
+        let r12 = self.mem.peek(frame_ptr + BV32::from(0x10));
+        let lr = self.mem.peek(frame_ptr + BV32::from(0x14));
+        let ret_addr = self.mem.peek(frame_ptr + BV32::from(0x18));
+        let psr = self.mem.peek(frame_ptr + BV32::from(0x1C));
+        (r0, r1, r2, r3, r12, lr, ret_addr, psr)
     }
 
     #[flux_rs::sig(
@@ -210,11 +318,13 @@ impl Armv7m {
             BV32[@r3],
             BV32[@r12],
             BV32[@lr],
+            BV32[@ret_addr],
             BV32[@psr],
         )
         ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
                 general_regs: gprs_post_exception_exit_write_regs(cpu, r0, r1, r2, r3, r12),
                 lr: lr,
+                pc: ret_addr,
                 psr: psr,
                 ..cpu
             }
@@ -228,6 +338,7 @@ impl Armv7m {
         r3: BV32,
         r12: BV32,
         lr: BV32,
+        ret_addr: BV32,
         psr: BV32,
     ) {
         self.update_general_reg_with_b32(GPR::r0(), r0);
@@ -235,40 +346,97 @@ impl Armv7m {
         self.update_general_reg_with_b32(GPR::r2(), r2);
         self.update_general_reg_with_b32(GPR::r3(), r3);
         self.update_general_reg_with_b32(GPR::r12(), r12);
-        self.update_special_reg_with_b32(SpecialRegister::lr(), lr);
+        self.update_reg_with_b32(Reg::lr(), lr);
+        // the stacked return address is branched to as is (see PopStack in
+        // the manual)
+        self.pc = ret_addr;
         self.update_special_reg_with_b32(SpecialRegister::psr(), psr);
     }
 
+    #[flux_rs::sig(fn (BV32[@return_exec]) -> bool[exc_return_valid(return_exec)])]
+    fn exc_return_valid(return_exec: BV32) -> bool {
+        return_exec == BV32::from(0xFFFF_FFF1)
+            || return_exec == BV32::from(0xFFFF_FFF9)
+            || return_exec == BV32::from(0xFFFF_FFFD)
+    }
+
+    // A reserved EXC_RETURN deactivates the returning exception and takes a
+    // UsageFault (INVPC) in its place. Nothing is stacked - the frame of the
+    // exception that tried to return is still on the stack.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu], BV32[@return_exec])
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_invalid_exception_return(cpu, return_exec) }
+    )]
+    fn invalid_exception_return(&mut self, return_exec: BV32) {
+        let fault = self.invalid_exc_return_fault();
+        self.exception_taken(fault);
+        // LR = 0xF0000000 + EXC_RETURN
+        self.lr = return_exec;
+    }
+
+    // Exception return (see ExceptionReturn in the manual)
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@cpu], BV32[@return_exec])
-            requires sp_can_handle_exception_exit(get_sp_from_isr_ret(cpu.sp, return_exec))
-            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_exception_exit(cpu, return_exec) }
+            requires
+                exc_return_valid(return_exec)
+                =>
+                sp_can_handle_exception_exit(get_sp_from_isr_ret(cpu.sp, return_exec), cpu.mem)
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_exception_return(cpu, return_exec) }
     )]
-    fn exception_exit(&mut self, return_exec: BV32) {
+    pub(super) fn exception_exit(&mut self, return_exec: BV32) {
+        if !Self::exc_return_valid(return_exec) {
+            self.invalid_exception_return(return_exec);
+            return;
+        }
         let frame_ptr = self.exception_exit_get_fp_update_sp(return_exec);
-        let (r0, r1, r2, r3, r12, lr, psr) = self.exception_exit_read_regs(frame_ptr);
-        self.exception_exit_write_regs(r0, r1, r2, r3, r12, lr, psr);
+        let (r0, r1, r2, r3, r12, lr, ret_addr, psr) = self.exception_exit_read_regs(frame_ptr);
+        // bit 9 is reserved - it only told PopStack about the realignment
+        let psr = (psr & !BV32::from(0x200)) | (self.psr & BV32::from(0x200));
+        self.exception_exit_write_regs(r0, r1, r2, r3, r12, lr, ret_addr, psr);
+        // an exception return is an event (see WFE wake-up events in the
+        // manual)
+        self.event = true;
+        self.enter_sleep_on_exit(return_exec);
     }
 
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@cpu], u8[@exception_num]) -> BV32[get_bx_from_exception_num(exception_num, cpu.lr)]
-            requires mode_is_handler(cpu.mode) && get_special_reg(ipsr(), cpu) == bv32(exception_num)
-            // (exception_num == 11 || exception_num == 15 || (exception_num >= 16 => get_special_reg(ipsr(), cpu) >= 16))
+        fn (self: &strg Armv7m[@cpu], ExceptionNumber[@exception_num]) -> BV32[get_bx_from_exception_num(exception_num, cpu.lr)]
+            requires
+                exception_has_isr(exception_num)
+                &&
+                // an IRQ the chip does not implement has no handler to run
+                exception_implemented(exception_num, cpu.mem)
+                &&
+                kernel_statics_in_ram(cpu.mem)
+                &&
+                mode_is_handler(cpu.mode)
+                &&
+                get_special_reg(ipsr(), cpu) == bv32(exception_num)
+                &&
+                isr_returns(exception_num, cpu.lr)
             ensures self: Armv7m { new_cpu: new_cpu == cpu_post_run_isr(cpu, exception_num)  }
     )]
-    fn run_isr(&mut self, exception_number: u8) -> BV32 {
+    fn run_isr(&mut self, exception_number: ExceptionNumber) -> BV32 {
         match exception_number {
-            11 => self.svc_isr(),
-            15 => self.sys_tick_isr(),
-            16..=255 => self.generic_isr(),
+            ExceptionNumber::HardFault => self.hard_fault_isr(),
+            ExceptionNumber::SVCall => self.svc_isr(),
+            ExceptionNumber::SysTick => self.sys_tick_isr(),
+            ExceptionNumber::External(_) => self.generic_isr(),
             _ => panic!("Unhandled"),
         }
     }
 
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@cpu], u8[@exception_num])
+        fn (self: &strg Armv7m[@cpu], ExceptionNumber[@exception_num])
             requires
-                (exception_num == 11 || exception_num >= 15)
+                exception_has_isr(exception_num)
+                &&
+                exception_implemented(exception_num, cpu.mem)
+                &&
+                kernel_statics_in_ram(cpu.mem)
+                &&
+                // a HardFault is only preempting a process
+                isr_returns(exception_num, lr_post_exception_entry(cpu, cpu.control))
                 &&
                 // Stack Pointer is valid and can grow downwards 20 bytes
                 sp_can_handle_exception_entry(cpu)
@@ -277,11 +445,11 @@ impl Armv7m {
                 sp_can_handle_preempt_exception_exit(cpu, exception_num)
             ensures self: Armv7m { new_cpu: new_cpu == cpu_post_preempt(cpu, exception_num) }
     )]
-    pub fn preempt(&mut self, exception_number: u8) {
+    pub fn preempt(&mut self, exception_number: ExceptionNumber) {
         // stack
         self.exception_entry(exception_number);
         // TODO: get rid of this assume - it should hold automagically
-        assume(self.psr & BV32::from(0xff) == BV32::from(exception_number as u32));
+        assume(self.psr & BV32::from(0xff) == exception_number.to_bv32());
         // call isr
         let ret_value = self.run_isr(exception_number);
         // unstack
@@ -0,0 +1,309 @@
+use crate::armv7m::lang::{ExceptionNumber, SpecialRegister};
+use crate::armv7m::mem::sys_control::{
+    BFAR_ADDR, CFSR_ADDR, CFSR_BFARVALID, CFSR_DACCVIOL, CFSR_IACCVIOL, CFSR_INVPC, CFSR_MMARVALID,
+    CFSR_MSTKERR, CFSR_PRECISERR, CFSR_STKERR, CFSR_UNALIGNED, HFSR_ADDR, HFSR_FORCED, MMFAR_ADDR,
+    SHCSR_ADDR, SHCSR_BUSFAULTENA_BIT, SHCSR_MEMFAULTENA_BIT, SHCSR_USGFAULTENA_BIT,
+};
+use crate::armv7m::mem::MemFault;
+
+use super::Armv7m;
+
+use flux_rs::bitvec::BV32;
+
+// Fault escalation and lockup (see p. B1-548 & p. B1-549 of the manual)
+//
+// We do not model exception priorities yet so the rules below only use what
+// the architecture fixes:
+//
+// - NMI runs at priority -2, HardFault at -1 and FAULTMASK boosts the current
+//   execution priority to -1. A synchronous fault raised at a priority of -1
+//   or lower cannot be handled and the processor enters lockup.
+// - A configurable fault (MemManage, BusFault, UsageFault) escalates to
+//   HardFault when it is disabled in SHCSR or when it is raised from inside
+//   its own handler.
+// - A fault while stacking on exception entry is a derived exception. The
+//   exception being entered has already been taken when the fault is raised
+//   so the rules above apply at the priority of that exception.
+flux_rs::defs! {
+    fn execution_priority_is_negative(cpu: Armv7m) -> bool {
+        (
+            mode_is_handler(cpu.mode)
+            &&
+            (get_special_reg(ipsr(), cpu) == 2 || get_special_reg(ipsr(), cpu) == 3)
+        )
+        ||
+        nth_bit_is_set(cpu.faultmask, 0)
+    }
+
+    fn fault_enable_bit(fault: int) -> BV32 {
+        if fault == 4 {
+            SHCSR_MEMFAULTENA_BIT
+        } else if fault == 5 {
+            SHCSR_BUSFAULTENA_BIT
+        } else {
+            SHCSR_USGFAULTENA_BIT
+        }
+    }
+
+    fn configurable_fault_enabled(fault: int, mem: Memory) -> bool {
+        nth_bit_is_set(get_mem_addr(SHCSR_ADDR, mem), fault_enable_bit(fault))
+    }
+
+    fn fault_escalates(cpu: Armv7m, fault: int) -> bool {
+        // only MemManage, BusFault & UsageFault are configurable
+        (fault == 4 || fault == 5 || fault == 6)
+        &&
+        (
+            !configurable_fault_enabled(fault, cpu.mem)
+            ||
+            (mode_is_handler(cpu.mode) && get_special_reg(ipsr(), cpu) == bv32(fault))
+        )
+    }
+
+    fn escalated_fault(cpu: Armv7m, fault: int) -> int {
+        if fault_escalates(cpu, fault) { 3 } else { fault }
+    }
+
+    fn cpu_post_escalate_fault(cpu: Armv7m, fault: int) -> Armv7m {
+        if fault_escalates(cpu, fault) {
+            Armv7m {
+                mem: update_mem(HFSR_ADDR, cpu.mem, get_mem_addr(HFSR_ADDR, cpu.mem) | HFSR_FORCED),
+                ..cpu
+            }
+        } else {
+            cpu
+        }
+    }
+
+    fn fault_taken(cpu: Armv7m, fault: int, new_cpu: Armv7m) -> bool {
+        (execution_priority_is_negative(cpu) => new_cpu == cpu_post_lockup(cpu))
+        &&
+        (
+            (!execution_priority_is_negative(cpu) && sp_can_handle_exception_entry(cpu))
+            =>
+            new_cpu == cpu_post_exception_entry(cpu_post_escalate_fault(cpu, fault), escalated_fault(cpu, fault))
+        )
+        &&
+        (
+            (!execution_priority_is_negative(cpu) && !sp_can_handle_exception_entry(cpu))
+            =>
+            stacking_fault_taken(cpu_post_escalate_fault(cpu, fault), escalated_fault(cpu, fault), new_cpu)
+        )
+    }
+
+    // the returning exception is deactivated before the UsageFault is raised
+    // so it only escalates when it is disabled
+    fn invalid_exc_return_fault(mem: Memory) -> int {
+        if configurable_fault_enabled(6, mem) { 6 } else { 3 }
+    }
+
+    fn mem_post_invalid_exc_return(mem: Memory) -> Memory {
+        let mem_ = update_mem(CFSR_ADDR, mem, get_mem_addr(CFSR_ADDR, mem) | CFSR_INVPC);
+        if configurable_fault_enabled(6, mem) {
+            mem_
+        } else {
+            update_mem(HFSR_ADDR, mem_, get_mem_addr(HFSR_ADDR, mem_) | HFSR_FORCED)
+        }
+    }
+
+    fn cpu_post_lockup(cpu: Armv7m) -> Armv7m {
+        Armv7m { lockup: true, pc: 0xFFFF_FFFE, ..cpu }
+    }
+
+    fn stacking_fault_taken(cpu: Armv7m, exception_num: int, new_cpu: Armv7m) -> bool {
+        // the stack pointer is moved even though the frame was not written
+        new_cpu.sp == sp_post_exception_entry(cpu)
+        &&
+        mode_is_handler(new_cpu.mode)
+        &&
+        if exception_num == 2 || exception_num == 3 || nth_bit_is_set(cpu.faultmask, 0) {
+            new_cpu.lockup
+        } else {
+            new_cpu.lockup == cpu.lockup
+        }
+    }
+}
+
+impl Armv7m {
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[cpu.lockup])]
+    pub fn is_locked_up(&self) -> bool {
+        self.lockup
+    }
+
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[execution_priority_is_negative(cpu)])]
+    pub(super) fn execution_priority_is_negative(&self) -> bool {
+        let ipsr = self.get_value_from_special_reg(&SpecialRegister::IPSR);
+        let in_nmi_or_hard_fault =
+            self.mode_is_handler() && (ipsr == BV32::from(2) || ipsr == BV32::from(3));
+        in_nmi_or_hard_fault || Self::nth_bit_set(self.faultmask, BV32::from(0))
+    }
+
+    // Returns true when a configurable fault has to be taken as a HardFault
+    #[flux_rs::sig(fn (&Armv7m[@cpu], ExceptionNumber[@fault]) -> bool[fault_escalates(cpu, fault)])]
+    fn fault_escalates(&self, fault: ExceptionNumber) -> bool {
+        let shcsr_addr = SHCSR_ADDR;
+        let shcsr = self.mem.peek(shcsr_addr);
+        let enable_bit = match fault {
+            ExceptionNumber::MemManage => SHCSR_MEMFAULTENA_BIT,
+            ExceptionNumber::BusFault => SHCSR_BUSFAULTENA_BIT,
+            ExceptionNumber::UsageFault => SHCSR_USGFAULTENA_BIT,
+            // HardFault (and anything else) is never escalated
+            _ => return false,
+        };
+        let enabled = Self::nth_bit_set(shcsr, enable_bit);
+        let ipsr = self.get_value_from_special_reg(&SpecialRegister::IPSR);
+        let in_own_handler = self.mode_is_handler() && ipsr == fault.to_bv32();
+        !enabled || in_own_handler
+    }
+
+    // Returns the exception a fault is taken as, recording the escalation in
+    // HFSR when a configurable fault becomes a HardFault
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu], ExceptionNumber[@fault]) -> ExceptionNumber[escalated_fault(cpu, fault)]
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_escalate_fault(cpu, fault) }
+    )]
+    fn escalate_fault(&mut self, fault: ExceptionNumber) -> ExceptionNumber {
+        if self.fault_escalates(fault) {
+            let hfsr_addr = HFSR_ADDR;
+            let hfsr = self.mem.read(hfsr_addr);
+            self.mem.write(hfsr_addr, hfsr | HFSR_FORCED);
+            ExceptionNumber::HardFault
+        } else {
+            fault
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_lockup(cpu) }
+    )]
+    fn enter_lockup(&mut self) {
+        // Lockup: the processor stops executing and fetches from 0xFFFFFFFE
+        // until it is reset or an NMI arrives
+        self.lockup = true;
+        self.pc = BV32::from(0xFFFF_FFFE);
+    }
+
+    // Raises a synchronous fault. This either locks the core up, or takes the
+    // fault (or the HardFault it escalates to) as an exception.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu], ExceptionNumber[@fault])
+            ensures self: Armv7m { new_cpu: fault_taken(cpu, fault, new_cpu) }
+    )]
+    pub fn take_fault(&mut self, fault: ExceptionNumber) {
+        if self.execution_priority_is_negative() {
+            self.enter_lockup();
+            return;
+        }
+        let exception = self.escalate_fault(fault);
+        self.exception_entry(exception);
+    }
+
+    // Raised by an exception return with a reserved EXC_RETURN value. Sets
+    // UFSR.INVPC and returns the exception to take - the UsageFault or, when
+    // it is disabled, the HardFault it escalates to.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu]) -> ExceptionNumber[invalid_exc_return_fault(cpu.mem)]
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m { mem: mem_post_invalid_exc_return(cpu.mem), ..cpu } }
+    )]
+    pub(super) fn invalid_exc_return_fault(&mut self) -> ExceptionNumber {
+        let cfsr_addr = CFSR_ADDR;
+        let cfsr = self.mem.read(cfsr_addr);
+        self.mem.write(cfsr_addr, cfsr | CFSR_INVPC);
+        let shcsr_addr = SHCSR_ADDR;
+        let shcsr = self.mem.peek(shcsr_addr);
+        if Self::nth_bit_set(shcsr, SHCSR_USGFAULTENA_BIT) {
+            ExceptionNumber::UsageFault
+        } else {
+            let hfsr_addr = HFSR_ADDR;
+            let hfsr = self.mem.read(hfsr_addr);
+            self.mem.write(hfsr_addr, hfsr | HFSR_FORCED);
+            ExceptionNumber::HardFault
+        }
+    }
+
+    // Raised when the exception frame could not be written on exception entry.
+    // The exception being entered has already been taken (so we are in
+    // handler mode and the stack pointer has moved) and the derived fault
+    // replaces it by late arrival - the EXC_RETURN value in lr still describes
+    // the interrupted context.
+    //
+    // A frame rejected by the MPU is a MemManage fault (MMFSR.MSTKERR) and a
+    // frame that is not in RAM is a BusFault (BFSR.STKERR).
+    //
+    // NOTE: the exception being entered should stay pending but we do not
+    // track pending system exceptions.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu])
+            requires mode_is_handler(cpu.mode)
+            ensures self: Armv7m { new_cpu:
+                new_cpu.sp == cpu.sp
+                &&
+                mode_is_handler(new_cpu.mode)
+                &&
+                (execution_priority_is_negative(cpu) => new_cpu.lockup)
+                &&
+                (!execution_priority_is_negative(cpu) => new_cpu.lockup == cpu.lockup)
+            }
+    )]
+    pub(crate) fn stacking_fault(&mut self, mpu_violation: bool) {
+        let cfsr_addr = CFSR_ADDR;
+        let cfsr = self.mem.read(cfsr_addr);
+        let (status, fault) = if mpu_violation {
+            (CFSR_MSTKERR, ExceptionNumber::MemManage)
+        } else {
+            (CFSR_STKERR, ExceptionNumber::BusFault)
+        };
+        self.mem.write(cfsr_addr, cfsr | status);
+        if self.execution_priority_is_negative() {
+            self.enter_lockup();
+            return;
+        }
+        let exception = self.escalate_fault(fault);
+        self.psr = (self.psr & !BV32::from(0xff)) | exception.to_bv32();
+    }
+
+    // Raises the fault for a memory access that failed. The fault status
+    // (and address) registers are written before the fault is taken.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu], MemFault)
+            ensures self: Armv7m { new_cpu:
+                (execution_priority_is_negative(cpu) => new_cpu.lockup)
+                &&
+                (
+                    (!execution_priority_is_negative(cpu) && sp_can_handle_exception_entry(cpu))
+                    =>
+                    (mode_is_handler(new_cpu.mode) && new_cpu.lockup == cpu.lockup)
+                )
+            }
+    )]
+    pub fn take_mem_fault(&mut self, fault: MemFault) {
+        let cfsr_addr = CFSR_ADDR;
+        let cfsr = self.mem.read(cfsr_addr);
+        match fault {
+            MemFault::Unaligned => {
+                self.mem.write(cfsr_addr, cfsr | CFSR_UNALIGNED);
+                self.take_fault(ExceptionNumber::UsageFault);
+            }
+            MemFault::MpuViolation(address) => {
+                let mmfar_addr = MMFAR_ADDR;
+                self.mem.write(mmfar_addr, address);
+                self.mem
+                    .write(cfsr_addr, cfsr | CFSR_DACCVIOL | CFSR_MMARVALID);
+                self.take_fault(ExceptionNumber::MemManage);
+            }
+            MemFault::PpbPrivilege(address) => {
+                let bfar_addr = BFAR_ADDR;
+                self.mem.write(bfar_addr, address);
+                self.mem
+                    .write(cfsr_addr, cfsr | CFSR_PRECISERR | CFSR_BFARVALID);
+                self.take_fault(ExceptionNumber::BusFault);
+            }
+            MemFault::MpuExecute(_) => {
+                // the pc is the faulting address so MMFAR is not written
+                self.mem.write(cfsr_addr, cfsr | CFSR_IACCVIOL);
+                self.take_fault(ExceptionNumber::MemManage);
+            }
+        }
+    }
+}
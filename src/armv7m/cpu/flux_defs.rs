@@ -1,5 +1,5 @@
 use super::Memory;
-use super::{Armv7m, CPUMode, Control, SP};
+use super::{Armv7m, CPUMode, Control, LocalMonitor, SP};
 use crate::armv7m::lang::{SpecialRegister, GPR};
 use crate::flux_support::rmap::*;
 use flux_rs::bitvec::BV32;
@@ -13,14 +13,52 @@ flux_rs::defs! {
         Armv7m {
             mode: handler_mode(),
             control: control_post_exception_entry(cpu),
+            pending_control: control_post_exception_entry(cpu),
             psr: psr_post_exception_entry(cpu, exception_num),
             lr: lr_post_exception_entry(cpu, cpu.control),
             sp: sp_post_exc_entry,
             mem: mem_post_exception_entry(get_sp(sp_post_exc_entry, cpu.mode, cpu.control), cpu),
+            monitor: clear_exclusive_local(cpu),
             ..cpu
         }
     }
 
+    // ClearExclusiveLocal() - the monitor goes back to Open Access
+    fn clear_exclusive_local(cpu: Armv7m) -> LocalMonitor {
+        LocalMonitor { exclusive: false, ..cpu.monitor }
+    }
+
+    // SetExclusiveMonitors(address, size)
+    fn set_exclusive_monitors(address: BV32) -> LocalMonitor {
+        LocalMonitor { exclusive: true, address: address }
+    }
+
+    // ExclusiveMonitorsPass(address, size)
+    fn exclusive_monitors_pass(address: BV32, cpu: Armv7m) -> bool {
+        cpu.monitor.exclusive && cpu.monitor.address == address
+    }
+
+    // a data access by the current mode gets past the PPB & MPU checks (see
+    // access.rs)
+    fn data_access_ok(address: BV32, write: bool, cpu: Armv7m) -> bool {
+        data_access_permits(address, mode_is_privileged(cpu.mode, cpu.control), write, cpu.mem)
+    }
+
+    fn data_accesses_ok_4(address: BV32, write: bool, cpu: Armv7m) -> bool {
+        data_access_ok(address, write, cpu)
+        &&
+        data_access_ok(address + 0x4, write, cpu)
+        &&
+        data_access_ok(address + 0x8, write, cpu)
+        &&
+        data_access_ok(address + 0xc, write, cpu)
+    }
+
+    // the instruction at the pc can be fetched
+    fn fetch_ok(cpu: Armv7m) -> bool {
+        mpu_permits_execute(cpu.pc, mode_is_privileged(cpu.mode, cpu.control), cpu.mem)
+    }
+
     fn get_lr_direct(cpu: Armv7m) -> BV32 {
         cpu.lr
     }
@@ -33,19 +71,49 @@ flux_rs::defs! {
         cpu.mem
     }
 
+    // ExceptionReturn for a valid EXC_RETURN - a reserved one raises a
+    // UsageFault instead (see cpu_post_exception_return)
     fn cpu_post_exception_exit(cpu: Armv7m, return_exec: BV32) -> Armv7m {
         let sp_from_isr_ret = get_sp_from_isr_ret(cpu.sp, return_exec);
         Armv7m {
-            mode: thread_mode(),
-            control: Control { spsel: return_exec != 0xFFFF_FFF9, ..cpu.control },
+            mode: if exc_return_to_handler(return_exec) { handler_mode() } else { thread_mode() },
+            control: control_post_exception_exit(cpu, return_exec),
+            pending_control: control_post_exception_exit(cpu, return_exec),
             general_regs: gprs_post_exception_exit(sp_from_isr_ret, cpu),
             lr: get_mem_addr(sp_from_isr_ret + 0x14, cpu.mem),
-            psr: get_mem_addr(sp_from_isr_ret + 0x1C, cpu.mem),
-            sp: sp_post_exception_exit(cpu.sp, return_exec),
+            pc: get_mem_addr(sp_from_isr_ret + 0x18, cpu.mem),
+            psr: psr_post_exception_exit(get_mem_addr(sp_from_isr_ret + 0x1C, cpu.mem), cpu),
+            sp: sp_post_exception_exit(cpu, return_exec),
+            monitor: clear_exclusive_local(cpu),
+            event: true,
+            sleep: sleep_post_exception_exit(cpu, return_exec),
             ..cpu
         }
     }
 
+    fn cpu_post_exception_return(cpu: Armv7m, return_exec: BV32) -> Armv7m {
+        if exc_return_valid(return_exec) {
+            cpu_post_exception_exit(cpu, return_exec)
+        } else {
+            cpu_post_invalid_exception_return(cpu, return_exec)
+        }
+    }
+
+    // the returning exception is deactivated and a UsageFault (INVPC) is
+    // taken without stacking - lr keeps the EXC_RETURN value
+    fn cpu_post_invalid_exception_return(cpu: Armv7m, return_exec: BV32) -> Armv7m {
+        let faulted = Armv7m { mem: mem_post_invalid_exc_return(cpu.mem), ..cpu };
+        Armv7m {
+            mode: handler_mode(),
+            control: control_post_exception_entry(faulted),
+            pending_control: control_post_exception_entry(faulted),
+            psr: psr_post_exception_entry(faulted, invalid_exc_return_fault(cpu.mem)),
+            lr: return_exec,
+            monitor: clear_exclusive_local(faulted),
+            ..faulted
+        }
+    }
+
     fn cpu_post_preempt(cpu: Armv7m, exception_num: int) -> Armv7m {
         let cpu_post_exc_entry = cpu_post_exception_entry(cpu, exception_num);
         cpu_post_exception_exit(
@@ -75,21 +143,49 @@ flux_rs::defs! {
         }
     }
 
+    // EXC_RETURN[3:0] picks the mode & stack to return to (see
+    // ExceptionReturn in the manual): 0001 is Handler mode on the main stack,
+    // 1001 Thread mode on the main stack and 1101 Thread mode on the process
+    // stack. Without the FP extension bits 27:4 are all ones and every other
+    // value is reserved.
+    fn exc_return_valid(return_exec: BV32) -> bool {
+        return_exec == 0xFFFF_FFF1 || return_exec == 0xFFFF_FFF9 || return_exec == 0xFFFF_FFFD
+    }
+
+    fn exc_return_to_handler(return_exec: BV32) -> bool {
+        return_exec == 0xFFFF_FFF1
+    }
+
+    fn exc_return_to_process_stack(return_exec: BV32) -> bool {
+        return_exec == 0xFFFF_FFFD
+    }
+
     fn get_sp_from_isr_ret(sp: SP, return_exec: BV32) -> BV32 {
-        if return_exec == 0xFFFF_FFF9 {
-            sp.sp_main
-        } else {
+        if exc_return_to_process_stack(return_exec) {
             sp.sp_process
+        } else {
+            sp.sp_main
         }
     }
 
-    fn sp_post_exception_exit(sp: SP, return_exec: BV32) -> SP {
-        if return_exec == 0xFFFF_FFF9 {
-            SP { sp_main: sp.sp_main + 0x20, ..sp }
+    // the frame was realigned on entry - xPSR bit 9 of the stacked psr
+    fn frame_was_realigned(fp: BV32, mem: Memory) -> bool {
+        ccr_stkalign(mem) && (get_mem_addr(fp + 0x1C, mem) & 0x200) != 0
+    }
+
+    fn sp_post_exception_exit(cpu: Armv7m, return_exec: BV32) -> SP {
+        let fp = get_sp_from_isr_ret(cpu.sp, return_exec);
+        let sp = if frame_was_realigned(fp, cpu.mem) { (fp + 0x20) | 0x4 } else { fp + 0x20 };
+        if exc_return_to_process_stack(return_exec) {
+            SP { sp_process: sp, ..cpu.sp }
         } else {
-            SP { sp_process: sp.sp_process + 0x20, ..sp }
+            SP { sp_main: sp, ..cpu.sp }
         }
+    }
 
+    // bit 9 is reserved and is not restored from the frame
+    fn psr_post_exception_exit(stacked_psr: BV32, cpu: Armv7m) -> BV32 {
+        (stacked_psr & bv_not(0x200)) | (cpu.psr & 0x200)
     }
 
     fn gprs_post_exception_exit(sp: BV32, cpu: Armv7m) -> Map<GPR, BV32> {
@@ -207,13 +303,13 @@ flux_rs::defs! {
                         get_gpr(r12(), cpu)
                     ),
                     sp + 0x14,
-                    get_special_reg(lr(), cpu)
+                    get_reg(lr(), cpu)
                 ),
                 sp + 0x18,
-                0 // nonsense value
+                cpu.pc
             ),
             sp + 0x1c,
-            get_special_reg(psr(), cpu)
+            stacked_psr(cpu)
         )
     }
 
@@ -226,6 +322,7 @@ flux_rs::defs! {
         r3: BV32,
         r12: BV32,
         lr: BV32,
+        ret_addr: BV32,
         psr: BV32
     ) -> Map<BV32, BV32> {
         map_set(
@@ -256,7 +353,7 @@ flux_rs::defs! {
                     lr
                 ),
                 sp + 0x18,
-                0 // nonsense value
+                ret_addr
             ),
             sp + 0x1c,
             psr
@@ -275,7 +372,7 @@ flux_rs::defs! {
         rm7: int,
         rm8: int
     ) -> Map<GPR, BV32> {
-        let gpr = get_gpr(rd, cpu);
+        let gpr = get_reg(rd, cpu);
         map_set(
             map_set(
                 map_set(
@@ -311,26 +408,26 @@ flux_rs::defs! {
         )
     }
 
-    fn gprs_post_ldmia_w_special(
+    fn gprs_post_ldmia_w_wback(
         cpu: Armv7m,
         rd: int,
         rm1: int,
         rm2: int,
         rm3: int,
     ) -> Map<GPR, BV32> {
-        let special_reg = get_special_reg(rd, cpu);
+        let base = get_reg(rd, cpu);
         map_set(
             map_set(
                 map_set(
                     cpu.general_regs,
                     rm1,
-                    get_mem_addr(special_reg, cpu.mem)
+                    get_mem_addr(base, cpu.mem)
                 ),
                 rm2,
-                get_mem_addr(special_reg + 0x4, cpu.mem)
+                get_mem_addr(base + 0x4, cpu.mem)
             ),
             rm3,
-            get_mem_addr(special_reg + 0x8, cpu.mem)
+            get_mem_addr(base + 0x8, cpu.mem)
         )
     }
 
@@ -355,28 +452,28 @@ flux_rs::defs! {
                                 map_set(
                                     map_set(
                                         cpu.mem,
-                                        get_gpr(rd, cpu),
+                                        get_reg(rd, cpu),
                                         get_gpr(rm1, cpu),
                                     ),
-                                    get_gpr(rd, cpu) + 0x4,
+                                    get_reg(rd, cpu) + 0x4,
                                     get_gpr(rm2, cpu)
                                 ),
-                                get_gpr(rd, cpu) + 0x8,
+                                get_reg(rd, cpu) + 0x8,
                                 get_gpr(rm3, cpu)
                             ),
-                            get_gpr(rd, cpu) + 0xc,
+                            get_reg(rd, cpu) + 0xc,
                             get_gpr(rm4, cpu)
                         ),
-                        get_gpr(rd, cpu) + 0x10,
+                        get_reg(rd, cpu) + 0x10,
                         get_gpr(rm5, cpu)
                     ),
-                    get_gpr(rd, cpu) + 0x14,
+                    get_reg(rd, cpu) + 0x14,
                     get_gpr(rm6, cpu)
                 ),
-                get_gpr(rd, cpu) + 0x18,
+                get_reg(rd, cpu) + 0x18,
                 get_gpr(rm7, cpu)
             ),
-            get_gpr(rd, cpu) + 0x1c,
+            get_reg(rd, cpu) + 0x1c,
             get_gpr(rm8, cpu)
         )
     }
@@ -384,21 +481,28 @@ flux_rs::defs! {
     fn switch_to_user_pt1_save_clobbers_precondition(cpu: Armv7m) -> bool {
         mode_is_thread_privileged(cpu.mode, cpu.control)
         &&
-        is_valid_ram_addr(sp_main(cpu.sp))
+        mpu_permits_privileged(cpu.mem)
+        &&
+        is_valid_ram_addr(sp_main(cpu.sp), cpu.mem)
         &&
-        is_valid_ram_addr(sp_main(cpu.sp) - 0x3c)
+        is_valid_ram_addr(sp_main(cpu.sp) - 0x3c, cpu.mem)
     }
 
     fn switch_to_user_pt1_reg_restores_precondition(cpu: Armv7m) -> bool {
         let gpr0 = get_gpr(r0(), cpu);
         let gpr1 = get_gpr(r1(), cpu);
-        is_valid_ram_addr(gpr0)
+        // the msr to PSP is ignored when unprivileged
+        mode_is_privileged(cpu.mode, cpu.control)
+        &&
+        mpu_permits_privileged(cpu.mem)
         &&
-        is_valid_ram_addr(gpr0 + 0x20)
+        is_valid_ram_addr(gpr0, cpu.mem)
         &&
-        is_valid_ram_addr(gpr1)
+        is_valid_ram_addr(gpr0 + 0x20, cpu.mem)
         &&
-        is_valid_ram_addr(gpr1 + 0x1c)
+        is_valid_ram_addr(gpr1, cpu.mem)
+        &&
+        is_valid_ram_addr(gpr1 + 0x1c, cpu.mem)
     }
 
     fn switch_to_user_pt1_precondition(cpu: Armv7m) -> bool {
@@ -411,12 +515,18 @@ flux_rs::defs! {
         sp_can_handle_exception_entry(restores)
         &&
         sp_can_handle_preempt_exception_exit(restores, 11)
+        &&
+        kernel_statics_in_ram(restores.mem)
+        &&
+        // switch_to_user ends with svc 255
+        is_svc_instr(restores.pc, 0xff, restores.mem)
     }
 
     fn cpu_post_switch_to_user_pt1_reg_restores(cpu: Armv7m) -> Armv7m {
         Armv7m {
             general_regs: gprs_post_switch_to_user_pt1_reg_restores(cpu),
-            sp: SP { sp_process: get_gpr(r0(), cpu), ..cpu.sp },
+            // MSR keeps the stack pointers word aligned
+            sp: SP { sp_process: get_gpr(r0(), cpu) & 0xFFFF_FFFC, ..cpu.sp },
             ..cpu
         }
     }
@@ -430,14 +540,19 @@ flux_rs::defs! {
     }
 
     fn cpu_post_switch_to_user_pt1(cpu: Armv7m) -> Armv7m {
-        cpu_post_preempt(
+        cpu_post_svc(
             cpu_post_switch_to_user_pt1_reg_restores(
                 cpu_post_switch_to_user_pt1_save_clobbers(cpu)
-            ),
-            11
+            )
         )
     }
 
+    fn cpu_post_svc(cpu: Armv7m) -> Armv7m {
+        // the svc instruction is 2 bytes so the stacked return address is the
+        // instruction right after it
+        cpu_post_preempt(Armv7m { pc: cpu.pc + 0x2, ..cpu }, 11)
+    }
+
     fn gprs_post_switch_to_user_pt1_reg_restores(cpu: Armv7m) -> Map<GPR, BV32> {
         gprs_post_ldmia_w(
             Armv7m {
@@ -499,15 +614,26 @@ flux_rs::defs! {
     fn switch_to_user_pt2_save_registers_precondition(cpu: Armv7m) -> bool {
         // need r1 to be valid store
         let gpr = get_gpr(r1(), cpu);
-        is_valid_ram_addr(gpr) && is_valid_ram_addr(gpr + 0x1c)
+        mode_is_privileged(cpu.mode, cpu.control)
+        &&
+        mpu_permits_privileged(cpu.mem)
+        &&
+        is_valid_ram_addr(gpr, cpu.mem)
+        &&
+        is_valid_ram_addr(gpr + 0x1c, cpu.mem)
     }
 
     fn switch_to_user_pt2_restore_clobbers_precondition(cpu: Armv7m) -> bool {
         mode_is_thread_privileged(cpu.mode, cpu.control)
         &&
-        is_valid_ram_addr(sp_main(cpu.sp))
+        mpu_permits_privileged(cpu.mem)
+        &&
+        is_valid_ram_addr(sp_main(cpu.sp), cpu.mem)
         &&
-        is_valid_ram_addr(sp_main(cpu.sp) + 0x20)
+        is_valid_ram_addr(sp_main(cpu.sp) + 0x20, cpu.mem)
+        &&
+        // the pop returns to code the kernel can fetch
+        mpu_permits_execute(get_mem_addr(sp_main(cpu.sp) + 0x1c, cpu.mem) & 0xFFFF_FFFE, true, cpu.mem)
     }
 
     fn switch_to_user_pt2_precondition(cpu: Armv7m) -> bool {
@@ -588,36 +714,80 @@ flux_rs::defs! {
         }
     }
 
+    // exception entry & return are context synchronization events so a
+    // pending CONTROL write takes effect along with them
     fn control_post_exception_entry(cpu: Armv7m) -> Control {
-        Control { spsel: false, ..cpu.control }
+        Control { spsel: false, ..cpu.pending_control }
+    }
+
+    fn control_post_exception_exit(cpu: Armv7m, return_exec: BV32) -> Control {
+        Control { spsel: exc_return_to_process_stack(return_exec), ..cpu.pending_control }
+    }
+
+    fn cpu_post_context_sync(cpu: Armv7m) -> Armv7m {
+        Armv7m { control: cpu.pending_control, ..cpu }
+    }
+
+    // an ISR's msr CONTROL followed by an isb
+    fn control_post_isr(cpu: Armv7m, npriv: bool) -> Control {
+        Control { npriv: npriv, ..cpu.pending_control }
     }
 
     fn psr_post_exception_entry(cpu: Armv7m, exception_num: int) -> BV32 {
         (cpu.psr & bv_not(0xff)) | bv32(exception_num)
     }
 
+    // the frame is 8 byte aligned when CCR.STKALIGN is set (see PushStack)
+    fn exception_frame_ptr(cpu: Armv7m) -> BV32 {
+        let sp = get_sp(cpu.sp, cpu.mode, cpu.control);
+        if ccr_stkalign(cpu.mem) { (sp - 0x20) & bv_not(0x4) } else { sp - 0x20 }
+    }
+
+    fn frame_ptr_align(cpu: Armv7m) -> bool {
+        ccr_stkalign(cpu.mem) && (get_sp(cpu.sp, cpu.mode, cpu.control) & 0x4) != 0
+    }
+
+    // the psr goes on the frame with bit 9 recording the realignment
+    fn stacked_psr(cpu: Armv7m) -> BV32 {
+        if frame_ptr_align(cpu) {
+            cpu.psr | 0x200
+        } else {
+            cpu.psr & bv_not(0x200)
+        }
+    }
+
     fn sp_post_exception_entry(cpu: Armv7m) -> SP {
-        set_sp(
-            cpu.sp,
-            cpu.mode,
-            cpu.control,
-            // (get_sp(cpu.sp, cpu.mode, cpu.control) - 0x20) & bv_not(3)
-            get_sp(cpu.sp, cpu.mode, cpu.control) - 0x20
-        )
+        set_sp(cpu.sp, cpu.mode, cpu.control, exception_frame_ptr(cpu))
     }
 
-    fn push_stack_sp_precondition(sp: BV32) -> bool {
-        is_valid_ram_addr(sp) && is_valid_ram_addr(sp + 0x1C)
+    fn push_stack_sp_precondition(sp: BV32, mem: Memory) -> bool {
+        is_valid_ram_addr(sp, mem) && is_valid_ram_addr(sp + 0x1C, mem)
     }
 
-    fn sp_can_handle_exception_entry(cpu: Armv7m) -> bool {
+    fn stack_frame_in_ram(cpu: Armv7m) -> bool {
         // requires we have enough space to push 8 x 4 byte values into mem
         let sp = get_sp(cpu.sp, cpu.mode, cpu.control);
-        is_valid_ram_addr(sp) && is_valid_ram_addr(sp - 0x20)
+        is_valid_ram_addr(sp, cpu.mem) && is_valid_ram_addr(exception_frame_ptr(cpu), cpu.mem)
     }
 
-    fn sp_can_handle_exception_exit(sp: BV32) -> bool {
-        is_valid_ram_addr(sp) && is_valid_ram_addr(sp + 0x20)
+    fn mpu_permits_stacking(cpu: Armv7m) -> bool {
+        // stacking is checked with the privilege of the interrupted code. The
+        // frame spans at most two 32 byte blocks and the MPU can not tell
+        // addresses within a block apart so checking the first & last word
+        // covers the whole frame
+        let frame = exception_frame_ptr(cpu);
+        let privileged = mode_is_privileged(cpu.mode, cpu.control);
+        mpu_permits(frame, privileged, true, cpu.mem)
+        &&
+        mpu_permits(frame + 0x1C, privileged, true, cpu.mem)
+    }
+
+    fn sp_can_handle_exception_entry(cpu: Armv7m) -> bool {
+        stack_frame_in_ram(cpu) && mpu_permits_stacking(cpu)
+    }
+
+    fn sp_can_handle_exception_exit(sp: BV32, mem: Memory) -> bool {
+        is_valid_ram_addr(sp, mem) && is_valid_ram_addr(sp + 0x20, mem)
     }
 
     fn sp_can_handle_preempt_exception_exit(cpu: Armv7m, exception_num: int) -> bool {
@@ -628,10 +798,15 @@ flux_rs::defs! {
                     exception_num,
                     lr_post_exception_entry(cpu, cpu.control)
                 )
-            )
+            ),
+            cpu.mem
         )
     }
 
+    fn mode_is_privileged(mode: int, control: Control) -> bool {
+        mode_is_handler(mode) || !control.npriv
+    }
+
     fn mode_is_thread_privileged(mode: int, control: Control) -> bool {
         mode == 1 && !control.spsel && !control.npriv
     }
@@ -699,14 +874,10 @@ flux_rs::defs! {
     fn get_special_reg(reg: int, cpu: Armv7m) -> BV32 {
         if is_psp(reg) {
             get_psp(cpu.sp)
-        } else if is_sp(reg) {
-            get_sp(cpu.sp, cpu.mode, cpu.control)
-        } else if is_lr(reg) {
-            cpu.lr
-        } else if is_pc(reg) {
-            cpu.pc
+        } else if is_msp(reg) {
+            sp_main(cpu.sp)
         } else if is_control(reg) {
-            get_control(cpu.control)
+            get_control(cpu.pending_control)
         } else if is_psr(reg) {
             cpu.psr
         } else {
@@ -737,14 +908,10 @@ flux_rs::defs! {
     fn set_spr(reg: int, cpu: Armv7m, val: BV32) -> Armv7m {
         if is_psp(reg) {
             Armv7m { sp: set_psp(cpu.sp, val), ..cpu }
-        } else if is_sp(reg) {
-            Armv7m { sp: set_sp(cpu.sp, cpu.mode, cpu.control, val), ..cpu }
-        } else if is_lr(reg) {
-            Armv7m { lr: val, ..cpu }
-        } else if is_pc(reg) {
-            Armv7m { pc: val, ..cpu }
+        } else if is_msp(reg) {
+            Armv7m { sp: SP { sp_main: val, ..cpu.sp }, ..cpu }
         } else if is_control(reg) {
-            Armv7m { control: set_control(cpu.control, cpu.mode, val), ..cpu }
+            Armv7m { pending_control: set_control(cpu.pending_control, cpu.mode, val), ..cpu }
         } else if is_psr(reg) {
             Armv7m { psr: val, ..cpu }
         } else {
@@ -752,13 +919,39 @@ flux_rs::defs! {
         }
     }
 
+    // R[n] for a data-processing operand - reading the pc gives the address
+    // of the current instruction + 4
+    fn get_reg(reg: int, cpu: Armv7m) -> BV32 {
+        if reg < 13 {
+            get_gpr(reg, cpu)
+        } else if is_sp(reg) {
+            get_sp(cpu.sp, cpu.mode, cpu.control)
+        } else if is_lr(reg) {
+            cpu.lr
+        } else {
+            cpu.pc + 0x4
+        }
+    }
+
+    fn set_reg(reg: int, cpu: Armv7m, val: BV32) -> Armv7m {
+        if reg < 13 {
+            Armv7m { general_regs: set_gpr(reg, cpu, val), ..cpu }
+        } else if is_sp(reg) {
+            Armv7m { sp: set_sp(cpu.sp, cpu.mode, cpu.control, val), ..cpu }
+        } else if is_lr(reg) {
+            Armv7m { lr: val, ..cpu }
+        } else {
+            Armv7m { pc: val & 0xFFFF_FFFE, ..cpu }
+        }
+    }
+
     fn pop_spr_get_mem_addr_and_incr_precondition(cpu: Armv7m) -> bool {
         let sp = get_sp(cpu.sp, cpu.mode, cpu.control);
-        is_valid_ram_addr(sp) && is_valid_ram_addr(sp + 0x4)
+        is_valid_ram_addr(sp, cpu.mem) && is_valid_ram_addr(sp + 0x4, cpu.mem)
     }
 
     fn pop_spr_update_reg_precondition(cpu: Armv7m, reg: int, val: BV32) -> bool {
-        (is_sp(reg) || is_psp(reg)) => is_valid_ram_addr(val)
+        (is_psp(reg) || is_msp(reg)) => is_valid_ram_addr(val, cpu.mem)
     }
 
     fn pop_spr_get_mem_addr_and_incr_ret_val(cpu: Armv7m) -> BV32 {
@@ -766,7 +959,7 @@ flux_rs::defs! {
     }
 
     fn cpu_post_pop_spr_get_mem_addr_and_incr(cpu: Armv7m) -> Armv7m {
-        set_spr(sp(), cpu, get_sp(cpu.sp, cpu.mode, cpu.control) + 0x4)
+        set_reg(sp(), cpu, get_sp(cpu.sp, cpu.mode, cpu.control) + 0x4)
     }
 
     fn cpu_post_pop_spr_update_reg(cpu: Armv7m, reg: int, val: BV32) -> Armv7m {
@@ -794,47 +987,56 @@ flux_rs::defs! {
         r2: int,
         r3: int,
     ) -> Map<BV32, BV32> {
-        let special_reg = get_special_reg(rd, cpu);
+        let base = get_reg(rd, cpu);
         map_set(
             map_set(
                 map_set(
                     cpu.mem,
-                    special_reg - 0xc,
+                    base - 0xc,
                     get_gpr(r1, cpu)
                 ),
-                special_reg - 0x8,
+                base - 0x8,
                 get_gpr(r2, cpu)
             ),
-            special_reg - 0x4,
+            base - 0x4,
             get_gpr(r3, cpu)
         )
     }
 
-    fn cpu_post_pop(cpu: Armv7m, r1: int, r2: int, r3: int, r4: int, r5: int) -> Armv7m {
+    // SP is written back before the last register so a pop into the pc
+    // returns from an exception with the frame at the updated SP
+    fn cpu_post_pop_regs(cpu: Armv7m, r1: int, r2: int, r3: int, r4: int) -> Armv7m {
         let sp_ = get_sp(cpu.sp, cpu.mode, cpu.control);
-        set_spr(
+        set_reg(
             sp(),
-            set_spr(
-                r5,
-                Armv7m {
-                    general_regs: gprs_post_pop(cpu, sp_, r1, r2, r3, r4),
-                    ..cpu
-                },
-                get_mem_addr(
-                     sp_ + 0x10,
-                    cpu.mem)
-            ),
+            Armv7m {
+                general_regs: gprs_post_pop(cpu, sp_, r1, r2, r3, r4),
+                ..cpu
+            },
             sp_ + 0x14
         )
     }
 
+    fn pop_last_val(cpu: Armv7m) -> BV32 {
+        get_mem_addr(get_sp(cpu.sp, cpu.mode, cpu.control) + 0x10, cpu.mem)
+    }
+
+    fn cpu_post_pop(cpu: Armv7m, r1: int, r2: int, r3: int, r4: int, r5: int) -> Armv7m {
+        cpu_post_load_write_reg(r5, cpu_post_pop_regs(cpu, r1, r2, r3, r4), pop_last_val(cpu))
+    }
+
     fn cpu_post_stmdb_wback(cpu: Armv7m, rd: int, r1: int, r2: int, r3: int) -> Armv7m {
             Armv7m {
                 mem: mem_post_stmdb_wback(cpu, rd, r1, r2, r3),
-                ..set_spr(rd, cpu, get_special_reg(rd, cpu) - 0xc)
+                ..set_reg(rd, cpu, get_reg(rd, cpu) - 0xc)
             }
     }
 
+    // the external interrupt generic_isr is handling
+    fn generic_isr_irq(old_cpu: Armv7m) -> BV32 {
+        get_special_reg(ipsr(), old_cpu) - 16
+    }
+
     fn generic_isr_bit_loc(old_cpu: Armv7m) -> BV32 {
         (get_special_reg(ipsr(), old_cpu) - 16) & 31
     }
@@ -878,7 +1080,8 @@ flux_rs::defs! {
                 r3(),
                 0xe000_e200
             ),
-            control: Control { npriv: false, ..old_cpu.control },
+            control: control_post_isr(old_cpu, false),
+            pending_control: control_post_isr(old_cpu, false),
             lr: 0xFFFF_FFF9,
             ..old_cpu
         }
@@ -886,9 +1089,10 @@ flux_rs::defs! {
 
     fn cpu_post_svc_to_kernel_isr(old_cpu: Armv7m) -> Armv7m {
         Armv7m {
-            mem: map_set(old_cpu.mem, 0x8000_0000, 1),
+            mem: map_set(old_cpu.mem, syscall_fired_addr(old_cpu.mem), 1),
             general_regs: map_set(map_set(old_cpu.general_regs, r0(), 0), r1(), 1),
-            control: Control { npriv: false, ..old_cpu.control },
+            control: control_post_isr(old_cpu, false),
+            pending_control: control_post_isr(old_cpu, false),
             lr: 0xFFFF_FFF9,
             ..old_cpu
         }
@@ -897,14 +1101,15 @@ flux_rs::defs! {
     fn cpu_post_svc_to_app_isr(old_cpu: Armv7m) -> Armv7m {
         Armv7m {
             general_regs: map_set(old_cpu.general_regs, r0(), 1),
-            control: Control { npriv: true, ..old_cpu.control },
+            control: control_post_isr(old_cpu, true),
+            pending_control: control_post_isr(old_cpu, true),
             lr: 0xFFFF_FFFD,
             ..old_cpu
         }
     }
 
     fn svc_isr_ret_val(old_cpu: Armv7m) -> BV32 {
-        if get_special_reg(lr(), old_cpu) == 0xFFFF_FFF9 {
+        if get_reg(lr(), old_cpu) == 0xFFFF_FFF9 {
             0xFFFF_FFFD
         } else {
             0xFFFF_FFF9
@@ -912,24 +1117,74 @@ flux_rs::defs! {
     }
 
     fn cpu_post_svc_isr(old_cpu: Armv7m) -> Armv7m {
-        if get_special_reg(lr(), old_cpu) == 0xFFFF_FFF9 {
+        if get_reg(lr(), old_cpu) == 0xFFFF_FFF9 {
             cpu_post_svc_to_app_isr(old_cpu)
         } else {
             cpu_post_svc_to_kernel_isr(old_cpu)
         }
     }
 
+    fn cpu_post_hard_fault_isr(old_cpu: Armv7m) -> Armv7m {
+        Armv7m {
+            mem: map_set(old_cpu.mem, app_hard_fault_addr(old_cpu.mem), 1),
+            general_regs: map_set(map_set(old_cpu.general_regs, r0(), 0), r1(), 1),
+            control: control_post_isr(old_cpu, false),
+            pending_control: control_post_isr(old_cpu, false),
+            lr: 0xFFFF_FFF9,
+            ..old_cpu
+        }
+    }
+
     fn cpu_post_sys_tick_isr(old_cpu: Armv7m) -> Armv7m {
         Armv7m {
             general_regs: map_set(old_cpu.general_regs, r0(), 0),
-            control: Control { npriv: false, ..old_cpu.control },
+            control: control_post_isr(old_cpu, false),
+            pending_control: control_post_isr(old_cpu, false),
             lr: 0xFFFF_FFF9,
             ..old_cpu
         }
     }
 
+    // the ISRs set Tock's SYSCALL_FIRED & APP_HARD_FAULT statics
+    fn syscall_fired_addr(mem: Memory) -> BV32 {
+        kernel_statics_addr(mem)
+    }
+
+    fn app_hard_fault_addr(mem: Memory) -> BV32 {
+        kernel_statics_addr(mem) + 0x4
+    }
+
+    fn kernel_statics_in_ram(mem: Memory) -> bool {
+        is_valid_ram_addr(syscall_fired_addr(mem), mem)
+        &&
+        is_valid_ram_addr(app_hard_fault_addr(mem), mem)
+        &&
+        // and the MPU lets the (privileged) ISRs write them
+        mpu_permits(syscall_fired_addr(mem), true, true, mem)
+        &&
+        mpu_permits(app_hard_fault_addr(mem), true, true, mem)
+    }
+
+    // external interrupts the chip does not implement can not be taken
+    fn exception_implemented(exception_num: int, mem: Memory) -> bool {
+        exception_num < 16 || nvic_irq_implemented(bv32(exception_num - 16), mem)
+    }
+
+    fn exception_has_isr(exception_num: int) -> bool {
+        // HardFault, SVCall, SysTick or an external interrupt
+        exception_num == 3 || exception_num == 11 || exception_num >= 15
+    }
+
+    // Tock's HardFault handler panics on a kernel fault (lr & 4 == 0) so it
+    // only returns to a process
+    fn isr_returns(exception_num: int, lr: BV32) -> bool {
+        exception_num != 3 || (lr & 0x4) != 0
+    }
+
     fn cpu_post_run_isr(cpu: Armv7m, exception_num: int) -> Armv7m {
-        if exception_num == 11 {
+        if exception_num == 3 {
+            cpu_post_hard_fault_isr(cpu)
+        } else if exception_num == 11 {
             cpu_post_svc_isr(cpu)
         } else if exception_num == 15 {
             cpu_post_sys_tick_isr(cpu)
@@ -983,6 +1238,10 @@ flux_rs::defs! {
         reg == 19
     }
 
+    fn is_msp(reg: int) -> bool {
+        reg == 20
+    }
+
     fn r0() -> int {
         0
     }
@@ -1063,6 +1322,10 @@ flux_rs::defs! {
         19
     }
 
+    fn msp() -> int {
+        20
+    }
+
     fn nth_bit_is_set(val: BV32, n: BV32) -> bool {
         // val & (1 << n)
         val & (1 << n) != 0
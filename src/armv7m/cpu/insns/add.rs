@@ -1,13 +1,53 @@
-use crate::armv7m::{
-    cpu::Armv7m,
-    lang::{SpecialRegister, GPR},
-};
+use crate::armv7m::{cpu::Armv7m, lang::Reg};
 use flux_rs::bitvec::BV32;
 
+flux_rs::defs! {
+    // the operands ADD & SUB (immediate) and their SP forms take - the pc
+    // can be neither and only SP can be added to or taken from SP
+    fn sp_imm_operands_ok(rd: int, rn: int) -> bool {
+        !is_pc(rd) && !is_pc(rn) && (is_sp(rd) => is_sp(rn))
+    }
+}
+
 impl Armv7m {
-    pub fn add_imm(&mut self, rd: GPR, rn: SpecialRegister, imm: BV32) {
-        // adds rn and imm and stores the result in rd
+    // ADD (immediate) & ADD (SP plus immediate) (see ADD (immediate) and
+    // ADD (SP plus immediate) in the manual)
+    //
+    // This instruction adds an immediate value to a register value (or the SP value), and writes the result to the
+    // destination register.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  (result, carry, overflow) = AddWithCarry(R[n], imm32, '0');
+    //  R[d] = result;
+    //  if setflags then
+    //      APSR.N = result<31>;
+    //      APSR.Z = IsZeroBit(result);
+    //      APSR.C = carry;
+    //      APSR.V = overflow;
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@rd], Reg[@rn], BV32[@imm])
+            requires sp_imm_operands_ok(rd, rn)
+            ensures self: Armv7m { new_cpu: new_cpu == set_reg(rd, old_cpu, get_reg(rn, old_cpu) + imm) }
+    )]
+    pub fn add_imm(&mut self, rd: Reg, rn: Reg, imm: BV32) {
+        // Corresponds to encoding T3 of ADD (immediate) and T3 of ADD (SP
+        // plus immediate) - add sp, sp, #imm & add rd, sp, #imm are T2 & T1
+        //
+        // Specific encoding ops are:
+        //
+        //  ADD (immediate):
+        //  d = UInt(Rd); n = UInt(Rn); setflags = (S == '1'); imm32 = ThumbExpandImm(i:imm3:imm8);
+        //  if d == 13 || (d == 15 && S == '0') || n == 15 then UNPREDICTABLE;
+        //
+        //  ADD (SP plus immediate):
+        //  d = UInt(Rd); setflags = (S == '1'); imm32 = ThumbExpandImm(i:imm3:imm8);
+        //  if d == 15 && S == '0' then UNPREDICTABLE;
+        //
         // VTOCK TODO: Flag Updates
-        self.update_general_reg_with_b32(rd, self.get_value_from_special_reg(&rn) + imm);
+        let value = self.get_value_from_reg(&rn) + imm;
+        self.update_reg_with_b32(rd, value);
     }
 }
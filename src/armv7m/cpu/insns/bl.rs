@@ -0,0 +1,104 @@
+use crate::armv7m::lang::Reg;
+
+use super::super::Armv7m;
+
+use flux_rs::bitvec::BV32;
+
+flux_rs::defs! {
+    // the pc holds the address of the instruction being executed so the
+    // instruction after a BL is 4 bytes on
+    fn cpu_post_bl(cpu: Armv7m, imm: BV32) -> Armv7m {
+        Armv7m {
+            lr: (cpu.pc + 0x4) | 0x1,
+            pc: (cpu.pc + 0x4 + imm) & 0xFFFF_FFFE,
+            ..cpu
+        }
+    }
+
+    // and 2 bytes on after a BLX (register)
+    fn cpu_post_blx(cpu: Armv7m, rm: int) -> Armv7m {
+        Armv7m {
+            lr: (cpu.pc + 0x2) | 0x1,
+            pc: get_reg(rm, cpu) & 0xFFFF_FFFE,
+            ..cpu
+        }
+    }
+}
+
+impl Armv7m {
+    // BL (see BL in the manual)
+    //
+    // Branch with Link (immediate) calls a subroutine at a PC-relative address.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  next_instr_addr = PC;
+    //  LR = next_instr_addr<31:1> : '1';
+    //  BranchWritePC(PC + imm32);
+    //
+    // NOTE: reading PC gives the address of the BL + 4 while our pc is the
+    // address of the BL itself. imm is the sign extended offset from the
+    // encoding (wrapping makes a negative offset work). Fetching the
+    // instruction at the target faults if the MPU marks it XN.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@imm])
+            ensures self: Armv7m { new_cpu: fetch_ok(cpu_post_bl(old_cpu, imm)) => new_cpu == cpu_post_bl(old_cpu, imm) }
+    )]
+    pub fn bl(&mut self, imm: BV32) {
+        // Corresponds to encoding T1 of BL
+        //
+        // Specific encoding ops are:
+        //
+        //  I1 = NOT(J1 EOR S); I2 = NOT(J2 EOR S); imm32 = SignExtend(S:I1:I2:imm10:imm11:'0', 32);
+        //  if InITBlock() && !LastInITBlock() then UNPREDICTABLE;
+        let call_site = self.pc;
+        let next_instr_addr = call_site + BV32::from(0x4);
+        let target = (next_instr_addr + imm) & BV32::from(0xFFFF_FFFE);
+        self.lr = next_instr_addr | BV32::from(0x1);
+        self.pc = target;
+        self.push_call(call_site, next_instr_addr, target);
+        self.check_instruction_fetch();
+    }
+
+    // BLX (register) (see BLX (register) in the manual)
+    //
+    // Branch with Link and Exchange calls a subroutine at an address and instruction set specified by a register.
+    // ARMv7-M only supports the Thumb instruction set. An attempt to change the instruction set execution state causes
+    // the processor to take an exception on the instruction at the target address.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  target = R[m];
+    //  next_instr_addr = PC - 2;
+    //  LR = next_instr_addr<31:1> : '1';
+    //  BLXWritePC(target);
+    //
+    // NOTE: like BX we do not model EPSR.T so a target with bit 0 clear does
+    // not fault
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@rm])
+            requires !is_pc(rm)
+            ensures self: Armv7m { new_cpu: fetch_ok(cpu_post_blx(old_cpu, rm)) => new_cpu == cpu_post_blx(old_cpu, rm) }
+    )]
+    pub fn blx(&mut self, rm: Reg) {
+        // Corresponds to encoding T1 of BLX (register)
+        //
+        // Specific encoding ops are:
+        //
+        //  m = UInt(Rm);
+        //  if m == 15 then UNPREDICTABLE;
+        //  if InITBlock() && !LastInITBlock() then UNPREDICTABLE;
+        // read before lr is written so blx lr calls the old lr
+        let target = self.get_value_from_reg(&rm) & BV32::from(0xFFFF_FFFE);
+        let call_site = self.pc;
+        let next_instr_addr = call_site + BV32::from(0x2);
+        self.lr = next_instr_addr | BV32::from(0x1);
+        self.pc = target;
+        self.push_call(call_site, next_instr_addr, target);
+        self.check_instruction_fetch();
+    }
+}
@@ -1,9 +1,44 @@
-use crate::armv7m::lang::SpecialRegister;
+use crate::armv7m::lang::Reg;
 
 use super::super::Armv7m;
 
 use flux_rs::bitvec::BV32;
 
+flux_rs::defs! {
+    // an EXC_RETURN value in Handler mode returns from the exception
+    fn is_exc_return_branch(address: BV32, cpu: Armv7m) -> bool {
+        mode_is_handler(cpu.mode) && (address & 0xF000_0000) == 0xF000_0000
+    }
+
+    // an exception return unstacks a frame that has to be in RAM
+    fn bx_write_pc_precondition(address: BV32, cpu: Armv7m) -> bool {
+        (is_exc_return_branch(address, cpu) && exc_return_valid(address))
+        =>
+        sp_can_handle_exception_exit(get_sp_from_isr_ret(cpu.sp, address), cpu.mem)
+    }
+
+    fn cpu_post_bx_write_pc(address: BV32, cpu: Armv7m) -> Armv7m {
+        if is_exc_return_branch(address, cpu) {
+            cpu_post_exception_return(cpu, address)
+        } else {
+            set_reg(pc(), cpu, address)
+        }
+    }
+
+    // R[t] = data for a load - LoadWritePC is BXWritePC on ARMv7-M
+    fn load_write_reg_precondition(reg: int, val: BV32, cpu: Armv7m) -> bool {
+        is_pc(reg) => bx_write_pc_precondition(val, cpu)
+    }
+
+    fn cpu_post_load_write_reg(reg: int, cpu: Armv7m, val: BV32) -> Armv7m {
+        if is_pc(reg) {
+            cpu_post_bx_write_pc(val, cpu)
+        } else {
+            set_reg(reg, cpu, val)
+        }
+    }
+}
+
 impl Armv7m {
     // BX (see p. A7-215 in the manual)
     //
@@ -18,9 +53,15 @@ impl Armv7m {
     //      EncodingSpecificOperations();
     //      BXWritePC(R[m]);
 
-    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], BV32[@addr]) ensures self: Armv7m[cpu])]
-    fn bx_write_pc(&mut self, address: BV32) {
-        // VTOCK TODO: Implement this with current mode and mode handler etc.
+    // NOTE: we do not model EPSR.T so a target with bit 0 clear does not
+    // fault. A reserved EXC_RETURN raises a UsageFault (see
+    // Armv7m::exception_exit).
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@addr])
+            requires bx_write_pc_precondition(addr, old_cpu)
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_bx_write_pc(addr, old_cpu) }
+    )]
+    pub(super) fn bx_write_pc(&mut self, address: BV32) {
         // BXWritePC(bits(32) address)
         // if CurrentMode == Mode_Handler && address<31:28> == '1111' then
         //     ExceptionReturn(address<27:0>);
@@ -28,14 +69,46 @@ impl Armv7m {
         //     EPSR.T = address<0>;  // if EPSR.T == 0, a UsageFault('Invalid State')
         //                           // is taken on the next instruction
         //     BranchTo(address<31:1>:'0');
+        if self.mode_is_handler() && address & BV32::from(0xF000_0000) == BV32::from(0xF000_0000) {
+            self.exception_exit(address);
+        } else {
+            self.update_reg_with_b32(Reg::pc(), address);
+        }
+    }
+
+    // Writes a value loaded from memory to a register. A load into the pc
+    // (LDR, POP & LDM) is LoadWritePC, which is BXWritePC on ARMv7-M - so it
+    // returns from the exception when it loads an EXC_RETURN value in Handler
+    // mode.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@reg], BV32[@val])
+            requires load_write_reg_precondition(reg, val, old_cpu)
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_load_write_reg(reg, old_cpu, val) }
+    )]
+    pub(super) fn load_write_reg(&mut self, register: Reg, value: BV32) {
+        if register.is_pc() {
+            self.bx_write_pc(value);
+        } else {
+            self.update_reg_with_b32(register, value);
+        }
     }
 
-    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], SpecialRegister[@reg]) ensures self: Armv7m[cpu])]
-    pub fn bx(&mut self, register: SpecialRegister) {
+    // Fetching the instruction at the target faults if the MPU marks it XN
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@reg])
+            requires bx_write_pc_precondition(get_reg(reg, old_cpu), old_cpu)
+            ensures self: Armv7m { new_cpu:
+                fetch_ok(cpu_post_bx_write_pc(get_reg(reg, old_cpu), old_cpu))
+                =>
+                new_cpu == cpu_post_bx_write_pc(get_reg(reg, old_cpu), old_cpu)
+            }
+    )]
+    pub fn bx(&mut self, register: Reg) {
         // Corresponds to Encoding T1
         //
         // Which is simply as BxWritePc op
-        let addr = self.get_value_from_special_reg(&register);
+        let addr = self.get_value_from_reg(&register);
         self.bx_write_pc(addr);
+        self.check_instruction_fetch();
     }
 }
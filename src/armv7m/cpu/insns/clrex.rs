@@ -0,0 +1,21 @@
+use super::super::Armv7m;
+
+impl Armv7m {
+    // CLREX (see CLREX in the manual)
+    //
+    // Clear-Exclusive clears the local record of the executing processor that an address has had a request for an
+    // exclusive access.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  ClearExclusiveLocal(ProcessorID());
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m { monitor: clear_exclusive_local(old_cpu), ..old_cpu } }
+    )]
+    pub fn clrex(&mut self) {
+        self.clear_exclusive_local();
+    }
+}
@@ -0,0 +1,23 @@
+use crate::armv7m::lang::DmbOpt;
+
+use super::super::Armv7m;
+
+impl Armv7m {
+    // DMB (see DMB in the manual)
+    //
+    // Data Memory Barrier acts as a memory barrier. It ensures that all explicit memory accesses that appear in program
+    // order before the DMB instruction are observed before any explicit memory accesses that appear in program order
+    // after the DMB instruction. It does not affect the ordering of any other instructions executing on the processor.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  DataMemoryBarrier(option);
+    //
+    // NOTE: we model a single core whose accesses are observed in program
+    // order (buffered device writes stay in order too, see mem/mmio.rs) so
+    // there is nothing for a DMB to do
+    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], _) ensures self: Armv7m[cpu])]
+    pub fn dmb(&mut self, _opt: Option<DmbOpt>) {}
+}
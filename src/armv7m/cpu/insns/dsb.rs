@@ -0,0 +1,25 @@
+use crate::armv7m::lang::DsbOpt;
+
+use super::super::Armv7m;
+
+impl Armv7m {
+    // DSB (see DSB in the manual)
+    //
+    // Data Synchronization Barrier acts as a special kind of memory barrier. No instruction in program order after this
+    // instruction can execute until this instruction completes. This instruction completes only when both:
+    //  - any explicit memory access made before this instruction is complete
+    //  - the side-effects of any SCS access that performs a context-altering operation are visible.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  DataSynchronizationBarrier(option);
+    //
+    // NOTE: SCS writes take effect right away in our model so the DSB only
+    // has to complete the buffered device writes
+    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], _) ensures self: Armv7m[cpu])]
+    pub fn dsb(&mut self, _opt: Option<DsbOpt>) {
+        self.mem.drain_write_buffer();
+    }
+}
@@ -3,8 +3,26 @@ use crate::armv7m::lang::IsbOpt;
 use super::super::Armv7m;
 
 impl Armv7m {
-    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu], _) ensures self: Armv7m[cpu])]
+    // ISB (see ISB in the manual)
+    //
+    // Instruction Synchronization Barrier flushes the pipeline in the processor, so that all instructions following the
+    // ISB are fetched from cache or memory, after the instruction has been completed. It ensures that the effects of
+    // context altering operations executed before the ISB instruction, such as writes to the special purpose registers,
+    // are visible to the instructions fetched after the ISB.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  InstructionSynchronizationBarrier(option);
+    //
+    // NOTE: the only context we model is CONTROL - an MSR to it takes effect
+    // here (see Armv7m::pending_control)
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu], _)
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_context_sync(cpu) }
+    )]
     pub fn isb(&mut self, _opt: Option<IsbOpt>) {
-        // a no-op in our case
+        self.context_synchronize();
     }
 }
@@ -1,6 +1,6 @@
 use crate::armv7m::{
     cpu::Armv7m,
-    lang::{SpecialRegister, GPR},
+    lang::{Reg, GPR},
 };
 
 use flux_rs::bitvec::BV32;
@@ -8,7 +8,7 @@ impl Armv7m {
     #[flux_rs::sig(
         fn (
             self: &strg Armv7m[@old_cpu],
-            GPR[@rd],
+            Reg[@rd],
             GPR[@rm1],
             GPR[@rm2],
             GPR[@rm3],
@@ -19,23 +19,33 @@ impl Armv7m {
             GPR[@rm8],
         )
         requires
-            is_valid_read_addr(get_gpr(rd, old_cpu))
+            !is_pc(rd)
             &&
-            is_valid_read_addr(get_gpr(rd, old_cpu) + 0x4)
+            is_valid_read_addr(get_reg(rd, old_cpu), old_cpu.mem)
             &&
-            is_valid_read_addr(get_gpr(rd, old_cpu) + 0x8)
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x4, old_cpu.mem)
             &&
-            is_valid_read_addr(get_gpr(rd, old_cpu) + 0xc)
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x8, old_cpu.mem)
             &&
-            is_valid_read_addr(get_gpr(rd, old_cpu) + 0x10)
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0xc, old_cpu.mem)
             &&
-            is_valid_read_addr(get_gpr(rd, old_cpu) + 0x14)
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x10, old_cpu.mem)
             &&
-            is_valid_read_addr(get_gpr(rd, old_cpu) + 0x18)
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x14, old_cpu.mem)
             &&
-            is_valid_ram_addr(get_gpr(rd, old_cpu) + 0x1c)
-        ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x18, old_cpu.mem)
+            &&
+            is_valid_ram_addr(get_reg(rd, old_cpu) + 0x1c, old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            (
+                data_accesses_ok_4(get_reg(rd, old_cpu), false, old_cpu)
+                &&
+                data_accesses_ok_4(get_reg(rd, old_cpu) + 0x10, false, old_cpu)
+            )
+            =>
+            new_cpu == Armv7m {
                 general_regs: gprs_post_ldmia_w(old_cpu, rd, rm1, rm2, rm3, rm4, rm5, rm6, rm7, rm8),
+                mem: mem_post_read(get_reg(rd, old_cpu), get_reg(rd, old_cpu) + 0x1c, old_cpu.mem),
                 ..old_cpu
             }
         }
@@ -43,7 +53,7 @@ impl Armv7m {
     )]
     pub fn ldmia_w(
         &mut self,
-        rd: GPR,
+        rd: Reg,
         rm1: GPR,
         rm2: GPR,
         rm3: GPR,
@@ -53,7 +63,12 @@ impl Armv7m {
         rm7: GPR,
         rm8: GPR,
     ) {
-        let mut addr = self.get_value_from_general_reg(&rd);
+        let mut addr = self.get_value_from_reg(&rd);
+        if !(self.check_data_accesses_4(addr, false)
+            && self.check_data_accesses_4(addr + BV32::from(0x10), false))
+        {
+            return;
+        }
         let val = self.mem.read(addr);
         self.update_general_reg_with_b32(rm1, val);
         addr = addr + BV32::from(0x4);
@@ -82,68 +97,90 @@ impl Armv7m {
     #[flux_rs::sig(
         fn (
             self: &strg Armv7m[@old_cpu],
-            SpecialRegister[@rd],
+            Reg[@rd],
             GPR[@rm1],
             GPR[@rm2],
             GPR[@rm3],
         )
         requires
-            is_valid_read_addr(get_special_reg(rd, old_cpu))
+            !is_pc(rd)
             &&
-            is_valid_read_addr(get_special_reg(rd, old_cpu) + 0x4)
+            rd != rm1 && rd != rm2 && rd != rm3
             &&
-            is_valid_read_addr(get_special_reg(rd, old_cpu) + 0x8)
+            is_valid_read_addr(get_reg(rd, old_cpu), old_cpu.mem)
             &&
-            is_valid_read_addr(get_special_reg(rd, old_cpu) + 0xc)
-        ensures self: Armv7m { new_cpu: new_cpu == set_spr(
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x4, old_cpu.mem)
+            &&
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0x8, old_cpu.mem)
+            &&
+            is_valid_read_addr(get_reg(rd, old_cpu) + 0xc, old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            (
+                data_access_ok(get_reg(rd, old_cpu), false, old_cpu)
+                &&
+                data_access_ok(get_reg(rd, old_cpu) + 0x4, false, old_cpu)
+                &&
+                data_access_ok(get_reg(rd, old_cpu) + 0x8, false, old_cpu)
+            )
+            =>
+            new_cpu == set_reg(
                 rd,
                 Armv7m {
-                    general_regs: gprs_post_ldmia_w_special(old_cpu, rd, rm1, rm2, rm3),
+                    general_regs: gprs_post_ldmia_w_wback(old_cpu, rd, rm1, rm2, rm3),
+                    mem: mem_post_read(get_reg(rd, old_cpu), get_reg(rd, old_cpu) + 0x8, old_cpu.mem),
                     ..old_cpu
                 },
-                get_special_reg(rd, old_cpu) + 0xc
+                get_reg(rd, old_cpu) + 0xc
             )
         }
 
     )]
-    pub fn ldmia_w_special(&mut self, rd: SpecialRegister, rm1: GPR, rm2: GPR, rm3: GPR) {
+    pub fn ldmia_w_wback(&mut self, rd: Reg, rm1: GPR, rm2: GPR, rm3: GPR) {
         // NOTE: This is variant ldmia.w rd! { ... } so updates to rd are written back to the
-        // register
+        // register - the base can not be in the list
         //
         // lowest memory values are written to lowest registers but we can cheat a bit here
         // because our use case passes regs in order
-        let (val1, val2, val3) = self.ldmia_w_special_get_vals(rd);
-        self.ldmia_w_special_update_gprs(rm1, val1, rm2, val2, rm3, val3);
+        let base = self.get_value_from_reg(&rd);
+        if !(self.check_data_access(base, false)
+            && self.check_data_access(base + BV32::from(0x4), false)
+            && self.check_data_access(base + BV32::from(0x8), false))
+        {
+            return;
+        }
+        let (val1, val2, val3) = self.ldmia_w_wback_get_vals(&rd);
+        self.ldmia_w_wback_update_gprs(rm1, val1, rm2, val2, rm3, val3);
+        self.update_reg_with_b32(rd, base + BV32::from(0xc));
     }
 
     #[flux_rs::sig(
         fn (
-            self: &strg Armv7m[@old_cpu],
-            SpecialRegister[@rd],
+            self: &strg Armv7m[@cpu],
+            &Reg[@rd],
         ) -> (
-            BV32[get_mem_addr(get_special_reg(rd, old_cpu), old_cpu.mem)],
-            BV32[get_mem_addr(get_special_reg(rd, old_cpu) + 0x4, old_cpu.mem)],
-            BV32[get_mem_addr(get_special_reg(rd, old_cpu) + 0x8, old_cpu.mem)],
+            BV32[get_mem_addr(get_reg(rd, cpu), cpu.mem)],
+            BV32[get_mem_addr(get_reg(rd, cpu) + 0x4, cpu.mem)],
+            BV32[get_mem_addr(get_reg(rd, cpu) + 0x8, cpu.mem)],
         )
         requires
-            is_valid_read_addr(get_special_reg(rd, old_cpu))
+            is_valid_read_addr(get_reg(rd, cpu), cpu.mem)
             &&
-            is_valid_read_addr(get_special_reg(rd, old_cpu) + 0x4)
+            is_valid_read_addr(get_reg(rd, cpu) + 0x4, cpu.mem)
             &&
-            is_valid_read_addr(get_special_reg(rd, old_cpu) + 0x8)
-            &&
-            is_valid_read_addr(get_special_reg(rd, old_cpu) + 0xc)
-        ensures self: Armv7m { new_cpu: new_cpu == set_spr(rd, old_cpu, get_special_reg(rd, old_cpu) + 0xc) }
+            is_valid_read_addr(get_reg(rd, cpu) + 0x8, cpu.mem)
+        ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+                mem: mem_post_read(get_reg(rd, cpu), get_reg(rd, cpu) + 0x8, cpu.mem),
+                ..cpu
+            }
+        }
     )]
-    fn ldmia_w_special_get_vals(&mut self, rd: SpecialRegister) -> (BV32, BV32, BV32) {
-        let mut addr = self.get_value_from_special_reg(&rd);
+    fn ldmia_w_wback_get_vals(&mut self, rd: &Reg) -> (BV32, BV32, BV32) {
+        let mut addr = self.get_value_from_reg(rd);
         let val1 = self.mem.read(addr);
         addr = addr + BV32::from(0x4);
         let val2 = self.mem.read(addr);
         addr = addr + BV32::from(0x4);
         let val3 = self.mem.read(addr);
-        addr = addr + BV32::from(0x4);
-        self.update_special_reg_with_b32(rd, addr);
         (val1, val2, val3)
     }
 
@@ -174,7 +211,7 @@ impl Armv7m {
             ..cpu
         } }
     )]
-    fn ldmia_w_special_update_gprs(
+    fn ldmia_w_wback_update_gprs(
         &mut self,
         rm1: GPR,
         val1: BV32,
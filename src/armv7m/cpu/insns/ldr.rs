@@ -1,9 +1,23 @@
-use crate::armv7m::lang::{SpecialRegister, GPR};
+use crate::armv7m::lang::Reg;
 
 use flux_rs::bitvec::BV32;
 
 use super::super::Armv7m;
 
+flux_rs::defs! {
+    fn cpu_post_ldr_imm_read(rn: int, imm: BV32, cpu: Armv7m) -> Armv7m {
+        Armv7m { mem: mem_post_read(get_reg(rn, cpu) + imm, get_reg(rn, cpu) + imm, cpu.mem), ..cpu }
+    }
+
+    fn cpu_post_ldr_imm(rt: int, rn: int, imm: BV32, cpu: Armv7m) -> Armv7m {
+        cpu_post_load_write_reg(
+            rt,
+            cpu_post_ldr_imm_read(rn, imm, cpu),
+            get_mem_addr(get_reg(rn, cpu) + imm, cpu.mem)
+        )
+    }
+}
+
 impl Armv7m {
     // LDR (literal) see p. A7-248 in the manual
     //
@@ -22,12 +36,15 @@ impl Armv7m {
     //   else
     //      R[t] = data;
 
-    #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], GPR[@reg], BV32[@val])
+    #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], Reg[@reg], BV32[@val])
+        requires load_write_reg_precondition(reg, val, old_cpu)
         ensures self: Armv7m { new_cpu:
-            new_cpu == Armv7m { general_regs: set_gpr(reg, old_cpu, val), ..old_cpu }
+            (is_pc(reg) => fetch_ok(cpu_post_load_write_reg(reg, old_cpu, val)))
+            =>
+            new_cpu == cpu_post_load_write_reg(reg, old_cpu, val)
         }
     )]
-    pub fn pseudo_ldr(&mut self, register: GPR, value: BV32) {
+    pub fn pseudo_ldr(&mut self, register: Reg, value: BV32) {
         // Note the non pseudo instruction would do this:
         //
         //      fn align(value: BV32, alignment: BV32) -> BV32 {
@@ -36,33 +53,81 @@ impl Armv7m {
         //      let base = Self::align(self.pc, 4);
         //      let addr = base + value;
         //      let data = self.mem.read(addr);
-        //      VTOCK TODO: Deal with PC update here
-        //      self.update_general_reg_with_BV32(register, data);
+        //      self.load_write_reg(register, data);
         //
         // but since dealing with offsets to the PC isn't supported right
         // now we'll just encode the pseudo instruction as a mov
-        self.movw_imm(register, value);
+        self.load_write_reg(register, value);
+        if register.is_pc() {
+            self.check_instruction_fetch();
+        }
     }
 
-    #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], SpecialRegister[@reg], BV32[@val])
-        // right now requires that the register is the link register
-        requires is_lr(reg)
-        ensures self: Armv7m { new_cpu: new_cpu == Armv7m { lr: val, ..old_cpu } }
+    // LDR (immediate) (see LDR (immediate) in the manual)
+    //
+    // Load Register (immediate) calculates an address from a base register value and an immediate offset, loads a word
+    // from memory, and writes it to a register.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  offset_addr = if add then (R[n] + imm32) else (R[n] - imm32);
+    //  address = if index then offset_addr else R[n];
+    //  data = MemU[address,4];
+    //  if wback then R[n] = offset_addr;
+    //  if t == 15 then
+    //      if address<1:0> == '00' then LoadWritePC(data); else UNPREDICTABLE;
+    //  else
+    //      R[t] = data;
+    //
+    // NOTE: only the offset form (index, add & no writeback) - the base can
+    // be SP (ldr rt, [sp, #imm]) but not the pc, that is LDR (literal).
+    // Unaligned loads are not supported. Loading into the pc is LoadWritePC
+    // (see Armv7m::load_write_reg) - the instruction at the target is then
+    // fetched, which faults if the MPU marks it XN.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@rt], Reg[@rn], BV32[@imm])
+            requires
+                !is_pc(rn)
+                &&
+                is_valid_read_addr(get_reg(rn, old_cpu) + imm, old_cpu.mem)
+                &&
+                word_aligned(get_reg(rn, old_cpu) + imm)
+                &&
+                load_write_reg_precondition(
+                    rt,
+                    get_mem_addr(get_reg(rn, old_cpu) + imm, old_cpu.mem),
+                    cpu_post_ldr_imm_read(rn, imm, old_cpu)
+                )
+            ensures self: Armv7m { new_cpu:
+                (
+                    data_access_ok(get_reg(rn, old_cpu) + imm, false, old_cpu)
+                    &&
+                    (is_pc(rt) => fetch_ok(cpu_post_ldr_imm(rt, rn, imm, old_cpu)))
+                )
+                =>
+                new_cpu == cpu_post_ldr_imm(rt, rn, imm, old_cpu)
+            }
     )]
-    pub fn pseudo_ldr_special(&mut self, register: SpecialRegister, value: BV32) {
-        // Note the non pseudo instruction would do this:
+    pub fn ldr_imm(&mut self, rt: Reg, rn: Reg, imm: BV32) {
+        // Corresponds to encoding T3 of LDR (immediate) - T1 & T2 (SP base)
+        // are the narrow forms of it
         //
-        //      fn align(value: BV32, alignment: BV32) -> BV32 {
-        //          alignment * (value / alignment)
-        //      }
-        //      let base = Self::align(self.pc, 4);
-        //      let addr = base + value;
-        //      let data = self.mem.read(addr);
-        //      VTOCK TODO: Deal with PC update here
-        //      self.update_general_reg_with_BV32(register, data);
+        // Specific encoding ops are:
         //
-        // but since dealing with offsets to the PC isn't supported right
-        // now we'll just encode the pseudo instruction as a mov
-        self.update_special_reg_with_b32(register, value);
+        //  if Rn == '1111' then SEE LDR (literal);
+        //  t = UInt(Rt); n = UInt(Rn); imm32 = ZeroExtend(imm12, 32);
+        //  index = TRUE; add = TRUE; wback = FALSE;
+        //  if t == 15 && InITBlock() && !LastInITBlock() then UNPREDICTABLE;
+        let address = self.get_value_from_reg(&rn) + imm;
+        if !self.check_data_access(address, false) {
+            return;
+        }
+        let data = self.mem.read(address);
+        self.load_write_reg(rt, data);
+        if rt.is_pc() {
+            self.check_instruction_fetch();
+        }
     }
 }
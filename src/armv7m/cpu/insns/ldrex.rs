@@ -0,0 +1,152 @@
+use crate::armv7m::{cpu::Armv7m, lang::GPR};
+
+use flux_rs::bitvec::BV32;
+
+impl Armv7m {
+    // LDREX (see LDREX in the manual)
+    //
+    // Load Register Exclusive calculates an address from a base register value and an immediate offset, loads a word
+    // from memory, writes it to a register and:
+    //  - if the address has the Shared Memory attribute, marks the physical address as exclusive access for the
+    //    executing processor in a global monitor
+    //  - causes the executing processor to indicate an active exclusive access in the local monitor.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  address = R[n] + imm32;
+    //  SetExclusiveMonitors(address,4);
+    //  R[t] = MemA[address,4];
+    //
+    // NOTE: exclusive accesses always fault when unaligned, whatever
+    // CCR.UNALIGN_TRP says, so we require an aligned address. There is no
+    // global monitor since we model a single core.
+    #[flux_rs::sig(
+        fn (
+            self: &strg Armv7m[@old_cpu],
+            GPR[@rt],
+            GPR[@rn],
+            BV32[@imm]
+        )
+        requires
+            is_valid_read_addr(get_gpr(rn, old_cpu) + imm, old_cpu.mem)
+            &&
+            word_aligned(get_gpr(rn, old_cpu) + imm)
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_gpr(rn, old_cpu) + imm, false, old_cpu)
+            =>
+            new_cpu == Armv7m {
+                general_regs: set_gpr(rt, old_cpu, get_mem_addr(get_gpr(rn, old_cpu) + imm, old_cpu.mem)),
+                monitor: set_exclusive_monitors(get_gpr(rn, old_cpu) + imm),
+                mem: mem_post_read(get_gpr(rn, old_cpu) + imm, get_gpr(rn, old_cpu) + imm, old_cpu.mem),
+                ..old_cpu
+            }
+        }
+    )]
+    pub fn ldrex(&mut self, rt: GPR, rn: GPR, imm: BV32) {
+        // Corresponds to encoding T1 of LDREX
+        //
+        // Specific encoding ops are:
+        //
+        //  t = UInt(Rt); n = UInt(Rn); imm32 = ZeroExtend(imm8:'00', 32);
+        //  if t IN {13,15} || n == 15 then UNPREDICTABLE;
+        let address = self.get_value_from_general_reg(&rn) + imm;
+        if !self.check_data_access(address, false) {
+            return;
+        }
+        self.set_exclusive_monitors(address);
+        let value = self.mem.read(address);
+        self.update_general_reg_with_b32(rt, value);
+    }
+
+    // LDREXB (see LDREXB in the manual)
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  address = R[n];
+    //  SetExclusiveMonitors(address,1);
+    //  R[t] = ZeroExtend(MemA[address,1], 32);
+    #[flux_rs::sig(
+        fn (
+            self: &strg Armv7m[@old_cpu],
+            GPR[@rt],
+            GPR[@rn]
+        )
+        requires is_valid_read_access(get_gpr(rn, old_cpu), 1, old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_gpr(rn, old_cpu), false, old_cpu)
+            =>
+            new_cpu == Armv7m {
+                general_regs: set_gpr(rt, old_cpu, get_mem_byte(get_gpr(rn, old_cpu), old_cpu.mem)),
+                monitor: set_exclusive_monitors(get_gpr(rn, old_cpu)),
+                mem: mem_post_read_access(get_gpr(rn, old_cpu), 1, old_cpu.mem),
+                ..old_cpu
+            }
+        }
+    )]
+    pub fn ldrexb(&mut self, rt: GPR, rn: GPR) {
+        // Corresponds to encoding T1 of LDREXB
+        //
+        // Specific encoding ops are:
+        //
+        //  t = UInt(Rt); n = UInt(Rn);
+        //  if t IN {13,15} || n == 15 then UNPREDICTABLE;
+        let address = self.get_value_from_general_reg(&rn);
+        if !self.check_data_access(address, false) {
+            return;
+        }
+        self.set_exclusive_monitors(address);
+        let value = self.mem.read8(address);
+        self.update_general_reg_with_b32(rt, value);
+    }
+
+    // LDREXH (see LDREXH in the manual)
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  address = R[n];
+    //  SetExclusiveMonitors(address,2);
+    //  R[t] = ZeroExtend(MemA[address,2], 32);
+    #[flux_rs::sig(
+        fn (
+            self: &strg Armv7m[@old_cpu],
+            GPR[@rt],
+            GPR[@rn]
+        )
+        requires
+            is_valid_read_access(get_gpr(rn, old_cpu), 2, old_cpu.mem)
+            &&
+            halfword_aligned(get_gpr(rn, old_cpu))
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_gpr(rn, old_cpu), false, old_cpu)
+            =>
+            new_cpu == Armv7m {
+                general_regs: set_gpr(rt, old_cpu, get_mem_halfword(get_gpr(rn, old_cpu), old_cpu.mem)),
+                monitor: set_exclusive_monitors(get_gpr(rn, old_cpu)),
+                mem: mem_post_read_access(get_gpr(rn, old_cpu), 2, old_cpu.mem),
+                ..old_cpu
+            }
+        }
+    )]
+    pub fn ldrexh(&mut self, rt: GPR, rn: GPR) {
+        // Corresponds to encoding T1 of LDREXH
+        //
+        // Specific encoding ops are:
+        //
+        //  t = UInt(Rt); n = UInt(Rn);
+        //  if t IN {13,15} || n == 15 then UNPREDICTABLE;
+        let address = self.get_value_from_general_reg(&rn);
+        if !self.check_data_access(address, false) {
+            return;
+        }
+        self.set_exclusive_monitors(address);
+        // aligned so this can not fault
+        let value = self.mem.read16(address).unwrap();
+        self.update_general_reg_with_b32(rt, value);
+    }
+}
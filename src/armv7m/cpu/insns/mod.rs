@@ -1,18 +1,28 @@
 mod add;
 mod and;
+mod bl;
 mod bx;
+mod clrex;
+mod dmb;
+mod dsb;
 mod isb;
 mod ldmia;
 mod ldr;
+mod ldrex;
 mod mov;
 mod mrs;
 mod msr;
 mod mvn;
 mod pop;
 mod push;
+mod sev;
 mod shift;
 mod stmdb;
 mod stmia;
 mod str;
+mod strex;
 mod sub;
 mod svc;
+mod wfe;
+mod wfi;
+mod yield_;
@@ -1,9 +1,53 @@
 use super::super::flux_defs::*;
 use super::super::Armv7m;
-use crate::armv7m::lang::GPR;
+use crate::armv7m::lang::{Reg, GPR};
 use flux_rs::bitvec::BV32;
 
 impl Armv7m {
+    // MOV (register) (see MOV (register) in the manual)
+    //
+    // Move (register) copies a value from a register to the destination register. It can optionally update the
+    // condition flags based on the value.
+    //
+    // Pseudo code provided by arm:
+    // if ConditionPassed() then
+    //   EncodingSpecificOperations();
+    //   result = R[m];
+    //   if d == 15 then
+    //       ALUWritePC(result); // setflags is always FALSE here
+    //   else
+    //       R[d] = result;
+    //       if setflags then
+    //           APSR.N = result<31>;
+    //           APSR.Z = IsZeroBit(result);
+    //           // APSR.C unchanged
+    //           // APSR.V unchanged
+    //
+    // NOTE: like a branch, moving to the pc fetches the instruction at the
+    // target, which faults if the MPU marks it XN.
+    #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], Reg[@rd], Reg[@rm])
+       ensures self: Armv7m { new_cpu:
+            (is_pc(rd) => fetch_ok(set_reg(rd, old_cpu, get_reg(rm, old_cpu))))
+            =>
+            new_cpu == set_reg(rd, old_cpu, get_reg(rm, old_cpu))
+        }
+    )]
+    pub fn mov(&mut self, rd: Reg, rm: Reg) {
+        // Corresponds to encoding T1 of MOV (register)
+        //
+        // Specific encoding ops are:
+        //
+        //  d = UInt(D:Rd); m = UInt(Rm); setflags = FALSE;
+        //  if d == 15 && InITBlock() && !LastInITBlock() then UNPREDICTABLE;
+        //
+        // so any of R0-R15 can be moved to any other - no flag updates
+        let value = self.get_value_from_reg(&rm);
+        self.update_reg_with_b32(rd, value);
+        if rd.is_pc() {
+            self.check_instruction_fetch();
+        }
+    }
+
     // Move Immediate (see p. A7-291 of the manual)
     //
     // Pseudo code provided by arm:
@@ -18,16 +62,6 @@ impl Armv7m {
     //       // APSR.V unchanged
     //
 
-    #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], GPR[@rd], GPR[@rm])
-       ensures self: Armv7m { new_cpu:
-           new_cpu == Armv7m { general_regs: set_gpr(rd, old_cpu, get_gpr(rm, old_cpu)), ..old_cpu }
-       }
-    )]
-    pub fn mov(&mut self, rd: GPR, rm: GPR) {
-        // No flag updates - just moves rm to rd
-        self.update_general_reg_with_b32(rd, self.get_value_from_general_reg(&rm));
-    }
-
     #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], GPR[@reg], BV32[@val])
         ensures self: Armv7m { new_cpu:
             new_cpu == Armv7m { general_regs: set_gpr(reg, old_cpu, val), ..old_cpu }
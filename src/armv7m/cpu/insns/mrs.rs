@@ -1,18 +1,137 @@
-use crate::armv7m::lang::{SpecialRegister, GPR};
+use crate::armv7m::lang::{SpecialRegister, SysReg, GPR};
 
 use super::super::Armv7m;
 
-impl Armv7m {
-    // Move to Register from Special register moves the value from the
-    // selected special-purpose register into a general-purpose Arm register.
-    // See p. A7-300 & p. B5-675 of the manual
-    #[flux_rs::sig(fn (self: &strg Armv7m[@old_cpu], GPR[@reg], SpecialRegister[@val]) 
-        ensures self: Armv7m { new_cpu: 
-            new_cpu == Armv7m { general_regs: set_gpr(reg, old_cpu, get_special_reg(val, old_cpu)), ..old_cpu }
+use flux_rs::bitvec::BV32;
+
+flux_rs::defs! {
+    fn xpsr_includes_ipsr(sysm: int) -> bool {
+        sysm == 1 || sysm == 3 || sysm == 5 || sysm == 7
+    }
+
+    fn xpsr_includes_apsr(sysm: int) -> bool {
+        sysm < 4
+    }
+
+    // EPSR reads as zero so an xPSR view is the IPSR & APSR bits it includes
+    fn mrs_xpsr(sysm: int, cpu: Armv7m) -> BV32 {
+        (if xpsr_includes_ipsr(sysm) { cpu.psr & 0x1FF } else { 0 })
+        |
+        (if xpsr_includes_apsr(sysm) { cpu.psr & 0xF800_0000 } else { 0 })
+    }
+
+    fn mrs_value(sysm: int, cpu: Armv7m) -> BV32 {
+        if sysm < 8 {
+            mrs_xpsr(sysm, cpu)
+        } else if sysm == 8 || sysm == 9 {
+            if !mode_is_privileged(cpu.mode, cpu.control) {
+                0
+            } else if sysm == 8 {
+                sp_main(cpu.sp)
+            } else {
+                sp_process(cpu.sp)
+            }
+        } else if sysm == 16 {
+            cpu.primask & 0x1
+        } else if sysm == 17 || sysm == 18 {
+            cpu.basepri & 0xFF
+        } else if sysm == 19 {
+            cpu.faultmask & 0x1
+        } else {
+            get_control(cpu.pending_control)
         }
+    }
+}
+
+impl Armv7m {
+    // MRS (see MRS in the manual)
+    //
+    // Move to Register from Special register moves the value from the selected special-purpose register into a
+    // general-purpose Arm register.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  R[d] = Zeros(32);
+    //  case SYSm<7:3> of
+    //      when '00000'                            /* xPSR accesses */
+    //          if SYSm<0> == '1' then
+    //              R[d]<8:0> = IPSR<8:0>;
+    //          if SYSm<1> == '1' then
+    //              R[d]<26:24> = '000';            /* EPSR reads as zero */
+    //              R[d]<15:10> = '000000';
+    //          if SYSm<2> == '0' then
+    //              R[d]<31:27> = APSR<31:27>;
+    //              if HaveDSPExt() then
+    //                  R[d]<19:16> = APSR<19:16>;
+    //      when '00001'                            /* SP access */
+    //          if CurrentModeIsPrivileged() then
+    //              case SYSm<2:0> of
+    //                  when '000'
+    //                      R[d] = SP_main;
+    //                  when '001'
+    //                      R[d] = SP_process;
+    //      when '00010'                            /* Priority mask or Control access */
+    //          case SYSm<2:0> of
+    //              when '000'
+    //                  R[d]<0> = PRIMASK<0>;
+    //              when '001'
+    //                  R[d]<7:0> = BASEPRI<7:0>;
+    //              when '010'
+    //                  R[d]<7:0> = BASEPRI<7:0>;   /* BASEPRI_MAX alias */
+    //              when '011'
+    //                  R[d]<0> = FAULTMASK<0>;
+    //              when '100'
+    //                  if HaveFPExt() then
+    //                      R[d]<2:0> = CONTROL<2:0>;
+    //                  else
+    //                      R[d]<1:0> = CONTROL<1:0>;
+    //
+    // NOTE: we implement neither the DSP nor the FP extension. CONTROL reads
+    // the value last written to it (see Armv7m::pending_control).
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], GPR[@rd], SysReg[@sysm])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+                    general_regs: set_gpr(rd, old_cpu, mrs_value(sysm, old_cpu)),
+                    ..old_cpu
+                }
+            }
     )]
-    pub fn mrs(&mut self, register: GPR, value: SpecialRegister) {
-        let value = self.get_value_from_special_reg(&value);
-        self.update_general_reg_with_b32(register, value);
+    pub fn mrs(&mut self, rd: GPR, sysm: SysReg) {
+        // Corresponds to encoding T1 of MRS
+        //
+        // Specific encoding ops are:
+        //
+        //  d = UInt(Rd);
+        //  if d IN {13,15} || !(UInt(SYSm) IN {0..3,5..9,16..20}) then UNPREDICTABLE;
+        let value = match sysm {
+            SysReg::Apsr => self.psr & BV32::from(0xF800_0000),
+            SysReg::Iapsr | SysReg::Xpsr => {
+                (self.psr & BV32::from(0x1FF)) | (self.psr & BV32::from(0xF800_0000))
+            }
+            SysReg::Eapsr => self.psr & BV32::from(0xF800_0000),
+            SysReg::Ipsr | SysReg::Iepsr => self.psr & BV32::from(0x1FF),
+            SysReg::Epsr => BV32::from(0),
+            SysReg::Msp => {
+                if self.is_privileged() {
+                    self.sp.sp_main
+                } else {
+                    BV32::from(0)
+                }
+            }
+            SysReg::Psp => {
+                if self.is_privileged() {
+                    self.sp.sp_process
+                } else {
+                    BV32::from(0)
+                }
+            }
+            SysReg::Primask => self.primask & BV32::from(0x1),
+            SysReg::Basepri | SysReg::BasepriMax => self.basepri & BV32::from(0xFF),
+            SysReg::Faultmask => self.faultmask & BV32::from(0x1),
+            SysReg::Control => self.get_value_from_special_reg(&SpecialRegister::Control),
+        };
+        self.update_general_reg_with_b32(rd, value);
     }
 }
@@ -1,17 +1,180 @@
-use crate::armv7m::lang::{SpecialRegister, GPR};
+use crate::armv7m::lang::{SpecialRegister, SysReg, GPR};
 
-use super::super::Armv7m;
+use super::super::{Armv7m, SP};
+
+use flux_rs::bitvec::BV32;
+
+flux_rs::defs! {
+    // BASEPRI_MAX only ever raises the priority BASEPRI masks - 0 masks
+    // nothing and lower values mask more
+    fn basepri_max_writes(cpu: Armv7m, val: BV32) -> bool {
+        (val & 0xFF) != 0 && ((val & 0xFF) < (cpu.basepri & 0xFF) || (cpu.basepri & 0xFF) == 0)
+    }
+
+    fn cpu_post_msr(sysm: int, cpu: Armv7m, val: BV32) -> Armv7m {
+        if sysm < 4 {
+            // APSR_nzcvq - the only xPSR bits software can write
+            Armv7m { psr: (cpu.psr & 0x07FF_FFFF) | (val & 0xF800_0000), ..cpu }
+        } else if sysm < 8 || !mode_is_privileged(cpu.mode, cpu.control) {
+            // IPSR & EPSR are read only and unprivileged writes to the
+            // rest are ignored
+            cpu
+        } else if sysm == 8 {
+            Armv7m { sp: SP { sp_main: val & 0xFFFF_FFFC, ..cpu.sp }, ..cpu }
+        } else if sysm == 9 {
+            set_spr(psp(), cpu, val & 0xFFFF_FFFC)
+        } else if sysm == 16 {
+            Armv7m { primask: val & 0x1, ..cpu }
+        } else if sysm == 17 {
+            Armv7m { basepri: val & 0xFF, ..cpu }
+        } else if sysm == 18 {
+            if basepri_max_writes(cpu, val) {
+                Armv7m { basepri: val & 0xFF, ..cpu }
+            } else {
+                cpu
+            }
+        } else if sysm == 19 {
+            if !execution_priority_is_negative(cpu) {
+                Armv7m { faultmask: val & 0x1, ..cpu }
+            } else {
+                cpu
+            }
+        } else {
+            set_spr(control(), cpu, val)
+        }
+    }
+
+    // Until the next context synchronization event whether a write to
+    // CONTROL has taken effect is UNKNOWN - the core goes by either the old
+    // value or the one written
+    fn msr_post(sysm: int, old_cpu: Armv7m, val: BV32, new_cpu: Armv7m) -> bool {
+        let post = cpu_post_msr(sysm, old_cpu, val);
+        if sysm == 20 {
+            new_cpu == Armv7m { control: new_cpu.control, ..post }
+            &&
+            (new_cpu.control == post.control || new_cpu.control == post.pending_control)
+        } else {
+            new_cpu == post
+        }
+    }
+}
+
+// Whether a write to CONTROL has taken effect before the next context
+// synchronization event - flux knows nothing about the result (see
+// msr_post). Concretely it has not.
+#[flux_rs::trusted]
+fn control_write_took_effect() -> bool {
+    false
+}
 
 impl Armv7m {
-    // Move to Special Register from Arm Register moves the value of a
-    // general-purpose Arm register to the specified special-purpose register.
-    // See p. A7-301 & p. B5-677 of the manual
+    // MSR (see MSR in the manual)
+    //
+    // Move to Special Register from Arm Register moves the value of a general-purpose Arm register to the specified
+    // special-purpose register.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  case SYSm<7:3> of
+    //      when '00000'                            /* xPSR accesses */
+    //          if SYSm<2> == '0' then              /* Include APSR */
+    //              if mask<0> == '1' then          /* GE[3:0] bits */
+    //                  if !HaveDSPExt() then
+    //                      UNPREDICTABLE;
+    //                  else
+    //                      APSR<19:16> = R[n]<19:16>;
+    //              if mask<1> == '1' then          /* N, Z, C, V, Q bits */
+    //                  APSR<31:27> = R[n]<31:27>;
+    //      when '00001'                            /* SP access */
+    //          if CurrentModeIsPrivileged() then
+    //              case SYSm<2:0> of
+    //                  when '000'
+    //                      SP_main = R[n]<31:2>:'00';
+    //                  when '001'
+    //                      SP_process = R[n]<31:2>:'00';
+    //      when '00010'                            /* Priority mask or Control access */
+    //          case SYSm<2:0> of
+    //              when '000'
+    //                  if CurrentModeIsPrivileged() then PRIMASK<0> = R[n]<0>;
+    //              when '001'
+    //                  if CurrentModeIsPrivileged() then BASEPRI<7:0> = R[n]<7:0>;
+    //              when '010'
+    //                  if CurrentModeIsPrivileged() &&
+    //                      (R[n]<7:0> != '00000000') &&
+    //                      (UInt(R[n]<7:0>) < UInt(BASEPRI<7:0>) || BASEPRI<7:0> == '00000000') then
+    //                      BASEPRI<7:0> = R[n]<7:0>;
+    //              when '011'
+    //                  if CurrentModeIsPrivileged() &&
+    //                      (ExecutionPriority() > -1) then
+    //                      FAULTMASK<0> = R[n]<0>;
+    //              when '100'
+    //                  if CurrentModeIsPrivileged() then
+    //                      CONTROL.nPRIV = R[n]<0>;
+    //                      if CurrentMode == Mode_Thread then
+    //                          CONTROL.SPSEL = R[n]<1>;
+    //                      if HaveFPExt() then CONTROL.FPCA = R[n]<2>;
+    //
+    // NOTE: mask is always '10' (MSR APSR_nzcvq) since we have no DSP
+    // extension. A write to CONTROL only takes effect for certain at the
+    // next context synchronization event (see Armv7m::pending_control) -
+    // until then which value privilege & stack selection go by is UNKNOWN,
+    // so code that depends on the write needs an ISB after it.
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@old_cpu], SpecialRegister[@reg], GPR[@val])
-            requires !is_ipsr(reg) && (is_sp(reg) || is_psp(reg)) => is_valid_ram_addr(get_gpr(val, old_cpu))
-            ensures self: Armv7m { new_cpu: new_cpu == set_spr(reg, old_cpu, get_gpr(val, old_cpu)) }
+        fn (self: &strg Armv7m[@old_cpu], SysReg[@sysm], GPR[@rn])
+            ensures self: Armv7m { new_cpu: msr_post(sysm, old_cpu, get_gpr(rn, old_cpu), new_cpu) }
     )]
-    pub fn msr(&mut self, register: SpecialRegister, value: GPR) {
-        self.update_special_reg_with_b32(register, self.get_value_from_general_reg(&value));
+    pub fn msr(&mut self, sysm: SysReg, rn: GPR) {
+        // Corresponds to encoding T1 of MSR
+        //
+        // Specific encoding ops are:
+        //
+        //  n = UInt(Rn); mask = mask;
+        //  if mask == '00' || (mask != '10' && !(UInt(SYSm) IN {0..3})) then UNPREDICTABLE;
+        //  if n IN {13,15} || !(UInt(SYSm) IN {0..3,5..9,16..20}) then UNPREDICTABLE;
+        let value = self.get_value_from_general_reg(&rn);
+        match sysm {
+            SysReg::Apsr | SysReg::Iapsr | SysReg::Eapsr | SysReg::Xpsr => {
+                self.psr = (self.psr & BV32::from(0x07FF_FFFF)) | (value & BV32::from(0xF800_0000));
+            }
+            SysReg::Ipsr | SysReg::Epsr | SysReg::Iepsr => {}
+            _ if !self.is_privileged() => {}
+            SysReg::Msp => {
+                self.sp.sp_main = value & BV32::from(0xFFFF_FFFC);
+            }
+            SysReg::Psp => {
+                self.update_special_reg_with_b32(
+                    SpecialRegister::PSP,
+                    value & BV32::from(0xFFFF_FFFC),
+                );
+            }
+            SysReg::Primask => {
+                self.primask = value & BV32::from(0x1);
+            }
+            SysReg::Basepri => {
+                self.basepri = value & BV32::from(0xFF);
+            }
+            SysReg::BasepriMax => {
+                let new_basepri = value & BV32::from(0xFF);
+                let basepri = self.basepri & BV32::from(0xFF);
+                if new_basepri != BV32::from(0)
+                    && (new_basepri < basepri || basepri == BV32::from(0))
+                {
+                    self.basepri = new_basepri;
+                }
+            }
+            SysReg::Faultmask => {
+                if !self.execution_priority_is_negative() {
+                    self.faultmask = value & BV32::from(0x1);
+                }
+            }
+            SysReg::Control => {
+                self.update_special_reg_with_b32(SpecialRegister::Control, value);
+                if control_write_took_effect() {
+                    self.context_synchronize();
+                }
+            }
+        }
     }
 }
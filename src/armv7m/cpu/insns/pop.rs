@@ -1,6 +1,6 @@
 use crate::armv7m::{
     cpu::Armv7m,
-    lang::{SpecialRegister, GPR},
+    lang::{Reg, GPR},
 };
 
 use flux_rs::bitvec::BV32;
@@ -28,6 +28,12 @@ flux_rs::defs! {
 }
 
 impl Armv7m {
+    // NOTE: popping into the pc is LoadWritePC (see Armv7m::load_write_reg) -
+    // it branches to the popped value with bit 0 cleared, which is how a
+    // function pushed lr in its prologue returns to its caller, or returns
+    // from the exception when a handler pops an EXC_RETURN value. The
+    // instruction at the target is then fetched, which faults if the MPU
+    // marks it XN.
     #[flux_rs::sig(
         fn (
             self: &strg Armv7m[@old_cpu],
@@ -35,20 +41,35 @@ impl Armv7m {
             GPR[@r2],
             GPR[@r3],
             GPR[@r4],
-            SpecialRegister[@r5]
+            Reg[@r5]
         )
             requires
-                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control))
+                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control), old_cpu.mem)
                 &&
-                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) + 0x14)
+                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) + 0x14, old_cpu.mem)
                 &&
                 !is_sp(r5)
                 &&
-                !is_psp(r5)
-            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_pop(old_cpu, r1, r2, r3, r4, r5) }
+                load_write_reg_precondition(r5, pop_last_val(old_cpu), cpu_post_pop_regs(old_cpu, r1, r2, r3, r4))
+            ensures self: Armv7m { new_cpu:
+                (
+                    data_accesses_ok_4(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control), false, old_cpu)
+                    &&
+                    data_access_ok(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) + 0x10, false, old_cpu)
+                    &&
+                    (is_pc(r5) => fetch_ok(cpu_post_pop(old_cpu, r1, r2, r3, r4, r5)))
+                )
+                =>
+                new_cpu == cpu_post_pop(old_cpu, r1, r2, r3, r4, r5)
+            }
     )]
-    pub fn pop(&mut self, r1: GPR, r2: GPR, r3: GPR, r4: GPR, r5: SpecialRegister) {
-        let mut sp = self.get_value_from_special_reg(&SpecialRegister::sp());
+    pub fn pop(&mut self, r1: GPR, r2: GPR, r3: GPR, r4: GPR, r5: Reg) {
+        let mut sp = self.get_value_from_reg(&Reg::sp());
+        if !(self.check_data_accesses_4(sp, false)
+            && self.check_data_access(sp + BV32::from(0x10), false))
+        {
+            return;
+        }
 
         let val1 = self.mem.read(sp);
         self.update_general_reg_with_b32(r1, val1);
@@ -67,9 +88,12 @@ impl Armv7m {
         sp = sp + BV32::from(0x4);
 
         let val5 = self.mem.read(sp);
-        self.update_special_reg_with_b32(r5, val5);
         sp = sp + BV32::from(0x4);
 
-        self.update_special_reg_with_b32(SpecialRegister::sp(), sp);
+        self.update_reg_with_b32(Reg::sp(), sp);
+        self.load_write_reg(r5, val5);
+        if r5.is_pc() {
+            self.check_instruction_fetch();
+        }
     }
 }
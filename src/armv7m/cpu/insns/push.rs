@@ -1,6 +1,6 @@
 use crate::armv7m::{
     cpu::Armv7m,
-    lang::{SpecialRegister, GPR},
+    lang::{Reg, GPR},
 };
 use flux_rs::bitvec::BV32;
 
@@ -32,7 +32,7 @@ flux_rs::defs! {
                 get_gpr(r4, cpu)
             ),
             get_sp(cpu.sp, cpu.mode, cpu.control) - 0x4,
-            get_special_reg(r5, cpu)
+            get_reg(r5, cpu)
         )
     }
 }
@@ -45,13 +45,22 @@ impl Armv7m {
             GPR[@r2],
             GPR[@r3],
             GPR[@r4],
-            SpecialRegister[@r5]
+            Reg[@r5]
         )
             requires
-                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control))
+                !is_sp(r5) && !is_pc(r5)
                 &&
-                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) - 0x14)
-            ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control), old_cpu.mem)
+                &&
+                is_valid_ram_addr(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) - 0x14, old_cpu.mem)
+            ensures self: Armv7m { new_cpu:
+                (
+                    data_accesses_ok_4(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) - 0x14, true, old_cpu)
+                    &&
+                    data_access_ok(get_sp(old_cpu.sp, old_cpu.mode, old_cpu.control) - 0x4, true, old_cpu)
+                )
+                =>
+                new_cpu == Armv7m {
                     mem: mem_post_push(old_cpu, r1, r2, r3, r4, r5),
                     sp: set_sp(
                         old_cpu.sp,
@@ -63,9 +72,14 @@ impl Armv7m {
                 }
             }
     )]
-    pub fn push(&mut self, r1: GPR, r2: GPR, r3: GPR, r4: GPR, r5: SpecialRegister) {
+    pub fn push(&mut self, r1: GPR, r2: GPR, r3: GPR, r4: GPR, r5: Reg) {
         // NOTE: using set number of registers because of instrs we need to report
-        let mut sp = self.get_value_from_special_reg(&SpecialRegister::sp()) - BV32::from(0x14);
+        let mut sp = self.get_value_from_reg(&Reg::sp()) - BV32::from(0x14);
+        if !(self.check_data_accesses_4(sp, true)
+            && self.check_data_access(sp + BV32::from(0x10), true))
+        {
+            return;
+        }
 
         // NOTE: write the lowest first - in this case we can cheat a little bit
         // because we know that the args passed go from least to greatest
@@ -86,10 +100,10 @@ impl Armv7m {
         self.mem.write(sp, val4);
         sp = sp + BV32::from(0x4);
 
-        let val5 = self.get_value_from_special_reg(&r5);
+        let val5 = self.get_value_from_reg(&r5);
         self.mem.write(sp, val5);
         sp = sp + BV32::from(0x4);
 
-        self.update_special_reg_with_b32(SpecialRegister::sp(), sp - BV32::from(0x14));
+        self.update_reg_with_b32(Reg::sp(), sp - BV32::from(0x14));
     }
 }
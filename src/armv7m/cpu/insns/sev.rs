@@ -0,0 +1,23 @@
+use super::super::Armv7m;
+
+impl Armv7m {
+    // SEV (see SEV in the manual)
+    //
+    // Send Event is a hint instruction. It causes an event to be signaled to all processors in a multiprocessor
+    // system.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  SendEvent();
+    //
+    // NOTE: there is only one core so the event goes to our own event register
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m { event: true, ..old_cpu } }
+    )]
+    pub fn sev(&mut self) {
+        self.event = true;
+    }
+}
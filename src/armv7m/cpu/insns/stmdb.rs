@@ -1,6 +1,6 @@
 use crate::armv7m::{
     cpu::Armv7m,
-    lang::{SpecialRegister, GPR},
+    lang::{Reg, GPR},
 };
 use flux_rs::bitvec::BV32;
 
@@ -8,18 +8,36 @@ flux_rs::defs! {}
 
 impl Armv7m {
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@old_cpu], SpecialRegister[@rd], GPR[@r1], GPR[@r2], GPR[@r3])
+        fn (self: &strg Armv7m[@old_cpu], Reg[@rd], GPR[@r1], GPR[@r2], GPR[@r3])
             requires
-                is_valid_ram_addr(get_special_reg(rd, old_cpu))
+                !is_pc(rd)
                 &&
-                is_valid_ram_addr(get_special_reg(rd, old_cpu) - 0xc)
-            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_stmdb_wback(old_cpu, rd, r1, r2, r3) }
+                is_valid_ram_addr(get_reg(rd, old_cpu), old_cpu.mem)
+                &&
+                is_valid_ram_addr(get_reg(rd, old_cpu) - 0xc, old_cpu.mem)
+            ensures self: Armv7m { new_cpu:
+                (
+                    data_access_ok(get_reg(rd, old_cpu) - 0xc, true, old_cpu)
+                    &&
+                    data_access_ok(get_reg(rd, old_cpu) - 0x8, true, old_cpu)
+                    &&
+                    data_access_ok(get_reg(rd, old_cpu) - 0x4, true, old_cpu)
+                )
+                =>
+                new_cpu == cpu_post_stmdb_wback(old_cpu, rd, r1, r2, r3)
+            }
 
     )]
-    pub fn stmdb_wback(&mut self, rd: SpecialRegister, r1: GPR, r2: GPR, r3: GPR) {
+    pub fn stmdb_wback(&mut self, rd: Reg, r1: GPR, r2: GPR, r3: GPR) {
         // this is identical to push - especially in this case because it is only
         // used with sp!
-        let mut addr = self.get_value_from_special_reg(&rd) - BV32::from(0xc);
+        let mut addr = self.get_value_from_reg(&rd) - BV32::from(0xc);
+        if !(self.check_data_access(addr, true)
+            && self.check_data_access(addr + BV32::from(0x4), true)
+            && self.check_data_access(addr + BV32::from(0x8), true))
+        {
+            return;
+        }
 
         // note - goes in order from lowest register but we can cheat a bit because we know
         // these are passed in order in our case
@@ -35,6 +53,6 @@ impl Armv7m {
         self.mem.write(addr, val3);
         addr = addr + BV32::from(0x4);
 
-        self.update_special_reg_with_b32(rd, addr - BV32::from(0xc));
+        self.update_reg_with_b32(rd, addr - BV32::from(0xc));
     }
 }
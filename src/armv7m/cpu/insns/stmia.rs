@@ -1,6 +1,6 @@
 use crate::armv7m::{
     cpu::Armv7m,
-    lang::{SpecialRegister, GPR},
+    lang::{Reg, GPR},
 };
 use flux_rs::bitvec::BV32;
 
@@ -9,7 +9,7 @@ impl Armv7m {
     #[flux_rs::sig(
         fn (
             self: &strg Armv7m[@old_cpu],
-            GPR[@rd],
+            Reg[@rd],
             GPR[@rm1],
             GPR[@rm2],
             GPR[@rm3],
@@ -20,22 +20,31 @@ impl Armv7m {
             GPR[@rm8],
         )
         requires
-            is_valid_write_addr(get_gpr(rd, old_cpu))
+            !is_pc(rd)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0x4)
+            is_valid_write_addr(get_reg(rd, old_cpu), old_cpu.mem)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0x8)
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0x4, old_cpu.mem)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0xc)
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0x8, old_cpu.mem)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0x10)
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0xc, old_cpu.mem)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0x14)
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0x10, old_cpu.mem)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0x18)
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0x14, old_cpu.mem)
             &&
-            is_valid_write_addr(get_gpr(rd, old_cpu) + 0x1c)
-        ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0x18, old_cpu.mem)
+            &&
+            is_valid_write_addr(get_reg(rd, old_cpu) + 0x1c, old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            (
+                data_accesses_ok_4(get_reg(rd, old_cpu), true, old_cpu)
+                &&
+                data_accesses_ok_4(get_reg(rd, old_cpu) + 0x10, true, old_cpu)
+            )
+            =>
+            new_cpu == Armv7m {
                 mem: mem_post_stmia_w(old_cpu, rd, rm1, rm2, rm3, rm4, rm5, rm6, rm7, rm8),
                 ..old_cpu
             }
@@ -43,7 +52,7 @@ impl Armv7m {
     )]
     pub fn stmia_w(
         &mut self,
-        rd: GPR,
+        rd: Reg,
         rm1: GPR,
         rm2: GPR,
         rm3: GPR,
@@ -53,7 +62,12 @@ impl Armv7m {
         rm7: GPR,
         rm8: GPR,
     ) {
-        let mut addr = self.get_value_from_general_reg(&rd);
+        let mut addr = self.get_value_from_reg(&rd);
+        if !(self.check_data_accesses_4(addr, true)
+            && self.check_data_accesses_4(addr + BV32::from(0x10), true))
+        {
+            return;
+        }
         let val = self.get_value_from_general_reg(&rm1);
         self.mem.write(addr, val);
         addr = addr + BV32::from(0x4);
@@ -1,4 +1,4 @@
-use crate::armv7m::{cpu::Armv7m, lang::GPR};
+use crate::armv7m::{cpu::Armv7m, lang::Reg};
 use flux_rs::bitvec::BV32;
 
 impl Armv7m {
@@ -15,26 +15,38 @@ impl Armv7m {
     //  offset = Shift(R[m], shift_t, shift_n, APSR.C); address = R[n] + offset;
     //  MemU[address,4] = R[t];
 
-    // NOTE: Dest cannot be LR, PC, or SP
+    // NOTE: Rt & Rn cannot be PC and Rm cannot be SP or PC
     #[flux_rs::sig(fn (
             self: &strg Armv7m[@old_cpu],
-            GPR[@reg_to_store],
-            GPR[@reg_base],
-            GPR[@reg_offset],
+            Reg[@reg_to_store],
+            Reg[@reg_base],
+            Reg[@reg_offset],
             BV32[@shift]
         )
         requires
+            !is_pc(reg_to_store)
+            &&
+            !is_pc(reg_base)
+            &&
+            !is_sp(reg_offset)
+            &&
+            !is_pc(reg_offset)
+            &&
             is_valid_write_addr(
-                    get_gpr(reg_base, old_cpu) +
-                    (get_gpr(reg_offset, old_cpu) << shift)
+                    get_reg(reg_base, old_cpu) +
+                    (get_reg(reg_offset, old_cpu) << shift),
+                    old_cpu.mem
             )
-        ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_reg(reg_base, old_cpu) + (get_reg(reg_offset, old_cpu) << shift), true, old_cpu)
+            =>
+            new_cpu == Armv7m {
                 mem: update_mem(
-                            get_gpr(reg_base, old_cpu) +
-                            (get_gpr(reg_offset, old_cpu) << shift)
+                            get_reg(reg_base, old_cpu) +
+                            (get_reg(reg_offset, old_cpu) << shift)
                         ,
                         old_cpu.mem,
-                        get_gpr(reg_to_store, old_cpu)
+                        get_reg(reg_to_store, old_cpu)
                 ),
                 ..old_cpu
             }
@@ -42,9 +54,9 @@ impl Armv7m {
     )]
     pub fn strw_lsl_reg(
         &mut self,
-        register_to_str: GPR,
-        base_reg: GPR,
-        offset_reg: GPR,
+        register_to_str: Reg,
+        base_reg: Reg,
+        offset_reg: Reg,
         shift: BV32,
     ) {
         // Corresponds to encoding T2 of Str (register)
@@ -56,31 +68,47 @@ impl Armv7m {
         //  index = TRUE; add = TRUE; wback = FALSE;
         //  (shift_t, shift_n) = (SRType_LSL, UInt(imm2));
         //  if t == 15 || m IN {13,15} then UNPREDICTABLE;
-        let offset = self.get_value_from_general_reg(&offset_reg) << shift;
-        let addr = (self.get_value_from_general_reg(&base_reg) + offset);
-        let value = self.get_value_from_general_reg(&register_to_str);
+        let offset = self.get_value_from_reg(&offset_reg) << shift;
+        let addr = (self.get_value_from_reg(&base_reg) + offset);
+        if !self.check_data_access(addr, true) {
+            return;
+        }
+        let value = self.get_value_from_reg(&register_to_str);
         self.mem.write(addr, value);
     }
 
+    // STR (immediate) with no offset - Rn can be SP (str rt, [sp]) but
+    // neither Rt nor Rn can be the pc
     #[flux_rs::sig(fn (
             self: &strg Armv7m[@old_cpu],
-            GPR[@rt],
-            GPR[@rn],
+            Reg[@rt],
+            Reg[@rn],
         )
-        requires is_valid_write_addr(get_gpr(rn, old_cpu))
-        ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+        requires
+            !is_pc(rt)
+            &&
+            !is_pc(rn)
+            &&
+            is_valid_write_addr(get_reg(rn, old_cpu), old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_reg(rn, old_cpu), true, old_cpu)
+            =>
+            new_cpu == Armv7m {
                 mem: update_mem(
-                        get_gpr(rn, old_cpu),
+                        get_reg(rn, old_cpu),
                         old_cpu.mem,
-                        get_gpr(rt, old_cpu)
+                        get_reg(rt, old_cpu)
                 ),
                 ..old_cpu
             }
         }
     )]
-    pub fn str_no_wback(&mut self, rt: GPR, rn: GPR) {
-        let addr = self.get_value_from_general_reg(&rn);
-        let val = self.get_value_from_general_reg(&rt);
+    pub fn str_no_wback(&mut self, rt: Reg, rn: Reg) {
+        let addr = self.get_value_from_reg(&rn);
+        if !self.check_data_access(addr, true) {
+            return;
+        }
+        let val = self.get_value_from_reg(&rt);
         self.mem.write(addr, val);
     }
 }
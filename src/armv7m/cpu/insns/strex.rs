@@ -0,0 +1,228 @@
+use crate::armv7m::{cpu::Armv7m, lang::GPR};
+
+use flux_rs::bitvec::BV32;
+
+flux_rs::defs! {
+    // A STREX writes status 0 and the value when the monitor passes and status
+    // 1 otherwise - either way it leaves the monitor Open Access
+    fn cpu_post_strex(cpu: Armv7m, rd: int, rt: int, address: BV32) -> Armv7m {
+        if exclusive_monitors_pass(address, cpu) {
+            Armv7m {
+                general_regs: set_gpr(rd, cpu, 0),
+                mem: update_mem(address, cpu.mem, get_gpr(rt, cpu)),
+                monitor: clear_exclusive_local(cpu),
+                ..cpu
+            }
+        } else {
+            Armv7m {
+                general_regs: set_gpr(rd, cpu, 1),
+                monitor: clear_exclusive_local(cpu),
+                ..cpu
+            }
+        }
+    }
+
+    fn cpu_post_strexb(cpu: Armv7m, rd: int, rt: int, address: BV32) -> Armv7m {
+        if exclusive_monitors_pass(address, cpu) {
+            Armv7m {
+                general_regs: set_gpr(rd, cpu, 0),
+                mem: update_mem_byte(address, cpu.mem, get_gpr(rt, cpu)),
+                monitor: clear_exclusive_local(cpu),
+                ..cpu
+            }
+        } else {
+            Armv7m {
+                general_regs: set_gpr(rd, cpu, 1),
+                monitor: clear_exclusive_local(cpu),
+                ..cpu
+            }
+        }
+    }
+
+    fn cpu_post_strexh(cpu: Armv7m, rd: int, rt: int, address: BV32) -> Armv7m {
+        if exclusive_monitors_pass(address, cpu) {
+            Armv7m {
+                general_regs: set_gpr(rd, cpu, 0),
+                mem: update_mem_halfword(address, cpu.mem, get_gpr(rt, cpu)),
+                monitor: clear_exclusive_local(cpu),
+                ..cpu
+            }
+        } else {
+            Armv7m {
+                general_regs: set_gpr(rd, cpu, 1),
+                monitor: clear_exclusive_local(cpu),
+                ..cpu
+            }
+        }
+    }
+}
+
+impl Armv7m {
+    // STREX (see STREX in the manual)
+    //
+    // Store Register Exclusive calculates an address from a base register value and an immediate offset, and stores a
+    // word from a register to memory if the executing processor has exclusive access to the memory addressed.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  address = R[n] + imm32;
+    //  if ExclusiveMonitorsPass(address,4) then
+    //      MemA[address,4] = R[t];
+    //      R[d] = ZeroExtend('0');
+    //  else
+    //      R[d] = ZeroExtend('1');
+    //
+    // NOTE: ExclusiveMonitorsPass clears the local monitor whether it passes
+    // or not. Like LDREX the address has to be aligned. The manual leaves it
+    // IMPLEMENTATION DEFINED whether the MPU check comes before the monitor
+    // check - we check first so a STREX to an address the code can not write
+    // faults even when the monitor would fail it.
+    #[flux_rs::sig(
+        fn (
+            self: &strg Armv7m[@old_cpu],
+            GPR[@rd],
+            GPR[@rt],
+            GPR[@rn],
+            BV32[@imm]
+        )
+        requires
+            rd != rt && rd != rn
+            &&
+            is_valid_write_addr(get_gpr(rn, old_cpu) + imm, old_cpu.mem)
+            &&
+            word_aligned(get_gpr(rn, old_cpu) + imm)
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_gpr(rn, old_cpu) + imm, true, old_cpu)
+            =>
+            new_cpu == cpu_post_strex(old_cpu, rd, rt, get_gpr(rn, old_cpu) + imm)
+        }
+    )]
+    pub fn strex(&mut self, rd: GPR, rt: GPR, rn: GPR, imm: BV32) {
+        // Corresponds to encoding T1 of STREX
+        //
+        // Specific encoding ops are:
+        //
+        //  d = UInt(Rd); t = UInt(Rt); n = UInt(Rn); imm32 = ZeroExtend(imm8:'00', 32);
+        //  if d IN {13,15} || t IN {13,15} || n == 15 then UNPREDICTABLE;
+        //  if d == n || d == t then UNPREDICTABLE;
+        let address = self.get_value_from_general_reg(&rn) + imm;
+        if !self.check_data_access(address, true) {
+            return;
+        }
+        if self.exclusive_monitors_pass(address) {
+            let value = self.get_value_from_general_reg(&rt);
+            self.mem.write(address, value);
+            self.update_general_reg_with_b32(rd, BV32::from(0));
+        } else {
+            self.update_general_reg_with_b32(rd, BV32::from(1));
+        }
+        self.clear_exclusive_local();
+    }
+
+    // STREXB (see STREXB in the manual)
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  address = R[n];
+    //  if ExclusiveMonitorsPass(address,1) then
+    //      MemA[address,1] = R[t]<7:0>;
+    //      R[d] = ZeroExtend('0');
+    //  else
+    //      R[d] = ZeroExtend('1');
+    #[flux_rs::sig(
+        fn (
+            self: &strg Armv7m[@old_cpu],
+            GPR[@rd],
+            GPR[@rt],
+            GPR[@rn]
+        )
+        requires
+            rd != rt && rd != rn
+            &&
+            is_valid_write_access(get_gpr(rn, old_cpu), 1, old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_gpr(rn, old_cpu), true, old_cpu)
+            =>
+            new_cpu == cpu_post_strexb(old_cpu, rd, rt, get_gpr(rn, old_cpu))
+        }
+    )]
+    pub fn strexb(&mut self, rd: GPR, rt: GPR, rn: GPR) {
+        // Corresponds to encoding T1 of STREXB
+        //
+        // Specific encoding ops are:
+        //
+        //  d = UInt(Rd); t = UInt(Rt); n = UInt(Rn);
+        //  if d IN {13,15} || t IN {13,15} || n == 15 then UNPREDICTABLE;
+        //  if d == n || d == t then UNPREDICTABLE;
+        let address = self.get_value_from_general_reg(&rn);
+        if !self.check_data_access(address, true) {
+            return;
+        }
+        if self.exclusive_monitors_pass(address) {
+            let value = self.get_value_from_general_reg(&rt);
+            self.mem.write8(address, value);
+            self.update_general_reg_with_b32(rd, BV32::from(0));
+        } else {
+            self.update_general_reg_with_b32(rd, BV32::from(1));
+        }
+        self.clear_exclusive_local();
+    }
+
+    // STREXH (see STREXH in the manual)
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  address = R[n];
+    //  if ExclusiveMonitorsPass(address,2) then
+    //      MemA[address,2] = R[t]<15:0>;
+    //      R[d] = ZeroExtend('0');
+    //  else
+    //      R[d] = ZeroExtend('1');
+    #[flux_rs::sig(
+        fn (
+            self: &strg Armv7m[@old_cpu],
+            GPR[@rd],
+            GPR[@rt],
+            GPR[@rn]
+        )
+        requires
+            rd != rt && rd != rn
+            &&
+            is_valid_write_access(get_gpr(rn, old_cpu), 2, old_cpu.mem)
+            &&
+            halfword_aligned(get_gpr(rn, old_cpu))
+        ensures self: Armv7m { new_cpu:
+            data_access_ok(get_gpr(rn, old_cpu), true, old_cpu)
+            =>
+            new_cpu == cpu_post_strexh(old_cpu, rd, rt, get_gpr(rn, old_cpu))
+        }
+    )]
+    pub fn strexh(&mut self, rd: GPR, rt: GPR, rn: GPR) {
+        // Corresponds to encoding T1 of STREXH
+        //
+        // Specific encoding ops are:
+        //
+        //  d = UInt(Rd); t = UInt(Rt); n = UInt(Rn);
+        //  if d IN {13,15} || t IN {13,15} || n == 15 then UNPREDICTABLE;
+        //  if d == n || d == t then UNPREDICTABLE;
+        let address = self.get_value_from_general_reg(&rn);
+        if !self.check_data_access(address, true) {
+            return;
+        }
+        if self.exclusive_monitors_pass(address) {
+            let value = self.get_value_from_general_reg(&rt);
+            // aligned so this can not fault
+            self.mem.write16(address, value).unwrap();
+            self.update_general_reg_with_b32(rd, BV32::from(0));
+        } else {
+            self.update_general_reg_with_b32(rd, BV32::from(1));
+        }
+        self.clear_exclusive_local();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::armv7m::lang::GPR;
+use crate::armv7m::lang::{Reg, GPR};
 use flux_rs::bitvec::BV32;
 
 use super::super::Armv7m;
@@ -34,11 +34,48 @@ impl Armv7m {
         // if d == 13 || (d == 15 && S == '0') || n == 15 then UNPREDICTABLE;
         //
         // We already know d & n (register aboves) & that there is no S bit set so no SEE CMP
-        // Also Rn == '1101' is the SP which we are not dealing with for now so no SEE SUB
+        // Also Rn == '1101' is the SP which sub_imm handles so no SEE SUB
 
         // VTOCK TODO: Inspect ThumbExpandImm (same as ThumbExpandImm_C ignoring the carry flag)
         let val1 = self.get_value_from_general_reg(&value1);
         let res = val1 - value2;
         self.update_general_reg_with_b32(register, res);
     }
+
+    // SUB (immediate) & SUB (SP minus immediate) (see SUB (immediate) and
+    // SUB (SP minus immediate) in the manual)
+    //
+    // Pseudo code provided by arm:
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  (result, carry, overflow) = AddWithCarry(R[n], NOT(imm32), '1');
+    //  R[d] = result;
+    //  if setflags then
+    //      APSR.N = result<31>;
+    //      APSR.Z = IsZeroBit(result);
+    //      APSR.C = carry;
+    //      APSR.V = overflow;
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@rd], Reg[@rn], BV32[@imm])
+            requires sp_imm_operands_ok(rd, rn)
+            ensures self: Armv7m { new_cpu: new_cpu == set_reg(rd, old_cpu, get_reg(rn, old_cpu) - imm) }
+    )]
+    pub fn sub_imm(&mut self, rd: Reg, rn: Reg, imm: BV32) {
+        // Corresponds to encoding T3 of SUB (immediate) and T2 of SUB (SP
+        // minus immediate) - sub sp, sp, #imm is T1
+        //
+        // Specific encoding ops are:
+        //
+        //  SUB (immediate):
+        //  d = UInt(Rd); n = UInt(Rn); setflags = (S == '1'); imm32 = ThumbExpandImm(i:imm3:imm8);
+        //  if d == 13 || (d == 15 && S == '0') || n == 15 then UNPREDICTABLE;
+        //
+        //  SUB (SP minus immediate):
+        //  d = UInt(Rd); setflags = (S == '1'); imm32 = ThumbExpandImm(i:imm3:imm8);
+        //  if d == 15 && S == '0' then UNPREDICTABLE;
+        //
+        // VTOCK TODO: Flag Updates
+        let value = self.get_value_from_reg(&rn) - imm;
+        self.update_reg_with_b32(rd, value);
+    }
 }
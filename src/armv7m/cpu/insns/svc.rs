@@ -1,18 +1,36 @@
-use crate::armv7m::cpu::Armv7m;
+use crate::armv7m::{cpu::Armv7m, lang::ExceptionNumber};
+use flux_rs::bitvec::BV32;
 
 impl Armv7m {
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@cpu], u8[@svc_num]) 
-            requires 
+        fn (self: &strg Armv7m[@cpu], u8[@svc_num])
+            requires
+                // the instruction being executed is SVC #svc_num
+                is_svc_instr(cpu.pc, svc_num, cpu.mem)
+                &&
+                kernel_statics_in_ram(cpu.mem)
+                &&
                 // Stack Pointer is valid and can grow downwards 20 bytes
                 sp_can_handle_exception_entry(cpu)
                 &&
                 // and Stack Pointer used on exit is valid and can grow upwards 20 bytes
                 sp_can_handle_preempt_exception_exit(cpu, 11)
-            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_preempt(cpu, 11) }
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_svc(cpu) }
     )]
-    pub fn svc(&mut self, svc_num: u8) {
-        // TODO: Finish this instruction
-        self.preempt(11);
+    pub fn svc(&mut self, _svc_num: u8) {
+        // SVC (see p. A7-455 of the manual)
+        //
+        // Encoding T1: SVC<c> #<imm8> -> 0xDF00 | imm8
+        //
+        // The immediate only lives in the instruction encoding and is ignored
+        // by the processor. Handlers recover it by reading the halfword at the
+        // stacked return address - 2 (see Armv7m::svc_number) - so svc_num is
+        // only used by the precondition, which ties it to the instruction in
+        // memory.
+        //
+        // The return address stacked on exception entry is the instruction
+        // after the SVC, which is 2 bytes long
+        self.pc = self.pc + BV32::from(0x2);
+        self.preempt(ExceptionNumber::SVCall);
     }
 }
@@ -0,0 +1,39 @@
+use super::super::Armv7m;
+
+flux_rs::defs! {
+    fn cpu_post_wfe(cpu: Armv7m) -> Armv7m {
+        if cpu.event {
+            Armv7m { event: false, ..cpu }
+        } else {
+            Armv7m { sleep: sleep_post_wfe(cpu), ..cpu }
+        }
+    }
+}
+
+impl Armv7m {
+    // WFE (see WFE in the manual)
+    //
+    // Wait For Event is a hint instruction. If the Event Register is clear, it suspends execution in the lowest power
+    // state available consistent with a fast wakeup without the need for software restoration, until a reset,
+    // exception or other event occurs.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  if EventRegistered() then
+    //      ClearEventRegister();
+    //  else
+    //      WaitForEvent();
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_wfe(old_cpu) }
+    )]
+    pub fn wfe(&mut self) {
+        if self.event {
+            self.event = false;
+        } else {
+            self.enter_sleep(true);
+        }
+    }
+}
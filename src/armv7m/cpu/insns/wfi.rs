@@ -0,0 +1,25 @@
+use super::super::Armv7m;
+
+impl Armv7m {
+    // WFI (see WFI in the manual)
+    //
+    // Wait For Interrupt is a hint instruction. It suspends execution, in the lowest power state available consistent
+    // with a fast wakeup without the need for software restoration, until a reset, asynchronous exception or other
+    // event occurs.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  WaitForInterrupt();
+    //
+    // NOTE: the core sleeps until wake_up finds an interrupt pending (see
+    // cpu/sleep.rs)
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m { sleep: sleep_post_wfi(old_cpu), ..old_cpu } }
+    )]
+    pub fn wfi(&mut self) {
+        self.enter_sleep(false);
+    }
+}
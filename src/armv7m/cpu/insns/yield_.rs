@@ -0,0 +1,21 @@
+use super::super::Armv7m;
+
+impl Armv7m {
+    // YIELD (see YIELD in the manual)
+    //
+    // YIELD is a hint instruction. It enables software with a multithreading capability to indicate to the hardware
+    // that it is performing a task, for example a spinlock, that could be swapped out to improve overall system
+    // performance.
+    //
+    // Pseudo code provided by arm:
+    //
+    // if ConditionPassed() then
+    //  EncodingSpecificOperations();
+    //  Hint_Yield();
+    //
+    // NOTE: yield is a keyword so the method is yield_
+    #[flux_rs::sig(fn (self: &strg Armv7m[@cpu]) ensures self: Armv7m[cpu])]
+    pub fn yield_(&mut self) {
+        // a no-op in our case
+    }
+}
@@ -1,4 +1,4 @@
-use crate::armv7m::lang::{IsbOpt, SpecialRegister, GPR};
+use crate::armv7m::lang::{IsbOpt, Reg, SysReg, GPR};
 use flux_rs::bitvec::BV32;
 
 use super::{Armv7m, Control};
@@ -18,14 +18,14 @@ impl Armv7m {
         // r0 = 0
         self.movw_imm(GPR::R0, BV32::from(0));
         // control = r0 = 0
-        self.msr(SpecialRegister::Control, GPR::R0);
+        self.msr(SysReg::Control, GPR::R0);
         // isb
         self.isb(Some(IsbOpt::Sys));
         // NOTE: using pseudo instr here
         // lr = 0xFFFFFFF9
-        self.pseudo_ldr_special(SpecialRegister::lr(), BV32::from(0xFFFFFFF9));
+        self.pseudo_ldr(Reg::lr(), BV32::from(0xFFFFFFF9));
         // r0 = ipsr
-        self.mrs(GPR::R0, SpecialRegister::IPSR);
+        self.mrs(GPR::R0, SysReg::Ipsr);
         // Note: this seems to be a useless instruction?
         self.and_imm(GPR::R0, BV32::from(0xff));
         // r0 = ipsr - 16
@@ -44,46 +44,57 @@ impl Armv7m {
         // annoying
         //
         // Gonna encode this as a pseudo instruction for now
-        self.pseudo_ldr(GPR::R3, BV32::from(0xe000_e180));
+        self.pseudo_ldr(Reg::gpr(GPR::R3), BV32::from(0xe000_e180));
         // r0 = 1 << (ipsr - 16 & 31)
         // r3 = 0xe000_e180
         // r2 = (ipsr - 16 >> 5)
-        self.strw_lsl_reg(GPR::R0, GPR::R3, GPR::R2, BV32::from(2));
+        self.strw_lsl_reg(
+            Reg::gpr(GPR::R0),
+            Reg::gpr(GPR::R3),
+            Reg::gpr(GPR::R2),
+            BV32::from(2),
+        );
         // Note: Ignoring the dissasembled version of this because dealing with program counter is
         // annoying
         //
         // Gonna encode this as a pseudo instruction for now
-        self.pseudo_ldr(GPR::R3, BV32::from(0xe000_e200));
+        self.pseudo_ldr(Reg::gpr(GPR::R3), BV32::from(0xe000_e200));
         // r0 = 1 << (ipsr - 16 & 31)
         // r3 = 0xe000_e200
         // r2 = (ipsr - 16 >> 5) << 2
         //
         // mem[0xe000_e200 + ((ipsr - 16 >> 5) << 2)] = (1 << ipsr - 16 & 31) i.e. "bit for the ipsr # is set"
-        self.strw_lsl_reg(GPR::R0, GPR::R3, GPR::R2, BV32::from(2));
-        // self.bx(SpecialRegister::Lr);
-        return self.get_value_from_special_reg(&SpecialRegister::lr());
+        self.strw_lsl_reg(
+            Reg::gpr(GPR::R0),
+            Reg::gpr(GPR::R3),
+            Reg::gpr(GPR::R2),
+            BV32::from(2),
+        );
+        // self.bx(Reg::lr());
+        return self.get_value_from_reg(&Reg::lr());
     }
 
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@old_cpu]) -> BV32[0xFFFF_FFF9]
-            requires mode_is_handler(old_cpu.mode)
+            requires mode_is_handler(old_cpu.mode) && kernel_statics_in_ram(old_cpu.mem)
             ensures self: Armv7m { new_cpu: new_cpu == cpu_post_svc_to_kernel_isr(old_cpu) }
     )]
     fn svc_isr_to_kernel(&mut self) -> BV32 {
-        // sys call fired is a pub static mut so it has some location -
-        // giving it an arbitrary ram addr
+        // sys call fired is a pub static mut so it has some location - the
+        // chip's memory map says where it is linked
         //
         // basically we just want to set SYSCALL FIRED pub static mut to 1
-        self.pseudo_ldr(GPR::R0, BV32::from(0x8000_0000));
+        let syscall_fired = self.mem.kernel_statics_addr();
+        self.pseudo_ldr(Reg::gpr(GPR::R0), syscall_fired);
         self.movw_imm(GPR::R1, BV32::from(1));
-        self.str_no_wback(GPR::R1, GPR::R0);
+        self.str_no_wback(Reg::gpr(GPR::R1), Reg::gpr(GPR::R0));
         // now do everything else
         self.movw_imm(GPR::R0, BV32::from(0));
-        self.msr(SpecialRegister::Control, GPR::R0);
+        self.msr(SysReg::Control, GPR::R0);
         self.isb(Some(IsbOpt::Sys));
-        self.pseudo_ldr_special(SpecialRegister::lr(), BV32::from(0xFFFF_FFF9));
-        // self.bx(SpecialRegister::Lr);
-        return self.get_value_from_special_reg(&SpecialRegister::lr());
+        self.pseudo_ldr(Reg::lr(), BV32::from(0xFFFF_FFF9));
+        // self.bx(Reg::lr());
+        return self.get_value_from_reg(&Reg::lr());
     }
 
     #[flux_rs::sig(
@@ -93,26 +104,62 @@ impl Armv7m {
     )]
     fn svc_isr_to_app(&mut self) -> BV32 {
         self.movw_imm(GPR::R0, BV32::from(1));
-        self.msr(SpecialRegister::Control, GPR::R0);
+        self.msr(SysReg::Control, GPR::R0);
         self.isb(Some(IsbOpt::Sys));
-        self.pseudo_ldr_special(SpecialRegister::lr(), BV32::from(0xFFFF_FFFD));
-        return self.get_value_from_special_reg(&SpecialRegister::lr());
+        self.pseudo_ldr(Reg::lr(), BV32::from(0xFFFF_FFFD));
+        return self.get_value_from_reg(&Reg::lr());
     }
 
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@old_cpu]) -> BV32[svc_isr_ret_val(old_cpu)]
-            requires mode_is_handler(old_cpu.mode)
+            requires mode_is_handler(old_cpu.mode) && kernel_statics_in_ram(old_cpu.mem)
             ensures self: Armv7m { new_cpu: new_cpu == cpu_post_svc_isr(old_cpu) }
     )]
     pub fn svc_isr(&mut self) -> BV32 {
         // TODO: should really be a cmp & bne but tough to model that so using ite for now
-        if self.get_value_from_special_reg(&SpecialRegister::lr()) == BV32::from(0xFFFF_FFF9) {
+        if self.get_value_from_reg(&Reg::lr()) == BV32::from(0xFFFF_FFF9) {
             return self.svc_isr_to_app();
         } else {
             return self.svc_isr_to_kernel();
         }
     }
 
+    // Tock's hard_fault_handler_arm_v7m. Only the process fault path (taken
+    // when lr & 4 != 0, i.e. a process was running) returns - on the kernel
+    // path Tock panics so the handler never returns.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu]) -> BV32[0xFFFF_FFF9]
+            requires
+                mode_is_handler(old_cpu.mode)
+                &&
+                kernel_statics_in_ram(old_cpu.mem)
+                &&
+                isr_returns(3, get_reg(lr(), old_cpu))
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_hard_fault_isr(old_cpu) }
+    )]
+    pub fn hard_fault_isr(&mut self) -> BV32 {
+        // TODO: should really be a tst & beq but tough to model that so using ite for now
+        if self.get_value_from_reg(&Reg::lr()) & BV32::from(0x4) == BV32::from(0) {
+            panic!("Kernel HardFault");
+        }
+        // APP_HARD_FAULT is a pub static mut so it has some location - it is
+        // linked right after SYSCALL_FIRED
+        //
+        // basically we just want to set APP_HARD_FAULT to 1
+        let app_hard_fault = self.mem.kernel_statics_addr() + BV32::from(4);
+        self.pseudo_ldr(Reg::gpr(GPR::R0), app_hard_fault);
+        self.movw_imm(GPR::R1, BV32::from(1));
+        self.str_no_wback(Reg::gpr(GPR::R1), Reg::gpr(GPR::R0));
+        // NOTE: Tock copies CCR, CFSR, HFSR, MMFAR and BFAR into SCB_REGISTERS
+        // here for its panic message - we leave that out since it does not
+        // touch any state the kernel relies on
+        self.movw_imm(GPR::R0, BV32::from(0));
+        self.msr(SysReg::Control, GPR::R0);
+        self.isb(Some(IsbOpt::Sys));
+        self.pseudo_ldr(Reg::lr(), BV32::from(0xFFFF_FFF9));
+        return self.get_value_from_reg(&Reg::lr());
+    }
+
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@old_cpu]) -> BV32[0xFFFF_FFF9]
             requires mode_is_handler(old_cpu.mode)
@@ -120,9 +167,9 @@ impl Armv7m {
     )]
     pub fn sys_tick_isr(&mut self) -> BV32 {
         self.movw_imm(GPR::R0, BV32::from(0));
-        self.msr(SpecialRegister::Control, GPR::R0);
+        self.msr(SysReg::Control, GPR::R0);
         self.isb(Some(IsbOpt::Sys));
-        self.pseudo_ldr_special(SpecialRegister::lr(), BV32::from(0xFFFF_FFF9));
-        return self.get_value_from_special_reg(&SpecialRegister::lr());
+        self.pseudo_ldr(Reg::lr(), BV32::from(0xFFFF_FFF9));
+        return self.get_value_from_reg(&Reg::lr());
     }
 }
@@ -1,10 +1,21 @@
+mod access;
+mod call_stack;
 mod exception;
+mod fault;
 mod flux_defs;
 mod insns;
 mod isr;
 mod psr;
+mod reset;
+mod run;
+mod sleep;
+mod sys_tick;
+mod syscall;
 
-use super::lang::{SpecialRegister, GPR};
+pub use call_stack::CallFrame;
+pub use run::{Step, StopReason};
+
+use super::lang::{Reg, SpecialRegister, GPR};
 use super::mem::Memory;
 use crate::flux_support::rmap::Regs;
 use flux_defs::*;
@@ -54,12 +65,13 @@ pub enum CPUMode {
 
 #[derive(Debug)]
 #[flux_rs::refined_by(sp_main: BV32, sp_process: BV32)]
-#[flux_rs::invariant(is_valid_ram_addr(sp_main))]
-#[flux_rs::invariant(is_valid_ram_addr(sp_process))]
 pub struct SP {
-    #[field({ BV32[sp_main] | is_valid_ram_addr(sp_main) })]
+    // NOTE: neither stack pointer is required to point into RAM - software
+    // can put any value in them and exception entry faults when stacking
+    // to an invalid address
+    #[field(BV32[sp_main])]
     pub sp_main: BV32,
-    #[field({ BV32[sp_process] | is_valid_ram_addr(sp_process) })]
+    #[field(BV32[sp_process])]
     pub sp_process: BV32,
 }
 
@@ -77,16 +89,61 @@ pub struct Control {
     pub spsel: bool,
 }
 
+// The local exclusive monitor (see Synchronization and semaphores in the
+// manual)
+//
+// LDREX tags an address and moves the monitor to the Exclusive Access state,
+// a STREX only writes when the monitor is Exclusive for its address and
+// always leaves it Open Access, as do CLREX, exception entry and exception
+// return.
+//
+// NOTE: whether the local monitor compares addresses at all is
+// implementation defined (Cortex-M cores do not). We compare them, which only
+// makes a STREX fail in more cases than on hardware.
+#[derive(Debug)]
+#[flux_rs::refined_by(exclusive: bool, address: BV32)]
+pub struct LocalMonitor {
+    // false - Open Access, true - Exclusive Access
+    #[field(bool[exclusive])]
+    pub exclusive: bool,
+    // the address tagged by the last LDREX
+    #[field(BV32[address])]
+    pub address: BV32,
+}
+
+// Whether the core is asleep after a WFI, WFE or sleep-on-exit (see Power
+// management in the manual)
+#[derive(Debug)]
+#[flux_rs::refined_by(sleeping: bool, deep: bool, on_event: bool)]
+pub struct SleepState {
+    #[field(bool[sleeping])]
+    pub sleeping: bool,
+    // SCR.SLEEPDEEP was set when the core went to sleep
+    #[field(bool[deep])]
+    pub deep: bool,
+    // asleep in a WFE - the event register can wake the core too
+    #[field(bool[on_event])]
+    pub on_event: bool,
+}
+
 #[derive(Debug)]
 #[flux_rs::refined_by(
     general_regs: Map<GPR, BV32>,
     sp: SP,
     control: Control,
+    pending_control: Control,
     lr: BV32,
     pc: BV32,
     psr: BV32,
+    primask: BV32,
+    basepri: BV32,
+    faultmask: BV32,
     mem: Memory,
-    mode: CPUMode
+    mode: CPUMode,
+    lockup: bool,
+    monitor: LocalMonitor,
+    event: bool,
+    sleep: SleepState
 )]
 pub struct Armv7m {
     // General Registers r0 - r11
@@ -95,9 +152,16 @@ pub struct Armv7m {
     // Stack Pointer
     #[field(SP[sp])]
     pub sp: SP,
-    // Control register
+    // Control register - the value privilege & stack selection go by
     #[field(Control[control])]
     pub control: Control,
+    // The value last written to CONTROL. An MSR is only sure to take effect
+    // at the next context synchronization event - an ISB, exception entry or
+    // exception return (see Context synchronization event in the manual) -
+    // until then control may be either value (see insns/msr.rs). MRS reads
+    // this one.
+    #[field(Control[pending_control])]
+    pub pending_control: Control,
     // Program Counter
     #[field(BV32[pc])]
     pub pc: BV32,
@@ -107,12 +171,38 @@ pub struct Armv7m {
     // program status register
     #[field(BV32[psr])]
     pub psr: BV32,
+    // Mask registers
+    //
+    // primask is 1 bit - the rest being reserved
+    #[field(BV32[primask])]
+    pub primask: BV32,
+    // basepri is 8 bit - the rest being reserved
+    #[field(BV32[basepri])]
+    pub basepri: BV32,
+    // faultmask is 1 bit - the rest being reserved
+    #[field(BV32[faultmask])]
+    pub faultmask: BV32,
     // Memory
     #[field(Memory[mem])]
     pub mem: Memory,
     // current CPU mode
     #[field(CPUMode[mode])]
     pub mode: CPUMode,
+    // set when the core has entered the lockup state (see p. B1-549 of the manual)
+    #[field(bool[lockup])]
+    pub lockup: bool,
+    // local exclusive monitor
+    #[field(LocalMonitor[monitor])]
+    pub monitor: LocalMonitor,
+    // the event register WFE waits on (see The Wait For Event instruction in
+    // the manual)
+    #[field(bool[event])]
+    pub event: bool,
+    #[field(SleepState[sleep])]
+    pub sleep: SleepState,
+    // calls made through BL & BLX that have not returned yet (see
+    // call_stack.rs) - diagnostics only so not part of the refinement
+    call_stack: Vec<CallFrame>,
 }
 
 impl Armv7m {
@@ -120,26 +210,13 @@ impl Armv7m {
     fn get_value_from_special_reg(&self, register: &SpecialRegister) -> BV32 {
         match register {
             SpecialRegister::PSP => self.sp.sp_process,
-            SpecialRegister::Sp => {
-                // Thread mode: Main, else
-                // check spsel
-                // 0 use sp_main
-                // 1 In Thread mode, use SP_process as the current stack. In Handler mode, this value is reserved
-                if self.mode_is_handler() || !self.control.spsel {
-                    self.sp.sp_main
-                } else {
-                    self.sp.sp_process
-                }
-            }
-            SpecialRegister::Lr => self.lr,
-            SpecialRegister::Pc => self.pc,
             SpecialRegister::Control => {
-                if self.control.npriv && self.control.spsel {
+                if self.pending_control.npriv && self.pending_control.spsel {
                     BV32::from(3)
-                } else if self.control.npriv {
+                } else if self.pending_control.npriv {
                     // first bit is 1 - i.e. 01
                     BV32::from(1)
-                } else if self.control.spsel {
+                } else if self.pending_control.spsel {
                     // second bit is 1 - i.e. 10
                     BV32::from(2)
                 } else {
@@ -148,6 +225,27 @@ impl Armv7m {
             }
             SpecialRegister::PSR => self.psr,
             SpecialRegister::IPSR => self.psr & BV32::from(0xff),
+            SpecialRegister::MSP => self.sp.sp_main,
+        }
+    }
+
+    #[flux_rs::sig(fn (&Armv7m[@cpu], &Reg[@reg]) -> BV32[get_reg(reg, cpu)])]
+    fn get_value_from_reg(&self, register: &Reg) -> BV32 {
+        match register {
+            Reg::Gpr(gpr) => self.get_value_from_general_reg(gpr),
+            Reg::Sp => {
+                // Thread mode: Main, else
+                // check spsel
+                // 0 use sp_main
+                // 1 In Thread mode, use SP_process as the current stack. In Handler mode, this value is reserved
+                if self.mode_is_handler() || !self.control.spsel {
+                    self.sp.sp_main
+                } else {
+                    self.sp.sp_process
+                }
+            }
+            Reg::Lr => self.lr,
+            Reg::Pc => self.pc + BV32::from(0x4),
         }
     }
 
@@ -159,6 +257,45 @@ impl Armv7m {
         }
     }
 
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[mode_is_privileged(cpu.mode, cpu.control)])]
+    fn is_privileged(&self) -> bool {
+        self.mode_is_handler() || !self.control.npriv
+    }
+
+    // Writes to CONTROL take effect
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_context_sync(old_cpu) }
+    )]
+    fn context_synchronize(&mut self) {
+        self.control.npriv = self.pending_control.npriv;
+        self.control.spsel = self.pending_control.spsel;
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@addr])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m { monitor: set_exclusive_monitors(addr), ..old_cpu } }
+    )]
+    fn set_exclusive_monitors(&mut self, address: BV32) {
+        self.monitor.exclusive = true;
+        self.monitor.address = address;
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m { monitor: clear_exclusive_local(old_cpu), ..old_cpu } }
+    )]
+    fn clear_exclusive_local(&mut self) {
+        self.monitor.exclusive = false;
+    }
+
+    // Unlike the pseudo code's ExclusiveMonitorsPass this leaves the monitor
+    // alone - STREX clears it after the check
+    #[flux_rs::sig(fn (&Armv7m[@cpu], BV32[@addr]) -> bool[exclusive_monitors_pass(addr, cpu)])]
+    fn exclusive_monitors_pass(&self, address: BV32) -> bool {
+        self.monitor.exclusive && self.monitor.address == address
+    }
+
     #[flux_rs::sig(fn (BV32[@val], BV32[@n]) -> bool[nth_bit_is_set(val, n)])]
     fn nth_bit_set(value: BV32, n: BV32) -> bool {
         (value & (BV32::from(1) << n)) != BV32::from(0)
@@ -166,8 +303,6 @@ impl Armv7m {
 
     #[flux_rs::sig(
         fn (self: &strg Armv7m[@old_cpu], SpecialRegister[@reg], BV32[@val])
-            requires
-            (is_sp(reg) || is_psp(reg)) => is_valid_ram_addr(val)
             ensures self: Armv7m { new_cpu: new_cpu == set_spr(reg, old_cpu, val) }
     )]
     fn update_special_reg_with_b32(&mut self, register: SpecialRegister, value: BV32) {
@@ -175,27 +310,17 @@ impl Armv7m {
             SpecialRegister::PSP => {
                 self.sp.sp_process = value;
             }
-            SpecialRegister::Sp => {
-                if self.mode_is_handler() || !self.control.spsel {
-                    // updates sp_main
-                    self.sp.sp_main = value;
-                } else {
-                    self.sp.sp_process = value;
-                }
-            }
-            SpecialRegister::Lr => {
-                self.lr = value;
-            }
-            SpecialRegister::Pc => {
-                self.pc = value;
+            SpecialRegister::MSP => {
+                self.sp.sp_main = value;
             }
             SpecialRegister::Control => {
-                // TODO: This is only ok in privileged mode
+                // MSR checks privilege before it gets here (see insns/msr.rs).
+                // Takes effect at the next context synchronization event
                 let npriv_bit_set = Self::nth_bit_set(value, BV32::from(0));
-                self.control.npriv = npriv_bit_set;
+                self.pending_control.npriv = npriv_bit_set;
                 if !self.mode_is_handler() {
                     let spsel_bit_set = Self::nth_bit_set(value, BV32::from(1));
-                    self.control.spsel = spsel_bit_set;
+                    self.pending_control.spsel = spsel_bit_set;
                 }
             }
             SpecialRegister::PSR => self.psr = value,
@@ -215,6 +340,35 @@ impl Armv7m {
         self.general_regs.set(register, value);
     }
 
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], Reg[@reg], BV32[@val])
+            ensures self: Armv7m { new_cpu: new_cpu == set_reg(reg, old_cpu, val) }
+    )]
+    fn update_reg_with_b32(&mut self, register: Reg, value: BV32) {
+        match register {
+            Reg::Gpr(gpr) => self.update_general_reg_with_b32(gpr, value),
+            Reg::Sp => {
+                if self.mode_is_handler() || !self.control.spsel {
+                    // updates sp_main
+                    self.sp.sp_main = value;
+                } else {
+                    self.sp.sp_process = value;
+                }
+            }
+            Reg::Lr => {
+                self.lr = value;
+            }
+            Reg::Pc => {
+                // ALUWritePC & BranchWritePC branch to the value with bit 0
+                // cleared. Loads into the pc are LoadWritePC, which can return
+                // from an exception (see Armv7m::load_write_reg)
+                let target = value & BV32::from(0xFFFF_FFFE);
+                self.note_return(target);
+                self.pc = target;
+            }
+        }
+    }
+
     #[flux_rs::sig(fn (&Armv7m[@cpu], &GPR[@reg]) -> BV32[get_gpr(reg, cpu)])]
     fn get_value_from_general_reg(&self, register: &GPR) -> BV32 {
         *self.general_regs.get(register).unwrap()
@@ -0,0 +1,172 @@
+use crate::armv7m::lang::GPR;
+use crate::armv7m::mem::sys_control::VTOR_ADDR;
+use crate::armv7m::mem::Memory;
+use crate::flux_support::rmap::Regs;
+
+use super::{Armv7m, CPUMode, Control, LocalMonitor, SleepState, SP};
+
+use flux_rs::bitvec::BV32;
+
+flux_rs::defs! {
+    fn reset_vector_table(mem: Memory) -> BV32 {
+        // VTOR<31:7>:'0000000'
+        get_mem_addr(VTOR_ADDR, mem) & 0xFFFF_FF80
+    }
+
+    fn reset_sp_main(mem: Memory) -> BV32 {
+        get_mem_addr(reset_vector_table(mem), mem) & 0xFFFF_FFFC
+    }
+
+    fn reset_start(mem: Memory) -> BV32 {
+        get_mem_addr(reset_vector_table(mem) + 0x4, mem)
+    }
+
+    fn reset_precondition(mem: Memory) -> bool {
+        is_pure_read_addr(reset_vector_table(mem), mem)
+        &&
+        is_pure_read_addr(reset_vector_table(mem) + 0x4, mem)
+        &&
+        is_valid_ram_addr(reset_sp_main(mem), mem)
+    }
+}
+
+impl Armv7m {
+    // Reset behavior (see TakeReset on p. B1-531 of the manual)
+    //
+    // Pseudo code provided by arm:
+    //
+    //  CurrentMode = Mode_Thread;
+    //  LR = bits(32) UNKNOWN;
+    //  APSR = bits(32) UNKNOWN;
+    //  IPSR<8:0> = Zeros(9);
+    //  PRIMASK.PM = '0';
+    //  FAULTMASK.FM = '0';
+    //  BASEPRI<7:0> = Zeros(8);
+    //  CONTROL.SPSEL = '0';
+    //  CONTROL.nPRIV = '0';
+    //  ResetSCSRegs();
+    //  ExceptionActive[*] = '0';
+    //  ClearExclusiveLocal();
+    //  ClearEventRegister();
+    //  for i = 0 to 12
+    //      R[i] = bits(32) UNKNOWN;
+    //  bits(32) vectortable = VTOR<31:7>:'0000000';
+    //  SP_main = MemA_with_priv[vectortable, 4, AccType_VECTABLE] AND 0xFFFFFFFC<31:0>;
+    //  SP_process = ((bits(30) UNKNOWN):'00');
+    //  start = MemA_with_priv[vectortable+4, 4, AccType_VECTABLE];
+    //  EPSR.T = start<0>;
+    //  BranchTo(start AND 0xFFFFFFFE<31:0>);
+    //
+    // NOTE: The TBLOFF reset value is implementation defined so we use whatever
+    // VTOR holds in the memory we boot from. UNKNOWN registers are set to 0, LR
+    // is set to 0xFFFF_FFFF like Cortex-M cores do, and SP_process mirrors
+    // SP_main so that it starts out as a valid RAM address.
+    #[flux_rs::sig(
+        fn (&Memory[@mem]) -> Armv7m { cpu:
+            sp_main(cpu.sp) == reset_sp_main(mem)
+            &&
+            cpu.pc == reset_start(mem) & 0xFFFF_FFFE
+            &&
+            mode_is_thread_privileged(cpu.mode, cpu.control)
+            &&
+            mode_is_thread_privileged(cpu.mode, cpu.pending_control)
+            &&
+            cpu.psr == (reset_start(mem) & 0x1) << 24
+            &&
+            cpu.primask == 0
+            &&
+            cpu.basepri == 0
+            &&
+            cpu.faultmask == 0
+            &&
+            !cpu.lockup
+            &&
+            !cpu.monitor.exclusive
+            &&
+            !cpu.event
+            &&
+            !cpu.sleep.sleeping
+        }
+        requires reset_precondition(mem)
+    )]
+    pub fn reset(mem: &Memory) -> Armv7m {
+        let vtor_addr = VTOR_ADDR;
+        let vector_table = mem.peek(vtor_addr) & BV32::from(0xFFFF_FF80);
+        let sp_main = mem.peek(vector_table) & BV32::from(0xFFFF_FFFC);
+        let start = mem.peek(vector_table + BV32::from(0x4));
+
+        let mut mem = mem.duplicate();
+        mem.reset_ppb();
+
+        let mut general_regs = Regs::new();
+        general_regs.set(GPR::r0(), BV32::from(0));
+        general_regs.set(GPR::r1(), BV32::from(0));
+        general_regs.set(GPR::r2(), BV32::from(0));
+        general_regs.set(GPR::r3(), BV32::from(0));
+        general_regs.set(GPR::r4(), BV32::from(0));
+        general_regs.set(GPR::r5(), BV32::from(0));
+        general_regs.set(GPR::r6(), BV32::from(0));
+        general_regs.set(GPR::r7(), BV32::from(0));
+        general_regs.set(GPR::r8(), BV32::from(0));
+        general_regs.set(GPR::r9(), BV32::from(0));
+        general_regs.set(GPR::r10(), BV32::from(0));
+        general_regs.set(GPR::r11(), BV32::from(0));
+        general_regs.set(GPR::r12(), BV32::from(0));
+
+        Armv7m {
+            general_regs,
+            sp: SP {
+                sp_main,
+                sp_process: sp_main,
+            },
+            control: Control {
+                npriv: false,
+                spsel: false,
+            },
+            pending_control: Control {
+                npriv: false,
+                spsel: false,
+            },
+            pc: start & BV32::from(0xFFFF_FFFE),
+            lr: BV32::from(0xFFFF_FFFF),
+            // EPSR.T = start<0> and IPSR = 0
+            psr: (start & BV32::from(0x1)) << BV32::from(24),
+            primask: BV32::from(0),
+            basepri: BV32::from(0),
+            faultmask: BV32::from(0),
+            mem,
+            mode: CPUMode::Thread,
+            lockup: false,
+            // ClearExclusiveLocal()
+            monitor: LocalMonitor {
+                exclusive: false,
+                address: BV32::from(0),
+            },
+            // ClearEventRegister()
+            event: false,
+            sleep: SleepState {
+                sleeping: false,
+                deep: false,
+                on_event: false,
+            },
+            call_stack: Vec::new(),
+        }
+    }
+
+    // A reset requested through AIRCR.SYSRESETREQ - the core resets out of the
+    // image already in memory. The request stays latched in AIRCR until reset
+    // puts AIRCR back to its reset value.
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@cpu]) -> bool[sys_reset_requested(cpu.mem)]
+            requires sys_reset_requested(cpu.mem) => reset_precondition(cpu.mem)
+            ensures self: Armv7m { new_cpu: !sys_reset_requested(cpu.mem) => new_cpu == cpu }
+    )]
+    pub fn take_pending_reset(&mut self) -> bool {
+        if self.mem.sys_reset_requested() {
+            *self = Armv7m::reset(&self.mem);
+            true
+        } else {
+            false
+        }
+    }
+}
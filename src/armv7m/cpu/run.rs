@@ -0,0 +1,228 @@
+use crate::armv7m::mem::trace::{Access, Origin};
+
+use super::Armv7m;
+
+// Running a sequence of instructions concretely
+//
+// NOTE: we do not decode instructions so a program is a list of steps, each
+// naming the instruction it runs so memory accesses can be traced back to it
+// (see mem/trace.rs). Each step counts as one clock: run_until clocks
+// SysTick & the devices after it (see clock). A sleeping core (see
+// cpu/sleep.rs) runs no steps until something wakes it - run_until stops
+// rather than clock a core that may never wake, so a caller waiting on a
+// device clocks it and runs again.
+
+pub struct Step {
+    pub instruction: &'static str,
+    execute: Box<dyn FnOnce(&mut Armv7m)>,
+}
+
+impl Step {
+    pub fn new(instruction: &'static str, execute: impl FnOnce(&mut Armv7m) + 'static) -> Step {
+        Step {
+            instruction,
+            execute: Box::new(execute),
+        }
+    }
+}
+
+// Why run_until stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    // every step ran
+    Finished,
+    // the condition held after a step
+    Condition,
+    // a step made an access that hit a watchpoint
+    Watchpoint(Access),
+    // the core went to sleep with nothing pending to wake it
+    Asleep,
+}
+
+impl Armv7m {
+    // Runs one instruction with its memory accesses attributed to it. An
+    // instruction the MPU does not let the core fetch takes a MemManage fault
+    // instead of running.
+    #[flux_rs::trusted]
+    pub fn step(&mut self, step: Step) {
+        if !self.check_instruction_fetch() {
+            return;
+        }
+        let origin = Origin {
+            instruction: step.instruction,
+            pc: self.pc,
+            privileged: self.is_privileged(),
+        };
+        self.mem.set_access_origin(Some(origin));
+        (step.execute)(self);
+        self.mem.set_access_origin(None);
+    }
+
+    // One clock of everything outside the core: SysTick counts down and the
+    // devices tick & drive their interrupt lines
+    #[flux_rs::trusted]
+    pub fn clock(&mut self) {
+        self.tick();
+        self.mem.tick_devices();
+    }
+
+    // Runs steps until one hits a watchpoint, leaves the cpu in a state where
+    // until holds or leaves it asleep. The clock runs after every step.
+    #[flux_rs::trusted]
+    pub fn run_until(
+        &mut self,
+        steps: impl IntoIterator<Item = Step>,
+        mut until: impl FnMut(&Armv7m) -> bool,
+    ) -> StopReason {
+        let mut steps = steps.into_iter();
+        loop {
+            if self.is_sleeping() && !self.wake_up() {
+                return StopReason::Asleep;
+            }
+            let Some(step) = steps.next() else {
+                break;
+            };
+            self.step(step);
+            self.clock();
+            if let Some(access) = self.mem.take_watchpoint_hit() {
+                return StopReason::Watchpoint(access);
+            }
+            if until(self) {
+                return StopReason::Condition;
+            }
+        }
+        StopReason::Finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use flux_rs::bitvec::BV32;
+
+    use super::{Armv7m, Step, StopReason};
+    use crate::armv7m::chip::NRF52840;
+    use crate::armv7m::mem::mmio::MmioDevice;
+    use crate::armv7m::mem::Memory;
+
+    const TIMER_START: u32 = 0x4000_0000;
+    const TIMER_IRQ: u16 = 3;
+
+    // Stand-in for a peripheral timer: asserts its line once it has counted
+    // to fire_at until software writes to it
+    #[derive(Debug, Clone)]
+    struct Timer {
+        count: u32,
+        fire_at: u32,
+        acked: bool,
+    }
+
+    impl MmioDevice for Timer {
+        fn read(&mut self, _offset: BV32) -> BV32 {
+            BV32::from(self.count)
+        }
+
+        fn write(&mut self, _offset: BV32, _value: BV32) {
+            self.acked = true;
+        }
+
+        fn tick(&mut self) {
+            self.count += 1;
+        }
+
+        fn irqs(&self) -> Vec<u16> {
+            vec![TIMER_IRQ]
+        }
+
+        fn irq_lines(&self) -> Vec<u16> {
+            if self.count >= self.fire_at && !self.acked {
+                vec![TIMER_IRQ]
+            } else {
+                Vec::new()
+            }
+        }
+
+        fn duplicate(&self) -> Box<dyn MmioDevice> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn b(value: u32) -> BV32 {
+        BV32::from(value)
+    }
+
+    fn nrf52840_with_timer(fire_at: u32) -> Armv7m {
+        let mut mem = NRF52840.memory();
+        // initial sp & the reset handler
+        mem.load(b(0x0), b(0x2000_1000));
+        mem.load(b(0x4), b(0x101));
+        let timer = Timer {
+            count: 0,
+            fire_at,
+            acked: false,
+        };
+        mem.register_device(b(TIMER_START), b(TIMER_START + 0xFFF), Box::new(timer));
+        Armv7m::reset(&mem)
+    }
+
+    fn write(instruction: &'static str, address: u32, value: u32) -> Step {
+        Step::new(instruction, move |cpu: &mut Armv7m| {
+            cpu.mem.write(b(address), b(value))
+        })
+    }
+
+    fn nop() -> Step {
+        Step::new("nop", |_: &mut Armv7m| {})
+    }
+
+    #[test]
+    fn run_until_sys_tick_wraps() {
+        let mut cpu = nrf52840_with_timer(u32::MAX);
+        let mut steps = vec![
+            // SYST_RVR = 2, SYST_CVR = 0, SYST_CSR = ENABLE | TICKINT
+            write("str", 0xE000_E014, 2),
+            write("str", 0xE000_E018, 0),
+            write("str", 0xE000_E010, 0x3),
+        ];
+        steps.extend((0..8).map(|_| nop()));
+        let stop = cpu.run_until(steps, |cpu: &Armv7m| cpu.mem.sys_tick_pending());
+        assert_eq!(stop, StopReason::Condition);
+        // reloaded on the enabling step's clock then counted 2, 1, 0
+        assert_eq!(cpu.mem.peek(b(0xE000_E018)), b(0));
+        // reading SYST_CSR sees COUNTFLAG once
+        assert_eq!(cpu.mem.read(b(0xE000_E010)) & b(0x1_0000), b(0x1_0000));
+        assert_eq!(cpu.mem.read(b(0xE000_E010)) & b(0x1_0000), b(0));
+    }
+
+    #[test]
+    fn run_until_sleeps_until_device_interrupt() {
+        let mut cpu = nrf52840_with_timer(3);
+        let steps = vec![
+            // enable the timer's interrupt then wait for it
+            write("str", 0xE000_E100, 1 << TIMER_IRQ),
+            Step::new("wfi", |cpu: &mut Armv7m| cpu.wfi()),
+            nop(),
+        ];
+        let stop = cpu.run_until(steps, |_: &Armv7m| false);
+        // two clocks in, the timer has not fired yet
+        assert_eq!(stop, StopReason::Asleep);
+        assert!(cpu.is_sleeping());
+
+        // the third clock pends the timer's interrupt which wakes the core
+        cpu.clock();
+        assert!(cpu.mem.nvic_irq_pending_any(true));
+        let stop = cpu.run_until(vec![write("str", TIMER_START, 1)], |_: &Armv7m| false);
+        assert_eq!(stop, StopReason::Finished);
+        assert!(!cpu.is_sleeping());
+        assert!(cpu.mem.device::<Timer>(b(TIMER_START)).unwrap().acked);
+    }
+}
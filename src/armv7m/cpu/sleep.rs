@@ -0,0 +1,109 @@
+use super::{Armv7m, SleepState};
+
+use flux_rs::bitvec::BV32;
+
+// Sleep & wake up (see Power management in the manual)
+//
+// WFI, WFE and sleep-on-exit put the core to sleep (insns/wfi.rs & wfe.rs).
+// It stays asleep until wake_up finds a reason to wake:
+//
+// - an enabled external interrupt or SysTick is pending. Without exception
+//   priorities we can not tell whether it would preempt so any of them wakes
+//   the core, even when PRIMASK keeps it from being taken (a WFI wakes on
+//   those too).
+// - for a WFE, the event register is set. It is consumed by waking. SEV and
+//   every exception return set it (see Armv7m::exception_exit).
+// - for a WFE with SCR.SEVONPEND, any external interrupt is pending. The
+//   architecture only sets the event register when an interrupt becomes
+//   pending but a WFE is allowed to wake spuriously so we do not track when.
+//
+// NOTE: we do not fetch & execute instructions in a loop so whoever drives the
+// cpu calls wake_up (Armv7m::run_until does) and clocks SysTick & devices
+// while the core sleeps (see Armv7m::clock). Deep sleep is only recorded -
+// which clocks stop in it is implementation defined.
+
+flux_rs::defs! {
+    fn sleep_post_wfi(cpu: Armv7m) -> SleepState {
+        SleepState { sleeping: true, deep: scr_sleep_deep(cpu.mem), on_event: false }
+    }
+
+    fn sleep_post_wfe(cpu: Armv7m) -> SleepState {
+        SleepState { sleeping: true, deep: scr_sleep_deep(cpu.mem), on_event: true }
+    }
+
+    // returning to Thread mode with SCR.SLEEPONEXIT set sleeps as a WFI would.
+    // Hardware can put off the unstacking until it wakes - we return first,
+    // which the interrupted code can not tell apart. Returning to another
+    // handler never sleeps.
+    fn sleep_post_exception_exit(cpu: Armv7m, return_exec: BV32) -> SleepState {
+        if scr_sleep_on_exit(cpu.mem) && !exc_return_to_handler(return_exec) {
+            sleep_post_wfi(cpu)
+        } else {
+            cpu.sleep
+        }
+    }
+}
+
+impl Armv7m {
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[cpu.sleep.sleeping])]
+    pub fn is_sleeping(&self) -> bool {
+        self.sleep.sleeping
+    }
+
+    #[flux_rs::sig(fn (&Armv7m[@cpu]) -> bool[cpu.sleep.sleeping && cpu.sleep.deep])]
+    pub fn is_deep_sleeping(&self) -> bool {
+        self.sleep.sleeping && self.sleep.deep
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], bool[@on_event])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+                    sleep: SleepState { sleeping: true, deep: scr_sleep_deep(old_cpu.mem), on_event: on_event },
+                    ..old_cpu
+                }
+            }
+    )]
+    pub(super) fn enter_sleep(&mut self, on_event: bool) {
+        let deep = self.mem.sleep_deep();
+        self.sleep.sleeping = true;
+        self.sleep.deep = deep;
+        self.sleep.on_event = on_event;
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu], BV32[@return_exec])
+            ensures self: Armv7m { new_cpu: new_cpu == Armv7m {
+                    sleep: sleep_post_exception_exit(old_cpu, return_exec),
+                    ..old_cpu
+                }
+            }
+    )]
+    pub(super) fn enter_sleep_on_exit(&mut self, return_exec: BV32) {
+        if self.mem.sleep_on_exit() && return_exec != BV32::from(0xFFFF_FFF1) {
+            self.enter_sleep(false);
+        }
+    }
+
+    // Wakes the core if anything it waits for has happened - returns false
+    // while it is still asleep
+    #[flux_rs::trusted]
+    pub fn wake_up(&mut self) -> bool {
+        if !self.sleep.sleeping {
+            return true;
+        }
+        if self.sleep.on_event && self.mem.sev_on_pend() && self.mem.nvic_irq_pending_any(false) {
+            self.event = true;
+        }
+        let exception_pending = self.mem.nvic_irq_pending_any(true) || self.mem.sys_tick_pending();
+        let event = self.sleep.on_event && self.event;
+        if !exception_pending && !event {
+            return false;
+        }
+        if self.sleep.on_event {
+            // waking from a WFE clears the event register
+            self.event = false;
+        }
+        self.sleep.sleeping = false;
+        true
+    }
+}
@@ -0,0 +1,54 @@
+use crate::armv7m::lang::ExceptionNumber;
+
+use super::Armv7m;
+
+// SysTick as seen by the core (the counter itself lives in mem/sys_tick.rs)
+//
+// NOTE: we do not fetch & execute instructions in a loop so SysTick is only
+// clocked by Armv7m::run_until (see cpu/run.rs) - whoever drives the cpu
+// some other way calls tick after each instruction. Either way
+// take_pending_sys_tick is called where the exception could be taken.
+
+flux_rs::defs! {
+    fn cpu_post_tick(cpu: Armv7m) -> Armv7m {
+        Armv7m { mem: mem_post_sys_tick(cpu.mem), ..cpu }
+    }
+
+    fn cpu_post_take_sys_tick(cpu: Armv7m) -> Armv7m {
+        // taking SysTick clears ICSR.PENDSTSET
+        cpu_post_preempt(Armv7m { mem: mem_post_clear_sys_tick_pending(cpu.mem), ..cpu }, 15)
+    }
+}
+
+impl Armv7m {
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu])
+            ensures self: Armv7m { new_cpu: new_cpu == cpu_post_tick(old_cpu) }
+    )]
+    pub fn tick(&mut self) {
+        self.mem.sys_tick();
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Armv7m[@old_cpu]) -> bool[sys_tick_pending(old_cpu.mem)]
+            requires
+                kernel_statics_in_ram(old_cpu.mem)
+                &&
+                sp_can_handle_exception_entry(old_cpu)
+                &&
+                sp_can_handle_preempt_exception_exit(old_cpu, 15)
+            ensures self: Armv7m { new_cpu:
+                (sys_tick_pending(old_cpu.mem) => new_cpu == cpu_post_take_sys_tick(old_cpu))
+                &&
+                (!sys_tick_pending(old_cpu.mem) => new_cpu == old_cpu)
+            }
+    )]
+    pub fn take_pending_sys_tick(&mut self) -> bool {
+        if !self.mem.sys_tick_pending() {
+            return false;
+        }
+        self.mem.clear_sys_tick_pending();
+        self.preempt(ExceptionNumber::SysTick);
+        true
+    }
+}
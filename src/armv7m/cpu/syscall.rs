@@ -0,0 +1,243 @@
+use super::Armv7m;
+
+use flux_rs::bitvec::BV32;
+
+// Recovering the SVC immediate and syscall arguments from an exception frame
+//
+// The SVC immediate is not saved anywhere by the processor. Handlers have to
+// read the halfword at the stacked return address - 2, which holds the SVC
+// instruction (0xDF00 | imm8).
+//
+// Tock passes the syscall class as the SVC immediate and the arguments in
+// r0-r3, which on exception entry end up at the bottom of the process stack
+// frame.
+flux_rs::defs! {
+    fn svc_instr_addr(frame_ptr: BV32, mem: Memory) -> BV32 {
+        // the stacked return address points right after the SVC
+        get_mem_addr(frame_ptr + 0x18, mem) - 0x2
+    }
+
+    fn svc_number_from_frame(frame_ptr: BV32, mem: Memory) -> BV32 {
+        // imm8 is the low byte of the (little endian) instruction halfword
+        get_mem_byte(svc_instr_addr(frame_ptr, mem), mem)
+    }
+
+    // the halfword at address is SVC #imm8 (encoding T1)
+    fn is_svc_instr(address: BV32, imm8: int, mem: Memory) -> bool {
+        get_mem_halfword(address, mem) == (0xDF00 | bv32(imm8))
+    }
+
+    fn can_read_svc_number(frame_ptr: BV32, mem: Memory) -> bool {
+        is_pure_read_addr(frame_ptr + 0x18, mem)
+        &&
+        is_pure_read_addr(word_addr(svc_instr_addr(frame_ptr, mem)), mem)
+    }
+
+    fn can_decode_syscall(frame_ptr: BV32, mem: Memory) -> bool {
+        can_read_svc_number(frame_ptr, mem)
+        &&
+        is_pure_read_addr(frame_ptr, mem)
+        &&
+        is_pure_read_addr(frame_ptr + 0x4, mem)
+        &&
+        is_pure_read_addr(frame_ptr + 0x8, mem)
+        &&
+        is_pure_read_addr(frame_ptr + 0xC, mem)
+    }
+
+    // the arguments a syscall uses are the registers they were passed in -
+    // Yield ignores r3 and Memop & Exit ignore r2 & r3
+    fn syscall_args_match(syscall: Syscall, r0: int, r1: int, r2: int, r3: int) -> bool {
+        syscall.arg0 == r0
+        &&
+        syscall.arg1 == r1
+        &&
+        (syscall.class == MEMOP || syscall.class == EXIT || syscall.arg2 == r2)
+        &&
+        (syscall.class == YIELD || syscall.class == MEMOP || syscall.class == EXIT || syscall.arg3 == r3)
+    }
+
+    fn syscall_from_registers(syscall: Syscall, class: int, r0: int, r1: int, r2: int, r3: int) -> bool {
+        syscall.class == class && syscall_args_match(syscall, r0, r1, r2, r3)
+    }
+
+    fn syscall_from_frame(syscall: Syscall, frame_ptr: BV32, mem: Memory) -> bool {
+        syscall_from_registers(
+            syscall,
+            int(svc_number_from_frame(frame_ptr, mem)),
+            int(get_mem_addr(frame_ptr, mem)),
+            int(get_mem_addr(frame_ptr + 0x4, mem)),
+            int(get_mem_addr(frame_ptr + 0x8, mem)),
+            int(get_mem_addr(frame_ptr + 0xC, mem))
+        )
+    }
+}
+
+// A system call as described by Tock's syscall ABI (see TRD 104). The class is
+// the SVC immediate and the arguments are r0-r3 of the process.
+//
+// The refinement is the class followed by the register each field came from
+// (0 for registers the class does not use).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[flux_rs::refined_by(class: int, arg0: int, arg1: int, arg2: int, arg3: int)]
+pub enum Syscall {
+    #[variant({which: u32[@r0], param_a: u32[@r1], param_b: u32[@r2]} -> Syscall[YIELD, r0, r1, r2, 0])]
+    Yield {
+        which: u32,
+        param_a: u32,
+        param_b: u32,
+    },
+    #[variant({driver_number: u32[@r0], subdriver_number: u32[@r1], upcall_ptr: u32[@r2], appdata: u32[@r3]} -> Syscall[SUBSCRIBE, r0, r1, r2, r3])]
+    Subscribe {
+        driver_number: u32,
+        subdriver_number: u32,
+        upcall_ptr: u32,
+        appdata: u32,
+    },
+    #[variant({driver_number: u32[@r0], subdriver_number: u32[@r1], arg0: u32[@r2], arg1: u32[@r3]} -> Syscall[COMMAND, r0, r1, r2, r3])]
+    Command {
+        driver_number: u32,
+        subdriver_number: u32,
+        arg0: u32,
+        arg1: u32,
+    },
+    #[variant({driver_number: u32[@r0], subdriver_number: u32[@r1], allow_address: u32[@r2], allow_size: u32[@r3]} -> Syscall[RW_ALLOW, r0, r1, r2, r3])]
+    ReadWriteAllow {
+        driver_number: u32,
+        subdriver_number: u32,
+        allow_address: u32,
+        allow_size: u32,
+    },
+    #[variant({driver_number: u32[@r0], subdriver_number: u32[@r1], allow_address: u32[@r2], allow_size: u32[@r3]} -> Syscall[UREAD_ALLOW, r0, r1, r2, r3])]
+    UserspaceReadableAllow {
+        driver_number: u32,
+        subdriver_number: u32,
+        allow_address: u32,
+        allow_size: u32,
+    },
+    #[variant({driver_number: u32[@r0], subdriver_number: u32[@r1], allow_address: u32[@r2], allow_size: u32[@r3]} -> Syscall[RO_ALLOW, r0, r1, r2, r3])]
+    ReadOnlyAllow {
+        driver_number: u32,
+        subdriver_number: u32,
+        allow_address: u32,
+        allow_size: u32,
+    },
+    #[variant({operand: u32[@r0], arg0: u32[@r1]} -> Syscall[MEMOP, r0, r1, 0, 0])]
+    Memop { operand: u32, arg0: u32 },
+    #[variant({which: u32[@r0], completion_code: u32[@r1]} -> Syscall[EXIT, r0, r1, 0, 0])]
+    Exit { which: u32, completion_code: u32 },
+}
+
+// Syscall class numbers (the SVC immediate)
+#[flux_rs::constant(0)]
+pub const YIELD: u32 = 0;
+#[flux_rs::constant(1)]
+pub const SUBSCRIBE: u32 = 1;
+#[flux_rs::constant(2)]
+pub const COMMAND: u32 = 2;
+#[flux_rs::constant(3)]
+pub const RW_ALLOW: u32 = 3;
+#[flux_rs::constant(4)]
+pub const RO_ALLOW: u32 = 4;
+#[flux_rs::constant(5)]
+pub const MEMOP: u32 = 5;
+#[flux_rs::constant(6)]
+pub const EXIT: u32 = 6;
+#[flux_rs::constant(7)]
+pub const UREAD_ALLOW: u32 = 7;
+
+impl Syscall {
+    // Decodes the syscall class and the raw argument registers, returning None
+    // for unknown classes
+    #[flux_rs::sig(
+        fn (u32[@class], u32[@r0], u32[@r1], u32[@r2], u32[@r3])
+            -> Option<Syscall{syscall: syscall_from_registers(syscall, class, r0, r1, r2, r3)}>
+    )]
+    pub fn from_register_arguments(
+        class: u32,
+        r0: u32,
+        r1: u32,
+        r2: u32,
+        r3: u32,
+    ) -> Option<Syscall> {
+        match class {
+            YIELD => Some(Syscall::Yield {
+                which: r0,
+                param_a: r1,
+                param_b: r2,
+            }),
+            SUBSCRIBE => Some(Syscall::Subscribe {
+                driver_number: r0,
+                subdriver_number: r1,
+                upcall_ptr: r2,
+                appdata: r3,
+            }),
+            COMMAND => Some(Syscall::Command {
+                driver_number: r0,
+                subdriver_number: r1,
+                arg0: r2,
+                arg1: r3,
+            }),
+            RW_ALLOW => Some(Syscall::ReadWriteAllow {
+                driver_number: r0,
+                subdriver_number: r1,
+                allow_address: r2,
+                allow_size: r3,
+            }),
+            UREAD_ALLOW => Some(Syscall::UserspaceReadableAllow {
+                driver_number: r0,
+                subdriver_number: r1,
+                allow_address: r2,
+                allow_size: r3,
+            }),
+            RO_ALLOW => Some(Syscall::ReadOnlyAllow {
+                driver_number: r0,
+                subdriver_number: r1,
+                allow_address: r2,
+                allow_size: r3,
+            }),
+            MEMOP => Some(Syscall::Memop {
+                operand: r0,
+                arg0: r1,
+            }),
+            EXIT => Some(Syscall::Exit {
+                which: r0,
+                completion_code: r1,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Armv7m {
+    // Reads the SVC immediate of the SVC that created the exception frame at
+    // frame_ptr
+    #[flux_rs::sig(
+        fn (&Armv7m[@cpu], BV32[@frame_ptr]) -> BV32[svc_number_from_frame(frame_ptr, cpu.mem)]
+            requires can_read_svc_number(frame_ptr, cpu.mem)
+    )]
+    pub fn svc_number(&self, frame_ptr: BV32) -> BV32 {
+        let ret_addr = self.mem.peek(frame_ptr + BV32::from(0x18));
+        let svc_addr = ret_addr - BV32::from(0x2);
+        self.mem.peek8(svc_addr)
+    }
+
+    // Decodes the syscall a process made given the exception frame the SVC
+    // pushed on its stack
+    //
+    // trusted since flux does not know how BV32 converts to u32
+    #[flux_rs::trusted]
+    #[flux_rs::sig(
+        fn (&Armv7m[@cpu], BV32[@frame_ptr])
+            -> Option<Syscall{syscall: syscall_from_frame(syscall, frame_ptr, cpu.mem)}>
+            requires can_decode_syscall(frame_ptr, cpu.mem)
+    )]
+    pub fn decode_syscall(&self, frame_ptr: BV32) -> Option<Syscall> {
+        let class = self.svc_number(frame_ptr);
+        let r0 = self.mem.peek(frame_ptr);
+        let r1 = self.mem.peek(frame_ptr + BV32::from(0x4));
+        let r2 = self.mem.peek(frame_ptr + BV32::from(0x8));
+        let r3 = self.mem.peek(frame_ptr + BV32::from(0xC));
+        Syscall::from_register_arguments(class.into(), r0.into(), r1.into(), r2.into(), r3.into())
+    }
+}
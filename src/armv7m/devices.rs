@@ -0,0 +1,274 @@
+// Stand-in board peripherals (see mem/mmio.rs)
+//
+// These are not models of any real chip's peripherals - they are just enough
+// for kernel & driver code that touches a UART, GPIO pins or a timer to run.
+// Every register is a word:
+//
+// Uart   0x0 TXDATA  W    writing a byte sends it
+//        0x4 STATUS  R    bit 0: TX ready (always set)
+//        0x8 INTEN   RW   bit 0: interrupt when a byte has been sent
+//        0xC EVENTS  RW   bit 0: a byte has been sent - write 1 to clear
+//
+// Gpio   0x0 OUT     RW   pin levels driven by software
+//        0x4 IN      R    pin levels driven from outside (see set_input)
+//        0x8 INTEN   RW   interrupt on a rising edge of these pins
+//        0xC EVENTS  RW   pins that saw a rising edge - write 1 to clear
+//
+// Timer  0x0 CTRL    RW   bit 0: enable, bit 1: interrupt on match
+//        0x4 COMPARE RW   the count that raises a match
+//        0x8 COUNT   RW   counts up by 1 every tick while enabled
+//        0xC EVENTS  RW   bit 0: COUNT reached COMPARE - write 1 to clear
+//
+// A device asserts its NVIC line while EVENTS & INTEN (CTRL.bit 1 for the
+// timer) is non zero. Unknown offsets read as 0 and ignore writes.
+
+use std::any::Any;
+
+use super::mem::mmio::MmioDevice;
+
+use flux_rs::bitvec::BV32;
+
+const TXDATA: u32 = 0x0;
+const STATUS: u32 = 0x4;
+const INTEN: u32 = 0x8;
+const EVENTS: u32 = 0xC;
+
+const OUT: u32 = 0x0;
+const IN: u32 = 0x4;
+
+const CTRL: u32 = 0x0;
+const COMPARE: u32 = 0x4;
+const COUNT: u32 = 0x8;
+
+const TIMER_CTRL_ENABLE: u32 = 0x1;
+const TIMER_CTRL_INTEN: u32 = 0x2;
+
+// Captures everything written to it
+#[derive(Debug, Clone, Default)]
+pub struct Uart {
+    irq: u16,
+    output: Vec<u8>,
+    inten: u32,
+    events: u32,
+}
+
+impl Uart {
+    pub fn new(irq: u16) -> Uart {
+        Uart {
+            irq,
+            ..Uart::default()
+        }
+    }
+
+    // Every byte sent so far
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+}
+
+impl MmioDevice for Uart {
+    fn read(&mut self, offset: BV32) -> BV32 {
+        match u32::from(offset) {
+            STATUS => BV32::from(1),
+            INTEN => BV32::from(self.inten),
+            EVENTS => BV32::from(self.events),
+            _ => BV32::from(0),
+        }
+    }
+
+    fn write(&mut self, offset: BV32, value: BV32) {
+        let value = u32::from(value);
+        match u32::from(offset) {
+            TXDATA => {
+                self.output.push(value as u8);
+                self.events |= 1;
+            }
+            INTEN => self.inten = value & 1,
+            EVENTS => self.events &= !value,
+            _ => {}
+        }
+    }
+
+    fn irqs(&self) -> Vec<u16> {
+        vec![self.irq]
+    }
+
+    fn irq_lines(&self) -> Vec<u16> {
+        if self.events & self.inten != 0 {
+            vec![self.irq]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn MmioDevice> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// 32 pins
+#[derive(Debug, Clone, Default)]
+pub struct Gpio {
+    irq: u16,
+    out: u32,
+    input: u32,
+    inten: u32,
+    events: u32,
+}
+
+impl Gpio {
+    pub fn new(irq: u16) -> Gpio {
+        Gpio {
+            irq,
+            ..Gpio::default()
+        }
+    }
+
+    // Drives a pin from outside - a rising edge is latched in EVENTS
+    pub fn set_input(&mut self, pin: u8, high: bool) {
+        let bit = 1 << (pin & 31);
+        if high && self.input & bit == 0 {
+            self.events |= bit;
+        }
+        if high {
+            self.input |= bit;
+        } else {
+            self.input &= !bit;
+        }
+    }
+
+    pub fn output(&self, pin: u8) -> bool {
+        self.out & (1 << (pin & 31)) != 0
+    }
+}
+
+impl MmioDevice for Gpio {
+    fn read(&mut self, offset: BV32) -> BV32 {
+        match u32::from(offset) {
+            OUT => BV32::from(self.out),
+            IN => BV32::from(self.input),
+            INTEN => BV32::from(self.inten),
+            EVENTS => BV32::from(self.events),
+            _ => BV32::from(0),
+        }
+    }
+
+    fn write(&mut self, offset: BV32, value: BV32) {
+        let value = u32::from(value);
+        match u32::from(offset) {
+            OUT => self.out = value,
+            INTEN => self.inten = value,
+            EVENTS => self.events &= !value,
+            _ => {}
+        }
+    }
+
+    fn irqs(&self) -> Vec<u16> {
+        vec![self.irq]
+    }
+
+    fn irq_lines(&self) -> Vec<u16> {
+        if self.events & self.inten != 0 {
+            vec![self.irq]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn MmioDevice> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Counts ticks up to COMPARE then starts again from 0
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    irq: u16,
+    ctrl: u32,
+    compare: u32,
+    count: u32,
+    events: u32,
+}
+
+impl Timer {
+    pub fn new(irq: u16) -> Timer {
+        Timer {
+            irq,
+            ..Timer::default()
+        }
+    }
+}
+
+impl MmioDevice for Timer {
+    fn read(&mut self, offset: BV32) -> BV32 {
+        match u32::from(offset) {
+            CTRL => BV32::from(self.ctrl),
+            COMPARE => BV32::from(self.compare),
+            COUNT => BV32::from(self.count),
+            EVENTS => BV32::from(self.events),
+            _ => BV32::from(0),
+        }
+    }
+
+    fn write(&mut self, offset: BV32, value: BV32) {
+        let value = u32::from(value);
+        match u32::from(offset) {
+            CTRL => self.ctrl = value & (TIMER_CTRL_ENABLE | TIMER_CTRL_INTEN),
+            COMPARE => self.compare = value,
+            COUNT => self.count = value,
+            EVENTS => self.events &= !value,
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.ctrl & TIMER_CTRL_ENABLE == 0 {
+            return;
+        }
+        self.count = self.count.wrapping_add(1);
+        if self.count == self.compare {
+            self.events |= 1;
+            self.count = 0;
+        }
+    }
+
+    fn irqs(&self) -> Vec<u16> {
+        vec![self.irq]
+    }
+
+    fn irq_lines(&self) -> Vec<u16> {
+        if self.events != 0 && self.ctrl & TIMER_CTRL_INTEN != 0 {
+            vec![self.irq]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn MmioDevice> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -1,3 +1,5 @@
+use flux_rs::bitvec::BV32;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[flux_rs::refined_by(n: int)]
 pub enum GPR {
@@ -99,12 +101,6 @@ impl GPR {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[flux_rs::refined_by(n : int)]
 pub enum SpecialRegister {
-    #[variant(SpecialRegister[13])]
-    Sp,
-    #[variant(SpecialRegister[14])]
-    Lr,
-    #[variant(SpecialRegister[15])]
-    Pc,
     #[variant(SpecialRegister[16])]
     Control,
     // PSR and one of the sub register (IPSR)
@@ -114,36 +110,237 @@ pub enum SpecialRegister {
     IPSR,
     #[variant(SpecialRegister[19])]
     PSP,
+    // SP_main, the main stack pointer whichever stack is current
+    #[variant(SpecialRegister[20])]
+    MSP,
 }
 
 impl SpecialRegister {
-    #[flux_rs::sig(fn () -> SpecialRegister[sp()])]
-    pub fn sp() -> Self {
-        Self::Sp
-    }
-
     #[flux_rs::sig(fn () -> SpecialRegister[psp()])]
     pub fn psp() -> Self {
         Self::PSP
     }
 
-    #[flux_rs::sig(fn () -> SpecialRegister[lr()])]
-    pub fn lr() -> Self {
-        Self::Lr
-    }
-
     #[flux_rs::sig(fn () -> SpecialRegister[psr()])]
     pub fn psr() -> Self {
         Self::PSR
     }
 
-    #[flux_rs::sig(fn () -> SpecialRegister[pc()])]
+    #[flux_rs::sig(fn () -> SpecialRegister[msp()])]
+    pub fn msp() -> Self {
+        Self::MSP
+    }
+}
+
+// An R0-R15 operand - R0-R12 or one of SP, LR & PC, refined by its register
+// number. Reading PC gives the address of the
+// instruction + 4 and writing it branches (see ARM core registers in the
+// manual).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[flux_rs::refined_by(n: int)]
+pub enum Reg {
+    #[variant((GPR[@n]) -> Reg[n])]
+    Gpr(GPR),
+    #[variant(Reg[13])]
+    Sp,
+    #[variant(Reg[14])]
+    Lr,
+    #[variant(Reg[15])]
+    Pc,
+}
+
+impl Reg {
+    #[flux_rs::sig(fn (GPR[@n]) -> Reg[n])]
+    pub fn gpr(register: GPR) -> Self {
+        Self::Gpr(register)
+    }
+
+    #[flux_rs::sig(fn () -> Reg[sp()])]
+    pub fn sp() -> Self {
+        Self::Sp
+    }
+
+    #[flux_rs::sig(fn () -> Reg[lr()])]
+    pub fn lr() -> Self {
+        Self::Lr
+    }
+
+    #[flux_rs::sig(fn () -> Reg[pc()])]
     pub fn pc() -> Self {
         Self::Pc
     }
+
+    #[flux_rs::sig(fn (&Reg[@reg]) -> bool[is_pc(reg)])]
+    pub fn is_pc(&self) -> bool {
+        matches!(self, Self::Pc)
+    }
+}
+
+// The special registers MRS & MSR name, refined by their SYSm encoding (see
+// MRS and MSR in the manual)
+//
+// SYSm 0 - 7 are views of the xPSR: bit 0 includes IPSR, bit 1 EPSR and a
+// clear bit 2 includes APSR. 4 is reserved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[flux_rs::refined_by(sysm: int)]
+pub enum SysReg {
+    #[variant(SysReg[0])]
+    Apsr,
+    #[variant(SysReg[1])]
+    Iapsr,
+    #[variant(SysReg[2])]
+    Eapsr,
+    #[variant(SysReg[3])]
+    Xpsr,
+    #[variant(SysReg[5])]
+    Ipsr,
+    #[variant(SysReg[6])]
+    Epsr,
+    #[variant(SysReg[7])]
+    Iepsr,
+    #[variant(SysReg[8])]
+    Msp,
+    #[variant(SysReg[9])]
+    Psp,
+    #[variant(SysReg[16])]
+    Primask,
+    #[variant(SysReg[17])]
+    Basepri,
+    // writes only ever raise the priority BASEPRI masks
+    #[variant(SysReg[18])]
+    BasepriMax,
+    #[variant(SysReg[19])]
+    Faultmask,
+    #[variant(SysReg[20])]
+    Control,
 }
 
 #[derive(Debug)]
 pub enum IsbOpt {
     Sys,
 }
+
+// ARMv7-M only defines the SY option for DMB & DSB - the other encodings are
+// reserved and behave like SY
+#[derive(Debug)]
+pub enum DmbOpt {
+    Sys,
+}
+
+#[derive(Debug)]
+pub enum DsbOpt {
+    Sys,
+}
+
+// Exception numbers (see p. B1-525 of the manual)
+//
+// Exception numbers 7 - 10 and 13 are reserved and external interrupts start
+// at 16, so IRQ n is exception number 16 + n. The architecture allows up to 496
+// external interrupts but we model IPSR as 8 bits wide (the same assumption
+// generic_isr makes when it masks IPSR with 0xff) so we implement 240.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[flux_rs::refined_by(n: int)]
+pub enum ExceptionNumber {
+    #[variant(ExceptionNumber[1])]
+    Reset,
+    #[variant(ExceptionNumber[2])]
+    NMI,
+    #[variant(ExceptionNumber[3])]
+    HardFault,
+    #[variant(ExceptionNumber[4])]
+    MemManage,
+    #[variant(ExceptionNumber[5])]
+    BusFault,
+    #[variant(ExceptionNumber[6])]
+    UsageFault,
+    #[variant(ExceptionNumber[11])]
+    SVCall,
+    #[variant(ExceptionNumber[12])]
+    DebugMonitor,
+    #[variant(ExceptionNumber[14])]
+    PendSV,
+    #[variant(ExceptionNumber[15])]
+    SysTick,
+    // IRQ number - bounded by the most we model. A chip can implement fewer
+    // (see exception_implemented).
+    #[variant({u16[@irq] | irq < MAX_EXTERNAL_INTERRUPTS} -> ExceptionNumber[irq + 16])]
+    External(u16),
+}
+
+#[flux_rs::constant(240)]
+pub const MAX_EXTERNAL_INTERRUPTS: u16 = 240;
+
+impl ExceptionNumber {
+    #[flux_rs::sig(fn (&ExceptionNumber[@n]) -> u32[n])]
+    pub fn number(&self) -> u32 {
+        match self {
+            ExceptionNumber::Reset => 1,
+            ExceptionNumber::NMI => 2,
+            ExceptionNumber::HardFault => 3,
+            ExceptionNumber::MemManage => 4,
+            ExceptionNumber::BusFault => 5,
+            ExceptionNumber::UsageFault => 6,
+            ExceptionNumber::SVCall => 11,
+            ExceptionNumber::DebugMonitor => 12,
+            ExceptionNumber::PendSV => 14,
+            ExceptionNumber::SysTick => 15,
+            ExceptionNumber::External(irq) => *irq as u32 + 16,
+        }
+    }
+
+    #[flux_rs::sig(fn (&ExceptionNumber[@n]) -> BV32[bv32(n)])]
+    pub fn to_bv32(&self) -> BV32 {
+        BV32::from(self.number())
+    }
+
+    // Decodes the exception number held in IPSR. Thread mode (0) and the
+    // reserved numbers have no exception attached to them so they return None.
+    // Only the low 8 bits of IPSR are looked at (see the comment on
+    // ExceptionNumber).
+    //
+    // NOTE: this does not check the IRQ against the chip - whoever takes the
+    // exception does (see exception_implemented).
+    #[flux_rs::sig(
+        fn (BV32[@ipsr]) -> Option<ExceptionNumber{n: bv32(n) == (ipsr & 0xff)}>
+    )]
+    pub fn from_ipsr(ipsr: BV32) -> Option<ExceptionNumber> {
+        let n = ipsr & BV32::from(0xff);
+        if n == BV32::from(1) {
+            Some(ExceptionNumber::Reset)
+        } else if n == BV32::from(2) {
+            Some(ExceptionNumber::NMI)
+        } else if n == BV32::from(3) {
+            Some(ExceptionNumber::HardFault)
+        } else if n == BV32::from(4) {
+            Some(ExceptionNumber::MemManage)
+        } else if n == BV32::from(5) {
+            Some(ExceptionNumber::BusFault)
+        } else if n == BV32::from(6) {
+            Some(ExceptionNumber::UsageFault)
+        } else if n == BV32::from(11) {
+            Some(ExceptionNumber::SVCall)
+        } else if n == BV32::from(12) {
+            Some(ExceptionNumber::DebugMonitor)
+        } else if n == BV32::from(14) {
+            Some(ExceptionNumber::PendSV)
+        } else if n == BV32::from(15) {
+            Some(ExceptionNumber::SysTick)
+        } else if n >= BV32::from(16) {
+            Some(ExceptionNumber::External(Self::external_irq(n)))
+        } else {
+            None
+        }
+    }
+
+    // The IRQ an external interrupt's exception number stands for. Trusted as
+    // flux does not relate a BV32 to the integer it converts into.
+    #[flux_rs::trusted]
+    #[flux_rs::sig(
+        fn (BV32[@n]) -> u16{irq: bv32(irq + 16) == n && irq < MAX_EXTERNAL_INTERRUPTS}
+            requires 16 <= n && n <= 0xff
+    )]
+    fn external_irq(n: BV32) -> u16 {
+        let n: u32 = n.into();
+        (n - 16) as u16
+    }
+}
@@ -0,0 +1,117 @@
+// Bit-banding (see Bit-banding in the manual)
+//
+// Every bit of the first 1MB of SRAM and of the peripheral region has a word
+// in a 32MB alias region:
+//
+// 0x2000_0000-0x200F_FFFF  aliased at  0x2200_0000-0x23FF_FFFF
+// 0x4000_0000-0x400F_FFFF  aliased at  0x4200_0000-0x43FF_FFFF
+//
+// alias = alias_start + (byte_offset * 32) + (bit * 4)
+//
+// Reading an alias word returns the bit in bit 0 and writing one sets the bit
+// to bit 0 of the value. The write is an atomic read-modify-write of the word
+// holding the bit.
+//
+// NOTE: bit-banding is optional so a chip profile says whether it is there
+// (MemoryMap::bit_band). The SRAM alias region is never RAM and accesses to
+// it are only allowed when the chip implements bit-banding and the bit is in
+// the chip's SRAM (see memory_map.rs). A word of SRAM that was never written
+// reads as 0 through its alias too. The peripheral alias region only reaches devices (see
+// mmio.rs) so it is not known to flux.
+
+use super::flux_defs::bit_band_defs::*;
+use super::Memory;
+use flux_rs::bitvec::BV32;
+
+#[flux_rs::constant(0x2000_0000)]
+pub const BIT_BAND_SRAM_START: BV32 = BV32::new(0x2000_0000);
+#[flux_rs::constant(0x2200_0000)]
+pub const BIT_BAND_SRAM_ALIAS_START: BV32 = BV32::new(0x2200_0000);
+#[flux_rs::constant(0x23FF_FFFF)]
+pub const BIT_BAND_SRAM_ALIAS_END: BV32 = BV32::new(0x23FF_FFFF);
+
+#[flux_rs::constant(0x4000_0000)]
+pub const BIT_BAND_PERIPHERAL_START: BV32 = BV32::new(0x4000_0000);
+#[flux_rs::constant(0x4200_0000)]
+pub const BIT_BAND_PERIPHERAL_ALIAS_START: BV32 = BV32::new(0x4200_0000);
+#[flux_rs::constant(0x43FF_FFFF)]
+pub const BIT_BAND_PERIPHERAL_ALIAS_END: BV32 = BV32::new(0x43FF_FFFF);
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_bit_band_sram_alias(addr)])]
+pub fn is_bit_band_sram_alias(address: BV32) -> bool {
+    let alias_start = BIT_BAND_SRAM_ALIAS_START;
+    let alias_end = BIT_BAND_SRAM_ALIAS_END;
+    address >= alias_start && address <= alias_end
+}
+
+pub fn is_bit_band_peripheral_alias(address: BV32) -> bool {
+    let alias_start = BIT_BAND_PERIPHERAL_ALIAS_START;
+    let alias_end = BIT_BAND_PERIPHERAL_ALIAS_END;
+    address >= alias_start && address <= alias_end
+}
+
+// The word holding the bit an alias address stands for
+#[flux_rs::sig(fn (BV32[@addr]) -> BV32[bit_band_word_addr(addr)])]
+pub fn bit_band_word_addr(address: BV32) -> BV32 {
+    if is_bit_band_sram_alias(address) {
+        BIT_BAND_SRAM_START
+            + (((address - BIT_BAND_SRAM_ALIAS_START) >> BV32::from(7)) << BV32::from(2))
+    } else {
+        BIT_BAND_PERIPHERAL_START
+            + (((address - BIT_BAND_PERIPHERAL_ALIAS_START) >> BV32::from(7)) << BV32::from(2))
+    }
+}
+
+// The bit within that word - the byte offset's low 2 bits pick the byte and
+// the alias's bit number the bit in it
+#[flux_rs::sig(fn (BV32[@addr]) -> BV32[bit_band_bit(addr)])]
+pub fn bit_band_bit(address: BV32) -> BV32 {
+    (address >> BV32::from(2)) & BV32::from(31)
+}
+
+impl Memory {
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr]) -> BV32[get_mem_addr(addr, mem)]
+            requires is_bit_band_sram_alias(addr)
+    )]
+    pub(super) fn bit_band_read(&self, address: BV32) -> BV32 {
+        let word = self.mem.get_or_default(&bit_band_word_addr(address));
+        (word >> bit_band_bit(address)) & BV32::from(1)
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            requires is_bit_band_sram_alias(addr)
+            ensures self: Memory { new_mem: new_mem == update_mem(addr, old_mem, val) }
+    )]
+    pub(super) fn bit_band_write(&mut self, address: BV32, value: BV32) {
+        let word_address = bit_band_word_addr(address);
+        let bit = BV32::from(1) << bit_band_bit(address);
+        let word = self.mem.get_or_default(&word_address);
+        if value & BV32::from(1) == BV32::from(0) {
+            self.mem.set(word_address, word & !bit)
+        } else {
+            self.mem.set(word_address, word | bit)
+        }
+    }
+
+    // Peripheral bit-banding is a read-modify-write of the device word
+    #[flux_rs::trusted]
+    pub(super) fn bit_band_device_read(&self, address: BV32) -> Option<BV32> {
+        let word = self.device_read(bit_band_word_addr(address))?;
+        Some((word >> bit_band_bit(address)) & BV32::from(1))
+    }
+
+    #[flux_rs::trusted]
+    pub(super) fn bit_band_device_write(&self, address: BV32, value: BV32) -> bool {
+        let word_address = bit_band_word_addr(address);
+        let bit = BV32::from(1) << bit_band_bit(address);
+        match self.device_read(word_address) {
+            Some(word) if value & BV32::from(1) == BV32::from(0) => {
+                self.device_write(word_address, word & !bit)
+            }
+            Some(word) => self.device_write(word_address, word | bit),
+            None => false,
+        }
+    }
+}
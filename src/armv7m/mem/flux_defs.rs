@@ -1,160 +1,565 @@
+use super::bit_band;
+use super::memory_map;
+use super::memory_map::{CODE_END, RAM_END, RAM_START, SRAM_END, SRAM_START};
 use super::mpu;
 use super::nvic;
 use super::sys_control;
+use super::sys_control::STIR_ADDR;
 use super::sys_tick;
+use super::sys_tick::{SYST_CSR_ADDR, SYST_CVR_ADDR};
 use super::{
     Memory, INTERRUPT_AUXILIARY_CONTROL_REGISTER_END, INTERRUPT_AUXILIARY_CONTROL_REGISTER_START,
-    MPU_END, MPU_START, NVIC_END, NVIC_START, PPB_END, PPB_START, RAM_END, RAM_START,
-    SW_TRIGGER_INTERRUPT_REG_END, SW_TRIGGER_INTERRUPT_REG_START, SYSTEM_CONTROL_BLOCK_END,
-    SYSTEM_CONTROL_BLOCK_START, SYS_TICK_END, SYS_TICK_START,
+    MPU_END, MPU_START, NVIC_END, NVIC_START, PPB_END, PPB_START, SW_TRIGGER_INTERRUPT_REG_END,
+    SW_TRIGGER_INTERRUPT_REG_START, SYSTEM_CONTROL_BLOCK_END, SYSTEM_CONTROL_BLOCK_START,
+    SYS_TICK_END, SYS_TICK_START,
 };
 
 pub mod mpu_defs {
     use super::mpu::{
-        MPU_CTRL_ADDR, MPU_RASR_A1_ADDR, MPU_RASR_A2_ADDR, MPU_RASR_A3_ADDR, MPU_RASR_ADDR,
-        MPU_RBAR_A1_ADDR, MPU_RBAR_A2_ADDR, MPU_RBAR_A3_ADDR, MPU_RBAR_ADDR, MPU_RNR_ADDR,
-        MPU_TYPE_ADDR,
+        TockProcessRegions, MPU_AP_FULL_ACCESS, MPU_AP_UNPRIV_RO, MPU_CTRL_ADDR, MPU_CTRL_ENABLE,
+        MPU_CTRL_PRIVDEFENA, MPU_NO_REGION, MPU_RASR_A1_ADDR, MPU_RASR_A2_ADDR, MPU_RASR_A3_ADDR,
+        MPU_RASR_ADDR, MPU_RASR_ENABLE, MPU_RASR_XN, MPU_RBAR_A1_ADDR, MPU_RBAR_A2_ADDR,
+        MPU_RBAR_A3_ADDR, MPU_RBAR_ADDR, MPU_RBAR_ADDR_MASK, MPU_RBAR_REGION, MPU_RBAR_VALID,
+        MPU_REGION_STATE_START, MPU_RNR_ADDR, MPU_RNR_REGION, MPU_TYPE_ADDR, MPU_TYPE_DREGION,
+        TOCK_FLASH_REGION, TOCK_RAM_REGION,
     };
+    use super::{Memory, PPB_START};
     use flux_rs::bitvec::BV32;
 
     flux_rs::defs! {
-        fn is_valid_mpu_read_addr(address: BV32) -> bool {
-            // all address are read
-            address == MPU_TYPE_ADDR
-                || address == MPU_CTRL_ADDR
-                || address == MPU_RNR_ADDR
-                || address == MPU_RBAR_ADDR
-                || address == MPU_RASR_ADDR
+        // RBAR & RASR are windows onto the region RNR selects (see mpu.rs)
+        fn is_mpu_rbar_addr(address: BV32) -> bool {
+            address == MPU_RBAR_ADDR
                 || address == MPU_RBAR_A1_ADDR
-                || address == MPU_RASR_A1_ADDR
                 || address == MPU_RBAR_A2_ADDR
-                || address == MPU_RASR_A2_ADDR
                 || address == MPU_RBAR_A3_ADDR
-                || address == MPU_RASR_A3_ADDR
         }
 
-        fn is_valid_mpu_write_addr(address: BV32) -> bool {
-            // all address except MPU_TYPE are write
-            address == MPU_CTRL_ADDR
-                || address == MPU_RNR_ADDR
-                || address == MPU_RBAR_ADDR
-                || address == MPU_RASR_ADDR
-                || address == MPU_RBAR_A1_ADDR
+        fn is_mpu_rasr_addr(address: BV32) -> bool {
+            address == MPU_RASR_ADDR
                 || address == MPU_RASR_A1_ADDR
-                || address == MPU_RBAR_A2_ADDR
                 || address == MPU_RASR_A2_ADDR
-                || address == MPU_RBAR_A3_ADDR
                 || address == MPU_RASR_A3_ADDR
         }
+
+        // MPU_TYPE.DREGION - the regions the chip implements
+        fn mpu_num_regions(mem: Memory) -> BV32 {
+            (map_get(mem, MPU_TYPE_ADDR) & MPU_TYPE_DREGION) >> 8
+        }
+
+        fn mpu_rnr(mem: Memory) -> BV32 {
+            map_get(mem, MPU_RNR_ADDR) & MPU_RNR_REGION
+        }
+
+        fn mpu_region_rbar_addr(region: BV32) -> BV32 {
+            MPU_REGION_STATE_START + (region << 3)
+        }
+
+        fn mpu_region_rasr_addr(region: BV32) -> BV32 {
+            MPU_REGION_STATE_START + (region << 3) + 4
+        }
+
+        fn mpu_region_rbar(region: BV32, mem: Memory) -> BV32 {
+            map_get(mem, mpu_region_rbar_addr(region))
+        }
+
+        fn mpu_region_rasr(region: BV32, mem: Memory) -> BV32 {
+            map_get(mem, mpu_region_rasr_addr(region))
+        }
+
+        // RNR selects a region the chip implements
+        fn mpu_rnr_implemented(mem: Memory) -> bool {
+            mpu_rnr(mem) < mpu_num_regions(mem)
+        }
+
+        fn mpu_read(address: BV32, mem: Memory) -> BV32 {
+            if (is_mpu_rbar_addr(address) || is_mpu_rasr_addr(address)) && !mpu_rnr_implemented(mem) {
+                // RAZ past DREGION
+                0
+            } else if is_mpu_rbar_addr(address) {
+                // VALID reads as 0 and REGION as the selected region
+                mpu_region_rbar(mpu_rnr(mem), mem) | (mpu_rnr(mem) & MPU_RBAR_REGION)
+            } else if is_mpu_rasr_addr(address) {
+                mpu_region_rasr(mpu_rnr(mem), mem)
+            } else {
+                map_get(mem, address)
+            }
+        }
+
+        fn mpu_write(address: BV32, mem: Memory, value: BV32) -> Memory {
+            if is_mpu_rbar_addr(address) {
+                if value & MPU_RBAR_VALID != 0 {
+                    if value & MPU_RBAR_REGION >= mpu_num_regions(mem) {
+                        // WI past DREGION
+                        mem
+                    } else {
+                        // RBAR.REGION selects the region (and updates RNR)
+                        map_set(
+                            map_set(mem, MPU_RNR_ADDR, value & MPU_RBAR_REGION),
+                            mpu_region_rbar_addr(value & MPU_RBAR_REGION),
+                            value & MPU_RBAR_ADDR_MASK
+                        )
+                    }
+                } else if !mpu_rnr_implemented(mem) {
+                    mem
+                } else {
+                    map_set(mem, mpu_region_rbar_addr(mpu_rnr(mem)), value & MPU_RBAR_ADDR_MASK)
+                }
+            } else if is_mpu_rasr_addr(address) {
+                if !mpu_rnr_implemented(mem) {
+                    mem
+                } else {
+                    map_set(mem, mpu_region_rasr_addr(mpu_rnr(mem)), value)
+                }
+            } else {
+                map_set(mem, address, value)
+            }
+        }
+
+        fn mpu_enabled(mem: Memory) -> bool {
+            map_get(mem, MPU_CTRL_ADDR) & MPU_CTRL_ENABLE != 0
+        }
+
+        fn mpu_privdefena(mem: Memory) -> bool {
+            map_get(mem, MPU_CTRL_ADDR) & MPU_CTRL_PRIVDEFENA != 0
+        }
+
+        fn mpu_region_size_log2(region: BV32, mem: Memory) -> BV32 {
+            ((mpu_region_rasr(region, mem) >> 1) & 0x1F) + 1
+        }
+
+        fn mpu_region_ap(region: BV32, mem: Memory) -> BV32 {
+            (mpu_region_rasr(region, mem) >> 24) & 0x7
+        }
+
+        fn mpu_region_xn(region: BV32, mem: Memory) -> bool {
+            mpu_region_rasr(region, mem) & MPU_RASR_XN != 0
+        }
+
+        fn mpu_region_subregion_disabled(region: BV32, address: BV32, mem: Memory) -> bool {
+            // only regions of 256 bytes or more have subregions
+            mpu_region_size_log2(region, mem) >= 8
+            &&
+            (
+                (mpu_region_rasr(region, mem) >> 8)
+                >>
+                ((address >> (mpu_region_size_log2(region, mem) - 3)) & 0x7)
+            ) & 1 != 0
+        }
+
+        fn mpu_region_matches(region: BV32, address: BV32, mem: Memory) -> bool {
+            region < mpu_num_regions(mem)
+            &&
+            mpu_region_rasr(region, mem) & MPU_RASR_ENABLE != 0
+            &&
+            (
+                // a 4GB region contains every address
+                mpu_region_size_log2(region, mem) >= 32
+                ||
+                address >> mpu_region_size_log2(region, mem)
+                    == mpu_region_rbar(region, mem) >> mpu_region_size_log2(region, mem)
+            )
+            &&
+            !mpu_region_subregion_disabled(region, address, mem)
+        }
+
+        fn mpu_highest_region(address: BV32, mem: Memory) -> BV32 {
+            // the highest numbered region wins when regions overlap
+            if mpu_region_matches(7, address, mem) {
+                7
+            } else if mpu_region_matches(6, address, mem) {
+                6
+            } else if mpu_region_matches(5, address, mem) {
+                5
+            } else if mpu_region_matches(4, address, mem) {
+                4
+            } else if mpu_region_matches(3, address, mem) {
+                3
+            } else if mpu_region_matches(2, address, mem) {
+                2
+            } else if mpu_region_matches(1, address, mem) {
+                1
+            } else if mpu_region_matches(0, address, mem) {
+                0
+            } else {
+                MPU_NO_REGION
+            }
+        }
+
+        fn mpu_region_covers(address: BV32, mem: Memory) -> bool {
+            mpu_highest_region(address, mem) != MPU_NO_REGION
+        }
+
+        fn mpu_ap_permits(ap: BV32, privileged: bool, write: bool) -> bool {
+            if ap == 1 {
+                privileged
+            } else if ap == 2 {
+                privileged || !write
+            } else if ap == 3 {
+                true
+            } else if ap == 5 {
+                privileged && !write
+            } else if ap == 6 || ap == 7 {
+                !write
+            } else {
+                false
+            }
+        }
+
+        fn mpu_background_permits(privileged: bool, mem: Memory) -> bool {
+            privileged && mpu_privdefena(mem)
+        }
+
+        fn mpu_permits(address: BV32, privileged: bool, write: bool, mem: Memory) -> bool {
+            if !mpu_enabled(mem) || address >= PPB_START {
+                true
+            } else if !mpu_region_covers(address, mem) {
+                mpu_background_permits(privileged, mem)
+            } else {
+                mpu_ap_permits(mpu_region_ap(mpu_highest_region(address, mem), mem), privileged, write)
+            }
+        }
+
+        fn mpu_permits_execute(address: BV32, privileged: bool, mem: Memory) -> bool {
+            if !mpu_enabled(mem) || address >= PPB_START {
+                true
+            } else if !mpu_region_covers(address, mem) {
+                mpu_background_permits(privileged, mem)
+            } else {
+                !mpu_region_xn(mpu_highest_region(address, mem), mem)
+                &&
+                mpu_ap_permits(mpu_region_ap(mpu_highest_region(address, mem), mem), privileged, false)
+            }
+        }
+
+        fn mpu_region_permits_privileged(region: BV32, mem: Memory) -> bool {
+            region >= mpu_num_regions(mem)
+            ||
+            mpu_region_disabled(region, mem)
+            ||
+            mpu_ap_permits(mpu_region_ap(region, mem), true, true)
+        }
+
+        // privileged code can read & write anywhere - the MPU is off or the
+        // background region is on for it and every region lets it read &
+        // write (Tock's regions are full access or privileged RW)
+        fn mpu_permits_privileged(mem: Memory) -> bool {
+            !mpu_enabled(mem)
+            ||
+            (
+                mpu_privdefena(mem)
+                &&
+                mpu_region_permits_privileged(0, mem)
+                &&
+                mpu_region_permits_privileged(1, mem)
+                &&
+                mpu_region_permits_privileged(2, mem)
+                &&
+                mpu_region_permits_privileged(3, mem)
+                &&
+                mpu_region_permits_privileged(4, mem)
+                &&
+                mpu_region_permits_privileged(5, mem)
+                &&
+                mpu_region_permits_privileged(6, mem)
+                &&
+                mpu_region_permits_privileged(7, mem)
+            )
+        }
+
+        // Tock's process layout (see TockProcessRegions in mpu.rs)
+        fn tock_process_regions_valid(regions: TockProcessRegions) -> bool {
+            // regions are aligned to their size
+            regions.flash_size_log2 >= 5
+            &&
+            regions.flash_size_log2 <= 31
+            &&
+            regions.flash_start & ((1 << regions.flash_size_log2) - 1) == 0
+            &&
+            // RAM is at least 256 bytes so it has subregions
+            regions.ram_size_log2 >= 8
+            &&
+            regions.ram_size_log2 <= 31
+            &&
+            regions.ram_start & ((1 << regions.ram_size_log2) - 1) == 0
+            &&
+            regions.ram_subregions >= 1
+            &&
+            regions.ram_subregions <= 8
+        }
+
+        fn tock_ram_rasr(regions: TockProcessRegions) -> BV32 {
+            MPU_RASR_ENABLE
+            | ((regions.ram_size_log2 - 1) << 1)
+            // disable the subregions past the app break
+            | (((0xFF << regions.ram_subregions) & 0xFF) << 8)
+            | (MPU_AP_FULL_ACCESS << 24)
+            | MPU_RASR_XN
+        }
+
+        fn tock_flash_rasr(regions: TockProcessRegions) -> BV32 {
+            MPU_RASR_ENABLE
+            | ((regions.flash_size_log2 - 1) << 1)
+            | (MPU_AP_UNPRIV_RO << 24)
+        }
+
+        fn mpu_region_disabled(region: BV32, mem: Memory) -> bool {
+            mpu_region_rasr(region, mem) & MPU_RASR_ENABLE == 0
+        }
+
+        fn tock_process_mpu_configured(mem: Memory, regions: TockProcessRegions) -> bool {
+            mpu_enabled(mem)
+            &&
+            mpu_privdefena(mem)
+            &&
+            mpu_region_rbar(TOCK_RAM_REGION, mem) == regions.ram_start
+            &&
+            mpu_region_rasr(TOCK_RAM_REGION, mem) == tock_ram_rasr(regions)
+            &&
+            mpu_region_rbar(TOCK_FLASH_REGION, mem) == regions.flash_start
+            &&
+            mpu_region_rasr(TOCK_FLASH_REGION, mem) == tock_flash_rasr(regions)
+            &&
+            mpu_region_disabled(2, mem)
+            &&
+            mpu_region_disabled(3, mem)
+            &&
+            mpu_region_disabled(4, mem)
+            &&
+            mpu_region_disabled(5, mem)
+            &&
+            mpu_region_disabled(6, mem)
+            &&
+            mpu_region_disabled(7, mem)
+        }
+
+        fn tock_process_flash_contains(address: BV32, regions: TockProcessRegions) -> bool {
+            address >= regions.flash_start
+            &&
+            address - regions.flash_start < 1 << regions.flash_size_log2
+        }
+
+        fn tock_process_ram_contains(address: BV32, regions: TockProcessRegions) -> bool {
+            // up to the app break
+            address >= regions.ram_start
+            &&
+            address - regions.ram_start < regions.ram_subregions << (regions.ram_size_log2 - 3)
+        }
     }
 }
 
 pub mod sys_tick_defs {
 
-    use super::sys_tick::{SYST_CALIB_ADDR, SYST_CSR_ADDR, SYST_CVR_ADDR, SYST_RVR_ADDR};
+    use super::sys_control::{ICSR_ADDR, ICSR_PENDSTSET};
+    use super::sys_tick::{
+        SYST_CALIB_ADDR, SYST_CSR_ADDR, SYST_CSR_COUNTFLAG, SYST_CSR_ENABLE, SYST_CSR_TICKINT,
+        SYST_CVR_ADDR, SYST_RVR_ADDR, SYST_RVR_RELOAD_MASK,
+    };
+    use super::Memory;
     use flux_rs::bitvec::BV32;
 
     flux_rs::defs! {
 
-        fn is_valid_sys_tick_read_addr(address: BV32) -> bool {
-            // all addresses are read
-            address == SYST_CSR_ADDR ||
-            address == SYST_RVR_ADDR ||
-            address == SYST_CVR_ADDR ||
-            address == SYST_CALIB_ADDR
+        fn sys_tick_csr(mem: Memory) -> BV32 {
+            map_get(mem, SYST_CSR_ADDR)
+        }
+
+        fn sys_tick_enabled(mem: Memory) -> bool {
+            sys_tick_csr(mem) & SYST_CSR_ENABLE != 0
+        }
+
+        fn sys_tick_int_enabled(mem: Memory) -> bool {
+            sys_tick_csr(mem) & SYST_CSR_TICKINT != 0
         }
 
-        fn is_valid_sys_tick_write_addr(address: BV32) -> bool {
-            // all addresses but SYS_CALIB are write
-            address == SYST_CSR_ADDR ||
-            address == SYST_RVR_ADDR ||
-            address == SYST_CVR_ADDR
+        fn sys_tick_count_flag(mem: Memory) -> bool {
+            sys_tick_csr(mem) & SYST_CSR_COUNTFLAG != 0
+        }
+
+        fn sys_tick_current(mem: Memory) -> BV32 {
+            map_get(mem, SYST_CVR_ADDR)
+        }
+
+        fn sys_tick_reload(mem: Memory) -> BV32 {
+            map_get(mem, SYST_RVR_ADDR) & SYST_RVR_RELOAD_MASK
+        }
+
+        fn sys_tick_pending(mem: Memory) -> bool {
+            map_get(mem, ICSR_ADDR) & ICSR_PENDSTSET != 0
+        }
+
+        fn mem_post_clear_sys_tick_pending(mem: Memory) -> Memory {
+            map_set(mem, ICSR_ADDR, map_get(mem, ICSR_ADDR) & bv_not(ICSR_PENDSTSET))
+        }
+
+        fn mem_post_sys_tick_wrap(mem: Memory) -> Memory {
+            // the counter hits 0: COUNTFLAG is set and SysTick pends if
+            // TICKINT is set
+            let counted = map_set(
+                map_set(mem, SYST_CVR_ADDR, 0),
+                SYST_CSR_ADDR,
+                sys_tick_csr(mem) | SYST_CSR_COUNTFLAG
+            );
+            if sys_tick_int_enabled(mem) {
+                map_set(counted, ICSR_ADDR, map_get(mem, ICSR_ADDR) | ICSR_PENDSTSET)
+            } else {
+                counted
+            }
+        }
+
+        fn mem_post_sys_tick(mem: Memory) -> Memory {
+            if !sys_tick_enabled(mem) {
+                mem
+            } else if sys_tick_current(mem) == 0 {
+                // reload on the clock after hitting 0
+                map_set(mem, SYST_CVR_ADDR, sys_tick_reload(mem))
+            } else if sys_tick_current(mem) == 1 {
+                mem_post_sys_tick_wrap(mem)
+            } else {
+                map_set(mem, SYST_CVR_ADDR, sys_tick_current(mem) - 1)
+            }
+        }
+
+        fn mem_post_sys_tick_csr_read(mem: Memory) -> Memory {
+            // COUNTFLAG is cleared by reading SYST_CSR
+            map_set(mem, SYST_CSR_ADDR, sys_tick_csr(mem) & bv_not(SYST_CSR_COUNTFLAG))
+        }
+
+        // A read of the words first..=last - reading SYST_CSR is the only
+        // read with a side effect
+        fn mem_post_read(first: BV32, last: BV32, mem: Memory) -> Memory {
+            if first <= SYST_CSR_ADDR && SYST_CSR_ADDR <= last {
+                mem_post_sys_tick_csr_read(mem)
+            } else {
+                mem
+            }
+        }
+
+        fn mem_post_sys_tick_cvr_write(mem: Memory) -> Memory {
+            // any write clears the counter and COUNTFLAG
+            map_set(
+                map_set(mem, SYST_CVR_ADDR, 0),
+                SYST_CSR_ADDR,
+                sys_tick_csr(mem) & bv_not(SYST_CSR_COUNTFLAG)
+            )
         }
     }
 }
 
-pub mod sys_control_block_defs {
-    use super::sys_control::{
-        AFSR_ADDR, AIRCR_ADDR, BFAR_ADDR, CCR_ADDR, CFSR_ADDR, CPACR_ADDR, CPUID_ADDR, DFSR_ADDR,
-        HFSR_ADDR, ICSR_ADDR, MMFAR_ADDR, SCR_ADDR, SHCSR_ADDR, SHPR1_ADDR, SHPR2_ADDR, SHPR3_ADDR,
-        VTOR_ADDR,
+pub mod bit_band_defs {
+    use super::bit_band::{
+        BIT_BAND_PERIPHERAL_ALIAS_START, BIT_BAND_PERIPHERAL_START, BIT_BAND_SRAM_ALIAS_END,
+        BIT_BAND_SRAM_ALIAS_START, BIT_BAND_SRAM_START,
     };
+    use super::Memory;
     use flux_rs::bitvec::BV32;
 
     flux_rs::defs! {
-        fn is_valid_sys_control_block_read_addr(address: BV32) -> bool {
-            // all addresses are read
-            address == CPUID_ADDR ||
-                address == ICSR_ADDR ||
-                address == VTOR_ADDR ||
-                address == AIRCR_ADDR ||
-                address == SCR_ADDR ||
-                address == CCR_ADDR ||
-                address == SHPR1_ADDR ||
-                address == SHPR2_ADDR ||
-                address == SHPR3_ADDR ||
-                address == SHCSR_ADDR ||
-                address == CFSR_ADDR ||
-                address == HFSR_ADDR ||
-                address == DFSR_ADDR ||
-                address == MMFAR_ADDR ||
-                address == BFAR_ADDR ||
-                address == AFSR_ADDR ||
-                address == CPACR_ADDR
-        }
-
-        fn is_valid_sys_control_block_write_addr(address: BV32) -> bool {
-            // all addresses but CPUID are write
-            address == ICSR_ADDR ||
-                address == VTOR_ADDR ||
-                address == AIRCR_ADDR ||
-                address == SCR_ADDR ||
-                address == CCR_ADDR ||
-                address == SHPR1_ADDR ||
-                address == SHPR2_ADDR ||
-                address == SHPR3_ADDR ||
-                address == SHCSR_ADDR ||
-                address == CFSR_ADDR ||
-                address == HFSR_ADDR ||
-                address == DFSR_ADDR ||
-                address == MMFAR_ADDR ||
-                address == BFAR_ADDR ||
-                address == AFSR_ADDR ||
-                address == CPACR_ADDR
+        fn is_bit_band_sram_alias(address: BV32) -> bool {
+            address >= BIT_BAND_SRAM_ALIAS_START && address <= BIT_BAND_SRAM_ALIAS_END
+        }
+
+        fn bit_band_word_addr(address: BV32) -> BV32 {
+            if is_bit_band_sram_alias(address) {
+                BIT_BAND_SRAM_START + (((address - BIT_BAND_SRAM_ALIAS_START) >> 7) << 2)
+            } else {
+                BIT_BAND_PERIPHERAL_START + (((address - BIT_BAND_PERIPHERAL_ALIAS_START) >> 7) << 2)
+            }
+        }
+
+        fn bit_band_bit(address: BV32) -> BV32 {
+            (address >> 2) & 31
+        }
+
+        fn bit_band_get(address: BV32, mem: Memory) -> BV32 {
+            (map_get(mem, bit_band_word_addr(address)) >> bit_band_bit(address)) & 1
+        }
+
+        fn mem_post_bit_band_write(address: BV32, mem: Memory, value: BV32) -> Memory {
+            if value & 1 == 0 {
+                map_set(
+                    mem,
+                    bit_band_word_addr(address),
+                    map_get(mem, bit_band_word_addr(address)) & bv_not(1 << bit_band_bit(address))
+                )
+            } else {
+                map_set(
+                    mem,
+                    bit_band_word_addr(address),
+                    map_get(mem, bit_band_word_addr(address)) | (1 << bit_band_bit(address))
+                )
+            }
         }
     }
 }
 
-pub mod sys_control_id_reg_defs {
+pub mod sys_control_block_defs {
     use super::sys_control::{
-        ACTLR_ADDR, CID0_ADDR, CID1_ADDR, CID2_ADDR, CID3_ADDR, ICTR_ADDR, PID0_ADDR, PID1_ADDR,
-        PID2_ADDR, PID3_ADDR, PID4_ADDR, PID5_ADDR, PID6_ADDR, PID7_ADDR, STIR_ADDR,
+        AFSR_ADDR, AIRCR_ADDR, AIRCR_PRIGROUP, AIRCR_SYSRESETREQ, AIRCR_VECTKEY, AIRCR_VECTKEYSTAT,
+        AIRCR_VECTKEY_MASK, BFAR_ADDR, CCR_ADDR, CCR_STKALIGN, CCR_USERSETMPEND, CCR_WRITE_MASK,
+        CFSR_ADDR, CPACR_ADDR, CPUID_ADDR, DFSR_ADDR, HFSR_ADDR, ICSR_ADDR, ICSR_PENDSTCLR,
+        ICSR_PENDSTSET, MMFAR_ADDR, SCR_ADDR, SCR_SEVONPEND, SCR_SLEEPDEEP, SCR_SLEEPONEXIT, SCR_WRITE_MASK,
+        SHCSR_ADDR, SHPR1_ADDR, SHPR2_ADDR, SHPR3_ADDR, VTOR_ADDR,
     };
+    use super::Memory;
     use flux_rs::bitvec::BV32;
 
     flux_rs::defs! {
-            fn is_valid_sys_control_id_reg_read_addr(address: BV32) -> bool {
-                // all but STIR are read
-                address == ICTR_ADDR ||
-                    address == ACTLR_ADDR ||
-                    address == PID4_ADDR ||
-                    address == PID5_ADDR ||
-                    address == PID6_ADDR ||
-                    address == PID7_ADDR ||
-                    address == PID0_ADDR ||
-                    address == PID1_ADDR ||
-                    address == PID2_ADDR ||
-                    address == PID3_ADDR ||
-                    address == CID0_ADDR ||
-                    address == CID1_ADDR ||
-                    address == CID2_ADDR ||
-                    address == CID3_ADDR
-            }
+        fn sys_reset_requested(mem: Memory) -> bool {
+            map_get(mem, AIRCR_ADDR) & AIRCR_SYSRESETREQ != 0
+        }
 
-            fn is_valid_sys_control_id_reg_write_addr(address: BV32) -> bool {
-                // only actlr && stir are write
-                address == ACTLR_ADDR || address == STIR_ADDR
+        fn scr_sleep_on_exit(mem: Memory) -> bool {
+            map_get(mem, SCR_ADDR) & SCR_SLEEPONEXIT != 0
+        }
+
+        fn scr_sleep_deep(mem: Memory) -> bool {
+            map_get(mem, SCR_ADDR) & SCR_SLEEPDEEP != 0
+        }
+
+        fn scr_sev_on_pend(mem: Memory) -> bool {
+            map_get(mem, SCR_ADDR) & SCR_SEVONPEND != 0
+        }
+
+        fn ccr_stkalign(mem: Memory) -> bool {
+            map_get(mem, CCR_ADDR) & CCR_STKALIGN != 0
+        }
+
+        fn ccr_usersetmpend(mem: Memory) -> bool {
+            map_get(mem, CCR_ADDR) & CCR_USERSETMPEND != 0
+        }
+
+        fn is_scb_control_addr(address: BV32) -> bool {
+            address == ICSR_ADDR || address == AIRCR_ADDR || address == SCR_ADDR || address == CCR_ADDR
+        }
+
+        fn mem_post_scb_write(address: BV32, mem: Memory, value: BV32) -> Memory {
+            if address == ICSR_ADDR {
+                // write one to pend or clear SysTick - clearing wins
+                if value & ICSR_PENDSTCLR != 0 {
+                    map_set(mem, ICSR_ADDR, map_get(mem, ICSR_ADDR) & bv_not(ICSR_PENDSTSET))
+                } else if value & ICSR_PENDSTSET != 0 {
+                    map_set(mem, ICSR_ADDR, map_get(mem, ICSR_ADDR) | ICSR_PENDSTSET)
+                } else {
+                    mem
+                }
+            } else if address == AIRCR_ADDR {
+                // writes without the key are ignored
+                if value & AIRCR_VECTKEY_MASK == AIRCR_VECTKEY {
+                    map_set(
+                        mem,
+                        AIRCR_ADDR,
+                        AIRCR_VECTKEYSTAT | (value & (AIRCR_PRIGROUP | AIRCR_SYSRESETREQ))
+                    )
+                } else {
+                    mem
+                }
+            } else if address == SCR_ADDR {
+                map_set(mem, SCR_ADDR, value & SCR_WRITE_MASK)
+            } else {
+                map_set(mem, CCR_ADDR, value & CCR_WRITE_MASK)
             }
+        }
     }
 }
 
@@ -183,56 +588,257 @@ pub mod sys_control_space_defs {
 pub mod nvic_defs {
     use super::nvic::{
         IABR_END, IABR_START, ICER_END, ICER_START, ICPR_END, ICPR_START, IPR_END, IPR_START,
-        ISER_END, ISER_START, ISPR_END, ISPR_START,
+        ISER_END, ISER_START, ISPR_END, ISPR_START, NVIC_NUM_IRQS_ADDR,
     };
+    use super::sys_control::STIR_INTID;
+    use super::Memory;
     use flux_rs::bitvec::BV32;
 
     flux_rs::defs! {
-            // all addresses are read / write as long as they are 4 byte aligned
-            fn is_valid_nvic_addr(address: BV32) -> bool {
-                if (address >= ISER_START && address <= ISER_END) {
-                    bv_urem(address - ISER_START, 4) == 0
-                } else if (address >= ICER_START && address <= ICER_END) {
-                    bv_urem(address - ICER_START, 4) == 0
-                } else if (address >= ISPR_START && address <= ISPR_END) {
-                    bv_urem(address - ISPR_START, 4) == 0
-                } else if (address >= ICPR_START && address <= ICPR_END) {
-                    bv_urem(address - ICPR_START, 4) == 0
-                } else if (address >= IABR_START && address <= IABR_END) {
-                    bv_urem(address - IABR_START, 4) == 0
-                } else if (address >= IPR_START && address <= IPR_END) {
-                    bv_urem(address - IPR_START, 4) == 0
+            // Set & clear registers (see p. B3-626 of the manual)
+            //
+            // ISER/ICER and ISPR/ICPR are two views of the same enable and
+            // pending bits: writing a 1 to a set register sets the bit,
+            // writing a 1 to a clear register clears it and writing a 0 to
+            // either does nothing. Reading either returns the current state.
+            //
+            // Each clear register sits 0x80 above the set register it
+            // aliases so the state is kept at the set register's address.
+            fn is_nvic_set_addr(address: BV32) -> bool {
+                (address >= ISER_START && address <= ISER_END)
+                ||
+                (address >= ISPR_START && address <= ISPR_END)
+            }
+
+            fn is_nvic_clear_addr(address: BV32) -> bool {
+                (address >= ICER_START && address <= ICER_END)
+                ||
+                (address >= ICPR_START && address <= ICPR_END)
+            }
+
+            // the offset of a set register word from the start of its block
+            fn nvic_set_offset(address: BV32) -> BV32 {
+                if address >= ISER_START && address <= ISER_END {
+                    address - ISER_START
                 } else {
-                    false
+                    address - ISPR_START
                 }
             }
 
-            fn is_valid_nvic_read_addr(address: BV32) -> bool {
-                // all read
-                is_valid_nvic_addr(address)
+            fn nvic_state_addr(address: BV32) -> BV32 {
+                if is_nvic_clear_addr(address) {
+                    address - 0x80
+                } else {
+                    address
+                }
             }
 
-            fn is_valid_nvic_write_addr(address: BV32) -> bool {
-                // all write
-                is_valid_nvic_addr(address)
+            // IRQ n is bit n % 32 of word n / 32
+            fn nvic_irq_offset(irq: BV32) -> BV32 {
+                (irq >> 5) << 2
             }
+
+            fn nvic_irq_bit(irq: BV32) -> BV32 {
+                1 << (irq & 31)
+            }
+
+            // the number of external interrupts the chip implements (see
+            // nvic.rs)
+            fn nvic_num_irqs(mem: Memory) -> BV32 {
+                map_get(mem, NVIC_NUM_IRQS_ADDR)
+            }
+
+            fn nvic_irq_implemented(irq: BV32, mem: Memory) -> bool {
+                irq < nvic_num_irqs(mem)
+            }
+
+            // the bits of the set register word at offset that belong to
+            // implemented interrupts - the word holds IRQs offset * 8 to
+            // offset * 8 + 31
+            fn nvic_implemented_bits(offset: BV32, mem: Memory) -> BV32 {
+                if nvic_num_irqs(mem) >= (offset << 3) + 32 {
+                    0xFFFF_FFFF
+                } else if nvic_num_irqs(mem) <= offset << 3 {
+                    0
+                } else {
+                    (1 << (nvic_num_irqs(mem) - (offset << 3))) - 1
+                }
+            }
+
+            fn nvic_irq_enabled(irq: BV32, mem: Memory) -> bool {
+                get_mem_addr(ISER_START + nvic_irq_offset(irq), mem) & nvic_irq_bit(irq) != 0
+            }
+
+            fn nvic_irq_pending(irq: BV32, mem: Memory) -> bool {
+                get_mem_addr(ISPR_START + nvic_irq_offset(irq), mem) & nvic_irq_bit(irq) != 0
+            }
+
+            fn nvic_irq_active(irq: BV32, mem: Memory) -> bool {
+                get_mem_addr(IABR_START + nvic_irq_offset(irq), mem) & nvic_irq_bit(irq) != 0
+            }
+
+            fn mem_post_stir_write(mem: Memory, value: BV32) -> Memory {
+                // pends IRQ INTID if the chip implements it
+                if nvic_irq_implemented(value & STIR_INTID, mem) {
+                    map_set(
+                        mem,
+                        ISPR_START + nvic_irq_offset(value & STIR_INTID),
+                        map_get(mem, ISPR_START + nvic_irq_offset(value & STIR_INTID))
+                            | nvic_irq_bit(value & STIR_INTID)
+                    )
+                } else {
+                    mem
+                }
+            }
+
+            fn nvic_irq_unchanged(irq: BV32, old_mem: Memory, new_mem: Memory) -> bool {
+                nvic_irq_enabled(irq, new_mem) == nvic_irq_enabled(irq, old_mem)
+                &&
+                nvic_irq_pending(irq, new_mem) == nvic_irq_pending(irq, old_mem)
+                &&
+                nvic_irq_active(irq, new_mem) == nvic_irq_active(irq, old_mem)
+            }
+    }
+}
+
+// The chip's regions (see memory_map.rs)
+pub mod memory_map_defs {
+    use super::memory_map::{
+        CHIP_BIT_BAND_ADDR, CHIP_CODE_END_ADDR, CHIP_CODE_START_ADDR, CHIP_KERNEL_STATICS_ADDR,
+        CHIP_RAM_END_ADDR, CHIP_RAM_START_ADDR, CHIP_SRAM_END_ADDR, CHIP_SRAM_START_ADDR,
+        CODE_END, CODE_START, RAM_END, RAM_START, SRAM_END, SRAM_START,
+    };
+    use super::nvic::NVIC_NUM_IRQS_ADDR;
+    use super::Memory;
+    use flux_rs::bitvec::BV32;
+
+    flux_rs::defs! {
+        fn chip_code_contains(address: BV32, mem: Memory) -> bool {
+            address >= map_get(mem, CHIP_CODE_START_ADDR) && address <= map_get(mem, CHIP_CODE_END_ADDR)
+        }
+
+        fn chip_sram_contains(address: BV32, mem: Memory) -> bool {
+            address >= map_get(mem, CHIP_SRAM_START_ADDR) && address <= map_get(mem, CHIP_SRAM_END_ADDR)
+        }
+
+        fn chip_ram_contains(address: BV32, mem: Memory) -> bool {
+            address >= map_get(mem, CHIP_RAM_START_ADDR) && address <= map_get(mem, CHIP_RAM_END_ADDR)
+        }
+
+        fn chip_bit_band(mem: Memory) -> bool {
+            map_get(mem, CHIP_BIT_BAND_ADDR) != 0
+        }
+
+        fn kernel_statics_addr(mem: Memory) -> BV32 {
+            map_get(mem, CHIP_KERNEL_STATICS_ADDR)
+        }
+
+        fn chip_unchanged(old_mem: Memory, new_mem: Memory) -> bool {
+            map_get(old_mem, CHIP_CODE_START_ADDR) == map_get(new_mem, CHIP_CODE_START_ADDR)
+            &&
+            map_get(old_mem, CHIP_CODE_END_ADDR) == map_get(new_mem, CHIP_CODE_END_ADDR)
+            &&
+            map_get(old_mem, CHIP_SRAM_START_ADDR) == map_get(new_mem, CHIP_SRAM_START_ADDR)
+            &&
+            map_get(old_mem, CHIP_SRAM_END_ADDR) == map_get(new_mem, CHIP_SRAM_END_ADDR)
+            &&
+            map_get(old_mem, CHIP_RAM_START_ADDR) == map_get(new_mem, CHIP_RAM_START_ADDR)
+            &&
+            map_get(old_mem, CHIP_RAM_END_ADDR) == map_get(new_mem, CHIP_RAM_END_ADDR)
+            &&
+            map_get(old_mem, CHIP_BIT_BAND_ADDR) == map_get(new_mem, CHIP_BIT_BAND_ADDR)
+            &&
+            kernel_statics_addr(old_mem) == kernel_statics_addr(new_mem)
+            &&
+            map_get(old_mem, NVIC_NUM_IRQS_ADDR) == map_get(new_mem, NVIC_NUM_IRQS_ADDR)
+        }
+
+        // every region spans its whole architectural range (see
+        // MemoryMap::armv7m_default)
+        fn is_armv7m_default_map(mem: Memory) -> bool {
+            map_get(mem, CHIP_CODE_START_ADDR) == CODE_START
+            &&
+            map_get(mem, CHIP_CODE_END_ADDR) == CODE_END
+            &&
+            map_get(mem, CHIP_SRAM_START_ADDR) == SRAM_START
+            &&
+            map_get(mem, CHIP_SRAM_END_ADDR) == SRAM_END
+            &&
+            map_get(mem, CHIP_RAM_START_ADDR) == RAM_START
+            &&
+            map_get(mem, CHIP_RAM_END_ADDR) == RAM_END
+            &&
+            chip_bit_band(mem)
+            &&
+            kernel_statics_addr(mem) == 0x8000_0000
+        }
+
+        // a chip without external RAM (see microcontroller_map in chip.rs)
+        fn is_microcontroller_map(
+            mem: Memory,
+            flash_start: BV32,
+            flash_end: BV32,
+            sram_start: BV32,
+            sram_end: BV32,
+            bit_band: bool
+        ) -> bool {
+            map_get(mem, CHIP_CODE_START_ADDR) == flash_start
+            &&
+            map_get(mem, CHIP_CODE_END_ADDR) == flash_end
+            &&
+            map_get(mem, CHIP_SRAM_START_ADDR) == sram_start
+            &&
+            map_get(mem, CHIP_SRAM_END_ADDR) == sram_end
+            &&
+            map_get(mem, CHIP_RAM_START_ADDR) == 0xFFFF_FFFF
+            &&
+            map_get(mem, CHIP_RAM_END_ADDR) == 0
+            &&
+            chip_bit_band(mem) == bit_band
+            &&
+            kernel_statics_addr(mem) == sram_start
+        }
     }
 }
 
+use bit_band_defs::*;
 use flux_rs::bitvec::BV32;
+use memory_map_defs::*;
 use mpu_defs::*;
 use nvic_defs::*;
+use sys_control_block_defs::*;
 use sys_control_space_defs::*;
 use sys_tick_defs::*;
 
 flux_rs::defs! {
 
-    fn is_valid_ram_addr(address: BV32) -> bool {
+    fn is_valid_ram_addr(address: BV32, mem: Memory) -> bool {
         // TODO(VR): alignment?
-        address >= RAM_START && address <= RAM_END
+        // on-chip SRAM or external RAM - the bit-band alias region is not RAM
+        (
+            address >= SRAM_START && address <= SRAM_END && !is_bit_band_sram_alias(address)
+            &&
+            chip_sram_contains(address, mem)
+        )
+        ||
+        (address >= RAM_START && address <= RAM_END && chip_ram_contains(address, mem))
+    }
+
+    fn is_valid_code_addr(address: BV32, mem: Memory) -> bool {
+        // code starts at 0 so there is no lower bound to check
+        address <= CODE_END && chip_code_contains(address, mem)
     }
 
-    fn is_valid_read_addr(address: BV32) -> bool {
+    // the bit the alias stands for is in the chip's SRAM
+    fn is_valid_bit_band_alias(address: BV32, mem: Memory) -> bool {
+        is_bit_band_sram_alias(address)
+        &&
+        chip_bit_band(mem)
+        &&
+        chip_sram_contains(bit_band_word_addr(address), mem)
+    }
+
+    fn is_valid_read_addr(address: BV32, mem: Memory) -> bool {
         is_valid_sys_control_space_read_addr(address)
         ||
         is_valid_nvic_read_addr(address)
@@ -241,10 +847,14 @@ flux_rs::defs! {
         ||
         is_valid_sys_tick_read_addr(address)
         ||
-        is_valid_ram_addr(address)
+        is_valid_code_addr(address, mem)
+        ||
+        is_valid_ram_addr(address, mem)
+        ||
+        is_valid_bit_band_alias(address, mem)
     }
 
-    fn is_valid_write_addr(address: BV32) -> bool {
+    fn is_valid_write_addr(address: BV32, mem: Memory) -> bool {
         is_valid_sys_control_space_write_addr(address)
         ||
         is_valid_nvic_write_addr(address)
@@ -253,15 +863,151 @@ flux_rs::defs! {
         ||
         is_valid_sys_tick_write_addr(address)
         ||
-        is_valid_ram_addr(address)
+        is_valid_ram_addr(address, mem)
+        ||
+        is_valid_bit_band_alias(address, mem)
     }
 
+    // the value a read returns - reading SYST_CSR also clears COUNTFLAG so
+    // the memory after a read is mem_post_read(address, address, mem)
     fn get_mem_addr(address: BV32, mem: Memory) -> BV32 {
-        map_get(mem, address)
+        if is_mpu_rbar_addr(address) || is_mpu_rasr_addr(address) {
+            mpu_read(address, mem)
+        } else if is_bit_band_sram_alias(address) {
+            bit_band_get(address, mem)
+        } else {
+            // the NVIC clear registers read back the bits of the set registers
+            map_get(mem, nvic_state_addr(address))
+        }
     }
 
     fn update_mem(address: BV32, old_mem: Memory, value: BV32) -> Map<BV32, BV32> {
-        map_set(old_mem, address, value)
+        if is_nvic_set_addr(address) {
+            // write one to set - the bits of unimplemented interrupts are WI
+            map_set(
+                old_mem,
+                address,
+                map_get(old_mem, address) | (value & nvic_implemented_bits(nvic_set_offset(address), old_mem))
+            )
+        } else if is_nvic_clear_addr(address) {
+            // write one to clear
+            map_set(old_mem, nvic_state_addr(address), map_get(old_mem, nvic_state_addr(address)) & bv_not(value))
+        } else if address == SYST_CVR_ADDR {
+            mem_post_sys_tick_cvr_write(old_mem)
+        } else if is_scb_control_addr(address) {
+            mem_post_scb_write(address, old_mem, value)
+        } else if address == STIR_ADDR {
+            mem_post_stir_write(old_mem, value)
+        } else if is_mpu_rbar_addr(address) || is_mpu_rasr_addr(address) {
+            mpu_write(address, old_mem, value)
+        } else if is_bit_band_sram_alias(address) {
+            mem_post_bit_band_write(address, old_mem, value)
+        } else {
+            map_set(old_mem, address, value)
+        }
+    }
+}
+
+// Privileged access to the PPB (see The system address map on p. B3-592 of
+// the manual)
+//
+// Unprivileged accesses to the PPB raise a BusFault - apart from writes to
+// STIR when CCR.USERSETMPEND is set. The MPU is not consulted for the PPB.
+flux_rs::defs! {
+    fn is_ppb_addr(address: BV32) -> bool {
+        address >= PPB_START && address <= PPB_END
+    }
+
+    fn ppb_permits(address: BV32, privileged: bool, write: bool, mem: Memory) -> bool {
+        !is_ppb_addr(address)
+        ||
+        privileged
+        ||
+        (write && address == STIR_ADDR && ccr_usersetmpend(mem))
+    }
+
+    // what a data access has to get past before it reaches memory
+    fn data_access_permits(address: BV32, privileged: bool, write: bool, mem: Memory) -> bool {
+        ppb_permits(address, privileged, write, mem) && mpu_permits(address, privileged, write, mem)
+    }
+}
+
+// Sub-word accesses: memory is kept as a map of words so a byte or halfword is
+// a lane of the (little endian) word holding it
+//
+// A sub-word write is a read-modify-write of the word - apart from the NVIC
+// set & clear registers which only get the lanes being written (the other
+// lanes are written as 0 which neither sets nor clears anything).
+flux_rs::defs! {
+    fn word_aligned(address: BV32) -> bool {
+        address & 0x3 == 0
+    }
+
+    fn halfword_aligned(address: BV32) -> bool {
+        address & 0x1 == 0
+    }
+
+    fn word_addr(address: BV32) -> BV32 {
+        address & 0xFFFF_FFFC
+    }
+
+    fn byte_lane_shift(address: BV32) -> BV32 {
+        (address & 0x3) << 3
+    }
+
+    fn get_mem_byte(address: BV32, mem: Memory) -> BV32 {
+        (get_mem_addr(word_addr(address), mem) >> byte_lane_shift(address)) & 0xFF
+    }
+
+    fn get_mem_halfword(address: BV32, mem: Memory) -> BV32 {
+        // only meaningful for halfword aligned addresses
+        (get_mem_addr(word_addr(address), mem) >> byte_lane_shift(address)) & 0xFFFF
+    }
+
+    fn set_byte_lanes(word: BV32, address: BV32, value: BV32, mask: BV32) -> BV32 {
+        (word & bv_not(mask << byte_lane_shift(address))) | ((value & mask) << byte_lane_shift(address))
+    }
+
+    fn sub_word_write_value(address: BV32, old_mem: Memory, value: BV32, mask: BV32) -> BV32 {
+        if is_nvic_set_addr(word_addr(address)) || is_nvic_clear_addr(word_addr(address)) {
+            (value & mask) << byte_lane_shift(address)
+        } else {
+            set_byte_lanes(get_mem_addr(word_addr(address), old_mem), address, value, mask)
+        }
+    }
+
+    fn update_mem_byte(address: BV32, old_mem: Memory, value: BV32) -> Map<BV32, BV32> {
+        update_mem(word_addr(address), old_mem, sub_word_write_value(address, old_mem, value, 0xFF))
+    }
+
+    fn update_mem_halfword(address: BV32, old_mem: Memory, value: BV32) -> Map<BV32, BV32> {
+        // only meaningful for halfword aligned addresses
+        update_mem(word_addr(address), old_mem, sub_word_write_value(address, old_mem, value, 0xFFFF))
+    }
+
+    fn is_valid_read_access(address: BV32, size: BV32, mem: Memory) -> bool {
+        // an access touches at most two words
+        is_valid_read_addr(word_addr(address), mem)
+        &&
+        is_valid_read_addr(word_addr(address + size - 1), mem)
+    }
+
+    // reading address leaves memory as it is (see mem_post_read)
+    fn is_pure_read_addr(address: BV32, mem: Memory) -> bool {
+        is_valid_read_addr(address, mem) && word_addr(address) != SYST_CSR_ADDR
+    }
+
+    fn mem_post_read_access(address: BV32, size: BV32, mem: Memory) -> Memory {
+        mem_post_read(word_addr(address), word_addr(address + size - 1), mem)
+    }
+
+    fn is_valid_write_access(address: BV32, size: BV32, mem: Memory) -> bool {
+        // sub-word writes read the word they update
+        is_valid_read_access(address, size, mem)
+        &&
+        is_valid_write_addr(word_addr(address), mem)
+        &&
+        is_valid_write_addr(word_addr(address + size - 1), mem)
     }
 }
 
@@ -0,0 +1,270 @@
+// Memory map (see The system address map on p. B3-592 of the manual)
+//
+// The architecture splits the 4GB address space into fixed 512MB regions:
+//
+// 0x0000_0000-0x1FFF_FFFF  Code        Normal             executable   flash / ROM
+// 0x2000_0000-0x3FFF_FFFF  SRAM        Normal             executable   on-chip RAM
+// 0x4000_0000-0x5FFF_FFFF  Peripheral  Device             XN           on-chip peripherals
+// 0x6000_0000-0x9FFF_FFFF  RAM         Normal             executable   external RAM
+// 0xA000_0000-0xDFFF_FFFF  Device      Device             XN           external devices
+// 0xE000_0000-0xE00F_FFFF  PPB         Strongly-ordered   XN           private peripheral bus
+//
+// A MemoryMap narrows each region down to what a chip actually implements.
+// The flux predicates in mem/flux_defs.rs are the architectural regions
+// intersected with the chip's: the code, SRAM & RAM bounds and whether
+// bit-banding is implemented are kept in the reserved part of the PPB (like
+// the MPU region state, see mpu.rs) where the predicates can see them and
+// software can not reach them. An access the predicates allow is always
+// backed by the chip.
+//
+// The map also says where the kernel's statics are linked (the ISRs in
+// cpu/isr.rs write them) so they land in RAM the chip has.
+//
+// NOTE: memory a chip puts outside the architectural region it belongs to
+// (like Apollo3's SRAM in the code region, see chip.rs) is still backed at
+// runtime but unknown to flux - such a chip can only be simulated.
+
+use super::bit_band::{bit_band_word_addr, is_bit_band_sram_alias};
+use super::flux_defs::memory_map_defs::*;
+use super::{Memory, PPB_END, PPB_START};
+use flux_rs::bitvec::BV32;
+
+#[flux_rs::constant(0x0000_0000)]
+pub const CODE_START: BV32 = BV32::new(0x0000_0000);
+#[flux_rs::constant(0x1FFF_FFFF)]
+pub const CODE_END: BV32 = BV32::new(0x1FFF_FFFF);
+
+#[flux_rs::constant(0x2000_0000)]
+pub const SRAM_START: BV32 = BV32::new(0x2000_0000);
+#[flux_rs::constant(0x3FFF_FFFF)]
+pub const SRAM_END: BV32 = BV32::new(0x3FFF_FFFF);
+
+#[flux_rs::constant(0x4000_0000)]
+pub const PERIPHERAL_START: BV32 = BV32::new(0x4000_0000);
+#[flux_rs::constant(0x5FFF_FFFF)]
+pub const PERIPHERAL_END: BV32 = BV32::new(0x5FFF_FFFF);
+
+#[flux_rs::constant(0x6000_0000)]
+pub const RAM_START: BV32 = BV32::new(0x6000_0000);
+#[flux_rs::constant(0x9FFF_FFFF)]
+pub const RAM_END: BV32 = BV32::new(0x9FFF_FFFF);
+
+#[flux_rs::constant(0xA000_0000)]
+pub const DEVICE_START: BV32 = BV32::new(0xA000_0000);
+#[flux_rs::constant(0xDFFF_FFFF)]
+pub const DEVICE_END: BV32 = BV32::new(0xDFFF_FFFF);
+
+// Where the chip's regions are kept - a chip without external RAM has an
+// empty RAM region (start > end)
+#[flux_rs::constant(0xE003_F000)]
+pub const CHIP_CODE_START_ADDR: BV32 = BV32::new(0xE003_F000);
+#[flux_rs::constant(0xE003_F004)]
+pub const CHIP_CODE_END_ADDR: BV32 = BV32::new(0xE003_F004);
+#[flux_rs::constant(0xE003_F008)]
+pub const CHIP_SRAM_START_ADDR: BV32 = BV32::new(0xE003_F008);
+#[flux_rs::constant(0xE003_F00C)]
+pub const CHIP_SRAM_END_ADDR: BV32 = BV32::new(0xE003_F00C);
+#[flux_rs::constant(0xE003_F010)]
+pub const CHIP_RAM_START_ADDR: BV32 = BV32::new(0xE003_F010);
+#[flux_rs::constant(0xE003_F014)]
+pub const CHIP_RAM_END_ADDR: BV32 = BV32::new(0xE003_F014);
+#[flux_rs::constant(0xE003_F018)]
+pub const CHIP_BIT_BAND_ADDR: BV32 = BV32::new(0xE003_F018);
+#[flux_rs::constant(0xE003_F020)]
+pub const CHIP_KERNEL_STATICS_ADDR: BV32 = BV32::new(0xE003_F020);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAttributes {
+    // idempotent, can be buffered & reordered
+    Normal,
+    // accesses have side effects
+    Device,
+    // every access acts as a memory barrier
+    StronglyOrdered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    // inclusive bounds
+    pub start: BV32,
+    pub end: BV32,
+    pub attributes: MemoryAttributes,
+    pub executable: bool,
+}
+
+impl Region {
+    pub const fn new(
+        start: BV32,
+        end: BV32,
+        attributes: MemoryAttributes,
+        executable: bool,
+    ) -> Region {
+        Region {
+            start,
+            end,
+            attributes,
+            executable,
+        }
+    }
+
+    pub fn contains(&self, address: BV32) -> bool {
+        address >= self.start && address <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryMap {
+    // flash - read only once the core is running
+    pub code: Region,
+    pub sram: Region,
+    pub peripheral: Region,
+    // external RAM - most microcontrollers have none
+    pub ram: Option<Region>,
+    pub device: Region,
+    pub ppb: Region,
+    // whether the SRAM & peripheral bit-band regions are aliased (see
+    // bit_band.rs)
+    pub bit_band: bool,
+    // Tock's SYSCALL_FIRED - APP_HARD_FAULT is the word after it
+    pub kernel_statics: BV32,
+}
+
+impl MemoryMap {
+    // Every region spans its whole architectural range
+    pub const fn armv7m_default() -> MemoryMap {
+        MemoryMap {
+            code: Region::new(CODE_START, CODE_END, MemoryAttributes::Normal, true),
+            sram: Region::new(SRAM_START, SRAM_END, MemoryAttributes::Normal, true),
+            peripheral: Region::new(
+                PERIPHERAL_START,
+                PERIPHERAL_END,
+                MemoryAttributes::Device,
+                false,
+            ),
+            ram: Some(Region::new(
+                RAM_START,
+                RAM_END,
+                MemoryAttributes::Normal,
+                true,
+            )),
+            device: Region::new(DEVICE_START, DEVICE_END, MemoryAttributes::Device, false),
+            ppb: Region::new(PPB_START, PPB_END, MemoryAttributes::StronglyOrdered, false),
+            bit_band: true,
+            kernel_statics: BV32::new(0x8000_0000),
+        }
+    }
+
+    pub fn region_of(&self, address: BV32) -> Option<&Region> {
+        [
+            Some(&self.code),
+            Some(&self.sram),
+            Some(&self.peripheral),
+            self.ram.as_ref(),
+            Some(&self.device),
+            Some(&self.ppb),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|region| region.contains(address))
+    }
+
+    pub fn attributes(&self, address: BV32) -> Option<MemoryAttributes> {
+        self.region_of(address).map(|region| region.attributes)
+    }
+
+    pub fn is_executable(&self, address: BV32) -> bool {
+        self.region_of(address)
+            .is_some_and(|region| region.executable)
+    }
+
+    // Memory we keep words for - writable RAM. The SRAM bit-band alias
+    // region is never RAM (see bit_band.rs).
+    pub fn is_ram(&self, address: BV32) -> bool {
+        if is_bit_band_sram_alias(address) {
+            return false;
+        }
+        self.sram.contains(address) || self.ram.is_some_and(|ram| ram.contains(address))
+    }
+
+    // Memory we keep words for - RAM or the (read only) code region
+    pub fn is_backed(&self, address: BV32) -> bool {
+        self.code.contains(address) || self.is_ram(address)
+    }
+}
+
+impl Default for MemoryMap {
+    fn default() -> Self {
+        MemoryMap::armv7m_default()
+    }
+}
+
+impl Memory {
+    // Puts the regions of the chip's memory map where the flux predicates
+    // look for them
+    #[flux_rs::trusted]
+    pub(super) fn load_chip_regions(&mut self) {
+        let map = self.map;
+        let (ram_start, ram_end) = match map.ram {
+            Some(ram) => (ram.start, ram.end),
+            None => (BV32::from(0xFFFF_FFFF), BV32::from(0)),
+        };
+        self.mem.set(CHIP_CODE_START_ADDR, map.code.start);
+        self.mem.set(CHIP_CODE_END_ADDR, map.code.end);
+        self.mem.set(CHIP_SRAM_START_ADDR, map.sram.start);
+        self.mem.set(CHIP_SRAM_END_ADDR, map.sram.end);
+        self.mem.set(CHIP_RAM_START_ADDR, ram_start);
+        self.mem.set(CHIP_RAM_END_ADDR, ram_end);
+        self.mem.set(CHIP_BIT_BAND_ADDR, BV32::from(map.bit_band as u32));
+        self.mem.set(CHIP_KERNEL_STATICS_ADDR, map.kernel_statics);
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> BV32[kernel_statics_addr(mem)])]
+    pub fn kernel_statics_addr(&self) -> BV32 {
+        self.mem.get_or_default(&CHIP_KERNEL_STATICS_ADDR)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[chip_bit_band(mem)])]
+    pub fn chip_bit_band(&self) -> bool {
+        self.mem.get_or_default(&CHIP_BIT_BAND_ADDR) != BV32::from(0)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@addr]) -> bool[is_valid_code_addr(addr, mem)])]
+    pub fn is_valid_code_addr(&self, address: BV32) -> bool {
+        let code_end = CODE_END;
+        let chip_start = self.mem.get_or_default(&CHIP_CODE_START_ADDR);
+        let chip_end = self.mem.get_or_default(&CHIP_CODE_END_ADDR);
+        address <= code_end && address >= chip_start && address <= chip_end
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@addr]) -> bool[is_valid_bit_band_alias(addr, mem)])]
+    pub fn is_valid_bit_band_alias(&self, address: BV32) -> bool {
+        let word_address = bit_band_word_addr(address);
+        let chip_sram_start = self.mem.get_or_default(&CHIP_SRAM_START_ADDR);
+        let chip_sram_end = self.mem.get_or_default(&CHIP_SRAM_END_ADDR);
+        is_bit_band_sram_alias(address)
+            && self.chip_bit_band()
+            && word_address >= chip_sram_start
+            && word_address <= chip_sram_end
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@addr]) -> bool[is_valid_ram_addr(addr, mem)])]
+    pub fn is_valid_ram_addr(&self, address: BV32) -> bool {
+        let sram_start = SRAM_START;
+        let sram_end = SRAM_END;
+        let ram_start = RAM_START;
+        let ram_end = RAM_END;
+        let chip_sram_start = self.mem.get_or_default(&CHIP_SRAM_START_ADDR);
+        let chip_sram_end = self.mem.get_or_default(&CHIP_SRAM_END_ADDR);
+        let chip_ram_start = self.mem.get_or_default(&CHIP_RAM_START_ADDR);
+        let chip_ram_end = self.mem.get_or_default(&CHIP_RAM_END_ADDR);
+        (address >= sram_start
+            && address <= sram_end
+            && !is_bit_band_sram_alias(address)
+            && address >= chip_sram_start
+            && address <= chip_sram_end)
+            || (address >= ram_start
+                && address <= ram_end
+                && address >= chip_ram_start
+                && address <= chip_ram_end)
+    }
+}
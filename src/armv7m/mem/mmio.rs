@@ -0,0 +1,213 @@
+// Memory mapped devices
+//
+// Board peripherals live outside the regions Memory keeps words for (see
+// memory_map.rs). A device is registered at an address range and gets every
+// read & write that lands in it, with the address made relative to the start
+// of the range.
+//
+// Devices can drive NVIC lines: tick_devices clocks every device and pends
+// the external interrupts whose lines are asserted (see p. B3-626 of the
+// manual - a level sensitive line keeps its interrupt pending while it is
+// asserted). The lines a device can assert are checked against the
+// interrupts the chip implements when it is registered.
+//
+// Device memory is not reordered but writes to it are buffered: a write
+// reaches the device when a DSB completes it (see drain_write_buffer), when a
+// later read of any device has to wait for it, or when the devices are next
+// clocked - after they have driven their interrupt lines for that tick. So a
+// handler that clears a device's event and returns without a DSB sees its
+// interrupt pended again, as it would on hardware.
+//
+// NOTE: the flux predicates know nothing about devices - a device address is
+// never a valid read or write address so verified code can not reach one.
+// Devices are only for running unverified kernel & driver code. Accesses are
+// word sized: a byte or halfword access is a read-modify-write of the word
+// holding it.
+
+use std::any::Any;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+use flux_rs::bitvec::BV32;
+
+use super::nvic::{nvic_irq_bit, nvic_irq_offset, ISPR_START};
+use super::{Memory, PPB_END, PPB_START};
+
+pub trait MmioDevice: Debug {
+    // offset is relative to the start of the range the device is registered at
+    fn read(&mut self, offset: BV32) -> BV32;
+
+    fn write(&mut self, offset: BV32, value: BV32);
+
+    // One clock
+    fn tick(&mut self) {}
+
+    // The external interrupts whose lines the device can assert
+    fn irqs(&self) -> Vec<u16> {
+        Vec::new()
+    }
+
+    // The external interrupts whose lines the device is asserting - a subset
+    // of irqs
+    fn irq_lines(&self) -> Vec<u16> {
+        Vec::new()
+    }
+
+    // A copy of the device with its current state (see Memory::duplicate)
+    fn duplicate(&self) -> Box<dyn MmioDevice>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+#[derive(Debug)]
+pub(super) struct MappedDevice {
+    // inclusive bounds
+    start: BV32,
+    end: BV32,
+    // reads can have side effects (e.g. popping a FIFO) but Memory::read
+    // only borrows memory
+    device: RefCell<Box<dyn MmioDevice>>,
+}
+
+impl MappedDevice {
+    fn contains(&self, address: BV32) -> bool {
+        address >= self.start && address <= self.end
+    }
+
+    fn duplicate(&self) -> MappedDevice {
+        MappedDevice {
+            start: self.start,
+            end: self.end,
+            device: RefCell::new(self.device.borrow().duplicate()),
+        }
+    }
+}
+
+impl Memory {
+    // Panics if the range overlaps memory, the PPB or another device, or if
+    // the device can assert an interrupt the chip does not implement
+    #[flux_rs::trusted]
+    pub fn register_device(&mut self, start: BV32, end: BV32, device: Box<dyn MmioDevice>) {
+        if start > end {
+            panic!("Device range is empty")
+        }
+        let overlaps_ppb = start <= PPB_END && end >= PPB_START;
+        let overlaps_memory = [self.map.code, self.map.sram]
+            .into_iter()
+            .chain(self.map.ram)
+            .any(|region| start <= region.end && end >= region.start);
+        let overlaps_device = self
+            .devices
+            .iter()
+            .any(|mapped| start <= mapped.end && end >= mapped.start);
+        if overlaps_ppb || overlaps_memory || overlaps_device {
+            panic!("Device range overlaps memory, the ppb or another device")
+        }
+        if device
+            .irqs()
+            .into_iter()
+            .any(|irq| !self.nvic_irq_implemented(BV32::from(irq as u32)))
+        {
+            panic!("Device interrupt is not implemented by the chip")
+        }
+        self.devices.push(MappedDevice {
+            start,
+            end,
+            device: RefCell::new(device),
+        });
+    }
+
+    #[flux_rs::trusted]
+    pub(super) fn duplicate_devices(&self) -> Vec<MappedDevice> {
+        self.devices.iter().map(MappedDevice::duplicate).collect()
+    }
+
+    #[flux_rs::trusted]
+    fn mapped_device(&self, address: BV32) -> Option<&MappedDevice> {
+        self.devices.iter().find(|mapped| mapped.contains(address))
+    }
+
+    // The device registered at start, if it is a T
+    #[flux_rs::trusted]
+    pub fn device<T: MmioDevice + 'static>(&self, start: BV32) -> Option<Ref<'_, T>> {
+        let mapped = self.devices.iter().find(|mapped| mapped.start == start)?;
+        Ref::filter_map(mapped.device.borrow(), |device| {
+            device.as_any().downcast_ref::<T>()
+        })
+        .ok()
+    }
+
+    #[flux_rs::trusted]
+    pub fn device_mut<T: MmioDevice + 'static>(&self, start: BV32) -> Option<RefMut<'_, T>> {
+        let mapped = self.devices.iter().find(|mapped| mapped.start == start)?;
+        RefMut::filter_map(mapped.device.borrow_mut(), |device| {
+            device.as_any_mut().downcast_mut::<T>()
+        })
+        .ok()
+    }
+
+    #[flux_rs::trusted]
+    pub(super) fn device_read(&self, address: BV32) -> Option<BV32> {
+        let mapped = self.mapped_device(address)?;
+        // the read waits for the writes before it
+        self.complete_device_writes();
+        let value = mapped.device.borrow_mut().read(address - mapped.start);
+        Some(value)
+    }
+
+    // Returns false when no device is registered at address
+    #[flux_rs::trusted]
+    pub(super) fn device_write(&self, address: BV32, value: BV32) -> bool {
+        if self.mapped_device(address).is_none() {
+            return false;
+        }
+        self.write_buffer.borrow_mut().push_back((address, value));
+        true
+    }
+
+    #[flux_rs::trusted]
+    fn complete_device_writes(&self) {
+        let writes: VecDeque<(BV32, BV32)> = self.write_buffer.take();
+        for (address, value) in writes {
+            if let Some(mapped) = self.mapped_device(address) {
+                mapped
+                    .device
+                    .borrow_mut()
+                    .write(address - mapped.start, value);
+            }
+        }
+    }
+
+    // Completes every buffered device write (what a DSB waits for)
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn (self: &strg Memory[@mem]) ensures self: Memory[mem])]
+    pub fn drain_write_buffer(&mut self) {
+        self.complete_device_writes();
+    }
+
+    // Clocks every device once and pends the interrupts they assert, then
+    // completes the writes still buffered
+    #[flux_rs::trusted]
+    pub fn tick_devices(&mut self) {
+        let mut lines = Vec::new();
+        for mapped in &self.devices {
+            let mut device = mapped.device.borrow_mut();
+            device.tick();
+            lines.extend(device.irq_lines());
+        }
+        self.complete_device_writes();
+        for irq in lines {
+            let irq = BV32::from(irq as u32);
+            // only a device that does not list the line in irqs gets here
+            if !self.nvic_irq_implemented(irq) {
+                continue;
+            }
+            let address = ISPR_START + nvic_irq_offset(irq);
+            let bits = *self.mem.get(&address).unwrap();
+            self.mem.set(address, bits | nvic_irq_bit(irq));
+        }
+    }
+}
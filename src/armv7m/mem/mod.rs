@@ -1,6 +1,6 @@
 // The following file implements memory layout for the ARMv7m architecture.
 //
-// NOTE: For now, just going to map the PPB - a 1 mb region reserved by the architecture. In the future, maybe we can expand this.
+// NOTE: We map the PPB - a 1 mb region reserved by the architecture - along with code and RAM (see memory_map.rs).
 //
 // Memory types:
 //
@@ -61,25 +61,54 @@ const MPU_START: BV32 = BV32::new(0xE000ED90);
 #[flux_rs::constant(0xE000EDEF)]
 const MPU_END: BV32 = BV32::new(0xE000EDEF);
 
-#[flux_rs::constant(0x6000_0000)]
-const RAM_START: BV32 = BV32::new(0x6000_0000);
-#[flux_rs::constant(0x9FFF_FFFF)]
-const RAM_END: BV32 = BV32::new(0x9FFF_FFFF);
-
+pub mod bit_band;
 pub mod flux_defs;
-mod mpu;
-mod nvic;
-mod sys_control;
-mod sys_tick;
+pub mod memory_map;
+pub mod mmio;
+pub mod mpu;
+pub mod nvic;
+pub mod scs;
+pub mod sys_control;
+pub mod sys_tick;
+pub mod trace;
 
+use bit_band::{is_bit_band_peripheral_alias, is_bit_band_sram_alias};
 use flux_defs::*;
-use mpu::{is_valid_mpu_read_addr, is_valid_mpu_write_addr};
-use nvic::{is_valid_nvic_read_addr, is_valid_nvic_write_addr};
-use sys_control::{is_valid_sys_control_space_read_addr, is_valid_sys_control_space_write_addr};
-use sys_tick::{is_valid_sys_tick_read_addr, is_valid_sys_tick_write_addr};
+use memory_map::MemoryMap;
+use mmio::MappedDevice;
+use mpu::{is_mpu_rasr_addr, is_mpu_rbar_addr, is_valid_mpu_read_addr, is_valid_mpu_write_addr};
+use nvic::{
+    is_nvic_clear_addr, is_nvic_set_addr, is_valid_nvic_read_addr, is_valid_nvic_write_addr,
+    nvic_set_offset, nvic_state_addr,
+};
+use sys_control::{
+    is_scb_control_addr, is_valid_sys_control_space_read_addr,
+    is_valid_sys_control_space_write_addr, CCR_ADDR, CCR_UNALIGN_TRP, STIR_ADDR,
+};
+use sys_tick::{
+    is_valid_sys_tick_read_addr, is_valid_sys_tick_write_addr, SYST_CSR_ADDR, SYST_CSR_COUNTFLAG,
+    SYST_CVR_ADDR,
+};
+use trace::{AccessKind, Trace};
 
+use crate::armv7m::lang::MAX_EXTERNAL_INTERRUPTS;
 use crate::flux_support::rmap::Regs;
 use flux_rs::bitvec::BV32;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+// A fault raised by a memory access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemFault {
+    // unaligned halfword or word access with CCR.UNALIGN_TRP set
+    Unaligned,
+    // data access to the address the MPU does not permit
+    MpuViolation(BV32),
+    // unprivileged access to the PPB - a precise bus error
+    PpbPrivilege(BV32),
+    // instruction fetch from the address the MPU does not permit (XN)
+    MpuExecute(BV32),
+}
 
 #[derive(Debug)]
 #[flux_rs::refined_by(
@@ -88,18 +117,198 @@ use flux_rs::bitvec::BV32;
 pub struct Memory {
     #[field(Regs<BV32, BV32>[mem])]
     mem: Mem,
+    // the regions the chip implements - flux sees them through the copy kept
+    // in mem (see memory_map.rs)
+    map: MemoryMap,
+    // board peripherals (see mmio.rs) - not part of the refinement either
+    devices: Vec<MappedDevice>,
+    // access log & watchpoints (see trace.rs) - reads only borrow memory
+    trace: RefCell<Trace>,
+    // device writes that have not reached their device yet (see mmio.rs)
+    write_buffer: RefCell<VecDeque<(BV32, BV32)>>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::new()
+    }
 }
 
 impl Memory {
+    pub fn new() -> Memory {
+        Memory::with_map(MemoryMap::armv7m_default())
+    }
+
+    pub fn with_map(map: MemoryMap) -> Memory {
+        let mut mem = Memory {
+            mem: Regs::new(),
+            map,
+            devices: Vec::new(),
+            trace: RefCell::new(Trace::default()),
+            write_buffer: RefCell::new(VecDeque::new()),
+        };
+        mem.load_chip_regions();
+        // every interrupt & MPU region we model is implemented until a chip
+        // profile says otherwise (see ChipProfile::memory)
+        mem.load(nvic::NVIC_NUM_IRQS_ADDR, BV32::from(MAX_EXTERNAL_INTERRUPTS as u32));
+        mem.load(mpu::MPU_TYPE_ADDR, BV32::from((mpu::MPU_MAX_REGIONS as u32) << 8));
+        // the PPB accessors expect every register to hold a value
+        mem.reset_ppb();
+        mem
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> Memory[mem])]
+    pub fn duplicate(&self) -> Memory {
+        Memory {
+            mem: self.mem.duplicate(),
+            map: self.map,
+            devices: self.duplicate_devices(),
+            trace: self.trace.clone(),
+            write_buffer: self.write_buffer.clone(),
+        }
+    }
+
+    pub fn map(&self) -> &MemoryMap {
+        &self.map
+    }
+
+    // Puts a word in memory without any checks - this is how code & data get
+    // into memory (including the read only code region) before reset. The
+    // word is stored as is so NVIC set & clear semantics do not apply.
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            ensures self: Memory { new_mem: new_mem == map_set(old_mem, addr, val) }
+    )]
+    pub fn load(&mut self, address: BV32, value: BV32) {
+        self.mem.set(address, value)
+    }
+
+    // Puts every PPB register back to its reset value (see ResetSCSRegs on
+    // p. B1-531 of the manual). Implementation defined registers (CPUID,
+    // VTOR, MPU_TYPE, SYST_CALIB and the ID registers) are left as they are.
+    #[flux_rs::trusted]
+    pub fn reset_ppb(&mut self) {
+        let zero = BV32::from(0);
+        let reset_values: [&[(BV32, BV32)]; 5] = [
+            &scs::sys_control_block::RESET_VALUES,
+            &scs::sys_control_id_reg::RESET_VALUES,
+            &scs::sys_tick::RESET_VALUES,
+            &scs::mpu::RESET_VALUES,
+            &scs::nvic::RESET_VALUES,
+        ];
+        for table in reset_values {
+            for &(address, value) in table {
+                self.mem.set(address, value);
+            }
+        }
+        // ICER & ICPR read back the ISER & ISPR bits so they have no state
+        for (start, end, value) in scs::nvic::RESET_ARRAYS {
+            let mut address = start;
+            while address <= end {
+                self.mem.set(address, value);
+                address = address + BV32::from(4);
+            }
+        }
+
+        // the SVD leaves out registers whose reset value is UNKNOWN - they
+        // come out of reset as 0
+        let unknown = [
+            sys_control::MMFAR_ADDR,
+            sys_control::BFAR_ADDR,
+            sys_control::AFSR_ADDR,
+            sys_control::CPACR_ADDR,
+            sys_control::ACTLR_ADDR,
+            sys_tick::SYST_RVR_ADDR,
+            sys_tick::SYST_CVR_ADDR,
+            mpu::MPU_RNR_ADDR,
+        ];
+        for address in unknown {
+            self.mem.set(address, zero);
+        }
+
+        // RBAR & RASR are windows onto the region state - every region the
+        // chip implements comes out of reset disabled
+        let mut region = zero;
+        while region < self.mpu_num_regions() {
+            self.mem.set(mpu::mpu_region_rbar_addr(region), zero);
+            self.mem.set(mpu::mpu_region_rasr_addr(region), zero);
+            region = region + BV32::from(1);
+        }
+    }
+
+    // Unprivileged code can only write STIR and only when CCR.USERSETMPEND
+    // is set - every other PPB access needs privilege
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr], bool[@privileged], bool[@write])
+            -> bool[ppb_permits(addr, privileged, write, mem)]
+    )]
+    pub fn ppb_permits(&self, address: BV32, privileged: bool, write: bool) -> bool {
+        let ppb_start = PPB_START;
+        let ppb_end = PPB_END;
+        let stir_addr = STIR_ADDR;
+        !(address >= ppb_start && address <= ppb_end)
+            || privileged
+            || (write && address == stir_addr && self.user_set_pending_enabled())
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr]) -> BV32[get_mem_addr(addr, old_mem)]
+            requires is_valid_read_addr(addr, old_mem)
+            ensures self: Memory { new_mem: new_mem == mem_post_read(addr, addr, old_mem) }
+    )]
+    pub fn read(&mut self, address: BV32) -> BV32 {
+        let value = self.read_word(address);
+        self.trace_access(AccessKind::Read, address, value, 4);
+        value
+    }
+
+    // A read that can not have a side effect - every read but one of
+    // SYST_CSR. This is for callers with only a shared borrow: exception
+    // return & syscall decoding read the stack and fault escalation reads
+    // SHCSR. It is not traced either.
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr]) -> BV32[get_mem_addr(addr, mem)]
+            requires is_pure_read_addr(addr, mem)
+    )]
+    pub fn peek(&self, address: BV32) -> BV32 {
+        self.peek_word(address)
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            requires is_valid_write_addr(addr, old_mem)
+            ensures self: Memory { new_mem: new_mem == update_mem(addr, old_mem, val) }
+    )]
+    pub fn write(&mut self, address: BV32, value: BV32) {
+        self.trace_access(AccessKind::Write, address, value, 4);
+        self.write_word(address, value)
+    }
+
+    // read & write without tracing
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr]) -> BV32[get_mem_addr(addr, old_mem)]
+            requires is_valid_read_addr(addr, old_mem)
+            ensures self: Memory { new_mem: new_mem == mem_post_read(addr, addr, old_mem) }
+    )]
+    fn read_word(&mut self, address: BV32) -> BV32 {
+        let value = self.peek_word(address);
+        let syst_csr_addr = SYST_CSR_ADDR;
+        if address == syst_csr_addr {
+            // reading SYST_CSR clears COUNTFLAG
+            self.mem.set(syst_csr_addr, value & !SYST_CSR_COUNTFLAG);
+        }
+        value
+    }
+
+    // the value a read would return without its side effects - for the
+    // read half of a read-modify-write and for the model's own lookups
     #[flux_rs::sig(
         fn (&Memory[@mem], BV32[@addr]) -> BV32[get_mem_addr(addr, mem)]
-            requires is_valid_read_addr(addr)
+            requires is_valid_read_addr(addr, mem)
     )]
-    pub fn read(&self, address: BV32) -> BV32 {
+    pub(crate) fn peek_word(&self, address: BV32) -> BV32 {
         let ppb_start = PPB_START;
         let ppb_end = PPB_END;
-        let ram_start = RAM_START;
-        let ram_end = RAM_END;
         if address >= ppb_start && address <= ppb_end {
             if !(is_valid_mpu_read_addr(address)
                 || is_valid_sys_tick_read_addr(address)
@@ -108,24 +317,48 @@ impl Memory {
             {
                 panic!("Read of Invalid PPB address")
             }
-            *self.mem.get(&address).unwrap()
-        } else if address >= ram_start && address <= ram_end {
-            *self.mem.get(&address).unwrap()
+            if is_mpu_rbar_addr(address) || is_mpu_rasr_addr(address) {
+                self.mpu_read(address)
+            } else {
+                *self.mem.get(&nvic_state_addr(address)).unwrap()
+            }
+        } else if is_bit_band_sram_alias(address) {
+            if !self.chip_bit_band() {
+                panic!("Bit-band read on a chip without bit-banding")
+            }
+            if !self.is_valid_bit_band_alias(address) {
+                panic!("Bit-band read of a bit outside the chip's SRAM")
+            }
+            self.bit_band_read(address)
+        } else if is_bit_band_peripheral_alias(address) && self.map.bit_band {
+            match self.bit_band_device_read(address) {
+                Some(value) => value,
+                None => panic!("Bit-band read of a peripheral without a device"),
+            }
+        } else if self.is_valid_code_addr(address)
+            || self.is_valid_ram_addr(address)
+            || self.map.is_backed(address)
+        {
+            // memory that was never written reads as 0
+            self.mem.get_or_default(&address)
+        } else if let Some(value) = self.device_read(address) {
+            value
         } else {
-            panic!("Read of unknown memory address (only ppb is defined)")
+            panic!("Read of unknown memory address (only ppb, code, RAM & devices are defined)")
         }
     }
 
     #[flux_rs::sig(
         fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
-            requires is_valid_write_addr(addr)
+            requires is_valid_write_addr(addr, old_mem)
             ensures self: Memory { new_mem: new_mem == update_mem(addr, old_mem, val) }
     )]
-    pub fn write(&mut self, address: BV32, value: BV32) {
+    fn write_word(&mut self, address: BV32, value: BV32) {
         let ppb_start = PPB_START;
         let ppb_end = PPB_END;
-        let ram_start = RAM_START;
-        let ram_end = RAM_END;
+        let syst_csr_addr = SYST_CSR_ADDR;
+        let syst_cvr_addr = SYST_CVR_ADDR;
+        let stir_addr = STIR_ADDR;
         if address >= ppb_start && address <= ppb_end {
             if !(is_valid_mpu_write_addr(address)
                 || is_valid_sys_tick_write_addr(address)
@@ -134,11 +367,223 @@ impl Memory {
             {
                 panic!("Write to Invalid PPB address")
             }
+            if is_nvic_set_addr(address) {
+                // write one to set - the bits of unimplemented interrupts are WI
+                let implemented = self.nvic_implemented_bits(nvic_set_offset(address));
+                let bits = *self.mem.get(&address).unwrap();
+                self.mem.set(address, bits | (value & implemented))
+            } else if is_nvic_clear_addr(address) {
+                // write one to clear
+                let state_address = nvic_state_addr(address);
+                let bits = *self.mem.get(&state_address).unwrap();
+                self.mem.set(state_address, bits & !value)
+            } else if address == syst_cvr_addr {
+                // any write clears the counter & COUNTFLAG
+                let csr = *self.mem.get(&syst_csr_addr).unwrap();
+                self.mem.set(address, BV32::from(0));
+                self.mem.set(syst_csr_addr, csr & !SYST_CSR_COUNTFLAG);
+            } else if is_scb_control_addr(address) {
+                self.scb_write(address, value)
+            } else if address == stir_addr {
+                self.stir_write(value)
+            } else if is_mpu_rbar_addr(address) || is_mpu_rasr_addr(address) {
+                self.mpu_write(address, value)
+            } else {
+                self.mem.set(address, value)
+            }
+        } else if is_bit_band_sram_alias(address) {
+            if !self.chip_bit_band() {
+                panic!("Bit-band write on a chip without bit-banding")
+            }
+            if !self.is_valid_bit_band_alias(address) {
+                panic!("Bit-band write of a bit outside the chip's SRAM")
+            }
+            self.bit_band_write(address, value)
+        } else if is_bit_band_peripheral_alias(address) && self.map.bit_band {
+            if !self.bit_band_device_write(address, value) {
+                panic!("Bit-band write to a peripheral without a device")
+            }
+        } else if self.is_valid_ram_addr(address) || self.map.is_ram(address) {
             self.mem.set(address, value)
-        } else if address >= ram_start && address <= ram_end {
-            self.mem.set(address, value)
+        } else if !self.device_write(address, value) {
+            panic!("Write to unknown memory address (only ppb, RAM & devices are defined)")
+        }
+    }
+
+    // Sub-word and unaligned accesses (see p. A3-86 of the manual)
+    //
+    // Memory is kept as a map of words (read & write above) so proofs can
+    // reason about whole words directly. Bytes and halfwords are lanes of the
+    // little endian word holding them. Unaligned halfword and word accesses
+    // either trap (CCR.UNALIGN_TRP) or are split into byte accesses - traced as
+    // the one access the instruction made.
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[get_mem_addr(CCR_ADDR, mem) & CCR_UNALIGN_TRP != 0])]
+    pub fn unaligned_trap_enabled(&self) -> bool {
+        let ccr_addr = CCR_ADDR;
+        self.peek_word(ccr_addr) & CCR_UNALIGN_TRP != BV32::from(0)
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr]) -> BV32[get_mem_byte(addr, old_mem)]
+            requires is_valid_read_access(addr, 1, old_mem)
+            ensures self: Memory { new_mem: new_mem == mem_post_read_access(addr, 1, old_mem) }
+    )]
+    pub fn read8(&mut self, address: BV32) -> BV32 {
+        let value = self.read_byte(address);
+        self.trace_access(AccessKind::Read, address, value, 1);
+        value
+    }
+
+    // read8 without tracing
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr]) -> BV32[get_mem_byte(addr, old_mem)]
+            requires is_valid_read_access(addr, 1, old_mem)
+            ensures self: Memory { new_mem: new_mem == mem_post_read_access(addr, 1, old_mem) }
+    )]
+    fn read_byte(&mut self, address: BV32) -> BV32 {
+        let word = self.read_word(address & BV32::from(0xFFFF_FFFC));
+        (word >> ((address & BV32::from(0x3)) << BV32::from(3))) & BV32::from(0xFF)
+    }
+
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr]) -> BV32[get_mem_byte(addr, mem)]
+            requires is_pure_read_addr(addr, mem)
+    )]
+    pub fn peek8(&self, address: BV32) -> BV32 {
+        let word = self.peek_word(address & BV32::from(0xFFFF_FFFC));
+        let value = (word >> ((address & BV32::from(0x3)) << BV32::from(3))) & BV32::from(0xFF);
+        self.trace_access(AccessKind::Read, address, value, 1);
+        value
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr]) -> Result<BV32{v: halfword_aligned(addr) => v == get_mem_halfword(addr, old_mem)}, MemFault>
+            requires is_valid_read_access(addr, 2, old_mem)
+            ensures self: Memory { new_mem: new_mem == mem_post_read_access(addr, 2, old_mem) }
+    )]
+    pub fn read16(&mut self, address: BV32) -> Result<BV32, MemFault> {
+        if address & BV32::from(0x1) == BV32::from(0) {
+            let word = self.read_word(address & BV32::from(0xFFFF_FFFC));
+            let value =
+                (word >> ((address & BV32::from(0x3)) << BV32::from(3))) & BV32::from(0xFFFF);
+            self.trace_access(AccessKind::Read, address, value, 2);
+            Ok(value)
+        } else if self.unaligned_trap_enabled() {
+            Err(MemFault::Unaligned)
+        } else {
+            let lo = self.read_byte(address);
+            let hi = self.read_byte(address + BV32::from(1));
+            let value = lo | (hi << BV32::from(8));
+            self.trace_access(AccessKind::Read, address, value, 2);
+            Ok(value)
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr]) -> Result<BV32{v: word_aligned(addr) => v == get_mem_addr(addr, old_mem)}, MemFault>
+            requires is_valid_read_access(addr, 4, old_mem)
+            ensures self: Memory { new_mem: new_mem == mem_post_read_access(addr, 4, old_mem) }
+    )]
+    pub fn read32(&mut self, address: BV32) -> Result<BV32, MemFault> {
+        if address & BV32::from(0x3) == BV32::from(0) {
+            Ok(self.read(address))
+        } else if self.unaligned_trap_enabled() {
+            Err(MemFault::Unaligned)
+        } else {
+            let b0 = self.read_byte(address);
+            let b1 = self.read_byte(address + BV32::from(1));
+            let b2 = self.read_byte(address + BV32::from(2));
+            let b3 = self.read_byte(address + BV32::from(3));
+            let value =
+                b0 | (b1 << BV32::from(8)) | (b2 << BV32::from(16)) | (b3 << BV32::from(24));
+            self.trace_access(AccessKind::Read, address, value, 4);
+            Ok(value)
+        }
+    }
+
+    // Writes the lanes of the word holding address that mask selects. The
+    // NVIC set & clear registers are write one to set / clear so they only
+    // get the lanes being written - the other lanes are written as 0 which
+    // leaves their bits alone. Everything else is a read-modify-write.
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val], BV32[@mask])
+            requires is_valid_read_addr(word_addr(addr), old_mem) && is_valid_write_addr(word_addr(addr), old_mem)
+            ensures self: Memory { new_mem: new_mem == update_mem(word_addr(addr), old_mem, sub_word_write_value(addr, old_mem, val, mask)) }
+    )]
+    fn write_lanes(&mut self, address: BV32, value: BV32, mask: BV32) {
+        let word_address = address & BV32::from(0xFFFF_FFFC);
+        let shift = (address & BV32::from(0x3)) << BV32::from(3);
+        if is_nvic_set_addr(word_address) || is_nvic_clear_addr(word_address) {
+            self.write_word(word_address, (value & mask) << shift)
+        } else {
+            let word = self.peek_word(word_address);
+            self.write_word(
+                word_address,
+                (word & !(mask << shift)) | ((value & mask) << shift),
+            )
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            requires is_valid_write_access(addr, 1, old_mem)
+            ensures self: Memory { new_mem: new_mem == update_mem_byte(addr, old_mem, val) }
+    )]
+    pub fn write8(&mut self, address: BV32, value: BV32) {
+        self.trace_access(AccessKind::Write, address, value & BV32::from(0xFF), 1);
+        self.write_byte(address, value);
+    }
+
+    // write8 without tracing
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            requires is_valid_write_access(addr, 1, old_mem)
+            ensures self: Memory { new_mem: new_mem == update_mem_byte(addr, old_mem, val) }
+    )]
+    fn write_byte(&mut self, address: BV32, value: BV32) {
+        self.write_lanes(address, value, BV32::from(0xFF));
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val]) -> Result<(), MemFault>
+            requires is_valid_write_access(addr, 2, old_mem)
+            ensures self: Memory { new_mem: halfword_aligned(addr) => new_mem == update_mem_halfword(addr, old_mem, val) }
+    )]
+    pub fn write16(&mut self, address: BV32, value: BV32) -> Result<(), MemFault> {
+        if address & BV32::from(0x1) == BV32::from(0) {
+            let mask = BV32::from(0xFFFF);
+            self.trace_access(AccessKind::Write, address, value & mask, 2);
+            self.write_lanes(address, value, mask);
+            Ok(())
+        } else if self.unaligned_trap_enabled() {
+            Err(MemFault::Unaligned)
+        } else {
+            self.trace_access(AccessKind::Write, address, value & BV32::from(0xFFFF), 2);
+            self.write_byte(address, value);
+            self.write_byte(address + BV32::from(1), value >> BV32::from(8));
+            Ok(())
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val]) -> Result<(), MemFault>
+            requires is_valid_write_access(addr, 4, old_mem)
+            ensures self: Memory { new_mem: word_aligned(addr) => new_mem == update_mem(addr, old_mem, val) }
+    )]
+    pub fn write32(&mut self, address: BV32, value: BV32) -> Result<(), MemFault> {
+        if address & BV32::from(0x3) == BV32::from(0) {
+            self.write(address, value);
+            Ok(())
+        } else if self.unaligned_trap_enabled() {
+            Err(MemFault::Unaligned)
         } else {
-            panic!("Write to unknown memory address (only ppb & RAM are defined)")
+            self.trace_access(AccessKind::Write, address, value, 4);
+            self.write_byte(address, value);
+            self.write_byte(address + BV32::from(1), value >> BV32::from(8));
+            self.write_byte(address + BV32::from(2), value >> BV32::from(16));
+            self.write_byte(address + BV32::from(3), value >> BV32::from(24));
+            Ok(())
         }
     }
 }
@@ -1,83 +1,424 @@
-// MPU
+// MPU (see Protected Memory System Architecture, PMSAv7 on p. B3-632 of the
+// manual)
 //
 // Here are some unimplemented blocks:
 // 0xE000EDBC - 0xE000EDEC
 // -	...	-	Reserved.
+//
+// MPU_RBAR & MPU_RASR (and their aliases) are windows onto the region MPU_RNR
+// selects. The registers of every region are kept in the reserved part of the
+// PPB at MPU_REGION_STATE_START where software can not reach them.
+//
+// An access is checked against the highest numbered enabled region that
+// contains it:
+//
+// - a region is 2^(SIZE + 1) bytes, aligned to its size
+// - regions of 256 bytes or more are split into 8 subregions which SRD can
+//   disable
+// - AP gives the privileged & unprivileged access permissions
+// - XN forbids instruction fetches
+//
+// An access no region contains uses the background (default) map if it is
+// privileged and MPU_CTRL.PRIVDEFENA is set, and faults otherwise. Accesses to
+// the PPB always use the default map.
+//
+// NOTE: MPU_CTRL.HFNMIENA is not modelled - handlers running at negative
+// priority go through the MPU like every other handler.
 
 use super::flux_defs::mpu_defs::*;
+use super::{Memory, PPB_START};
 use flux_rs::bitvec::BV32;
 
-#[flux_rs::constant(0xE000ED90)]
-pub const MPU_TYPE_ADDR: BV32 = BV32::new(0xE000ED90);
-#[flux_rs::constant(0xE000ED94)]
-pub const MPU_CTRL_ADDR: BV32 = BV32::new(0xE000ED94);
-#[flux_rs::constant(0xE000ED98)]
-pub const MPU_RNR_ADDR: BV32 = BV32::new(0xE000ED98);
-#[flux_rs::constant(0xE000ED9C)]
-pub const MPU_RBAR_ADDR: BV32 = BV32::new(0xE000ED9C);
-#[flux_rs::constant(0xE000EDA0)]
-pub const MPU_RASR_ADDR: BV32 = BV32::new(0xE000EDA0);
-#[flux_rs::constant(0xE000EDA4)]
-pub const MPU_RBAR_A1_ADDR: BV32 = BV32::new(0xE000EDA4);
-#[flux_rs::constant(0xE000EDA8)]
-pub const MPU_RASR_A1_ADDR: BV32 = BV32::new(0xE000EDA8);
-#[flux_rs::constant(0xE000EDAC)]
-pub const MPU_RBAR_A2_ADDR: BV32 = BV32::new(0xE000EDAC);
-#[flux_rs::constant(0xE000EDB0)]
-pub const MPU_RASR_A2_ADDR: BV32 = BV32::new(0xE000EDB0);
-#[flux_rs::constant(0xE000EDB4)]
-pub const MPU_RBAR_A3_ADDR: BV32 = BV32::new(0xE000EDB4);
-#[flux_rs::constant(0xE000EDB8)]
-pub const MPU_RASR_A3_ADDR: BV32 = BV32::new(0xE000EDB8);
-
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_mpu_read_addr(addr)])]
-pub fn is_valid_mpu_read_addr(address: BV32) -> bool {
-    // all address are read
-    let a1 = MPU_TYPE_ADDR;
-    let a2 = MPU_CTRL_ADDR;
-    let a3 = MPU_RNR_ADDR;
-    let a4 = MPU_RBAR_ADDR;
-    let a5 = MPU_RASR_ADDR;
-    let a6 = MPU_RBAR_A1_ADDR;
-    let a7 = MPU_RASR_A1_ADDR;
-    let a8 = MPU_RBAR_A2_ADDR;
-    let a9 = MPU_RASR_A2_ADDR;
-    let a10 = MPU_RBAR_A3_ADDR;
-    let a11 = MPU_RASR_A3_ADDR;
-    address == a1
-        || address == a2
-        || address == a3
-        || address == a4
-        || address == a5
-        || address == a6
-        || address == a7
-        || address == a8
-        || address == a9
-        || address == a10
-        || address == a11
+// The register addresses are generated from the SVD (see scs/mpu.rs)
+pub use super::scs::mpu::{
+    is_valid_mpu_read_addr, is_valid_mpu_write_addr, MPU_CTRL_ADDR, MPU_RASR_A1_ADDR,
+    MPU_RASR_A2_ADDR, MPU_RASR_A3_ADDR, MPU_RASR_ADDR, MPU_RBAR_A1_ADDR, MPU_RBAR_A2_ADDR,
+    MPU_RBAR_A3_ADDR, MPU_RBAR_ADDR, MPU_RNR_ADDR, MPU_TYPE_ADDR,
+};
+
+// the most regions we keep state for - a chip says how many it implements in
+// MPU_TYPE.DREGION (see chip.rs)
+pub const MPU_MAX_REGIONS: u8 = 8;
+
+// MPU_TYPE.DREGION
+#[flux_rs::constant(0x0000_FF00)]
+pub const MPU_TYPE_DREGION: BV32 = BV32::new(0x0000_FF00);
+
+#[flux_rs::constant(0xE003_0000)]
+pub const MPU_REGION_STATE_START: BV32 = BV32::new(0xE003_0000);
+
+// MPU_CTRL bits
+#[flux_rs::constant(0x0000_0001)]
+pub const MPU_CTRL_ENABLE: BV32 = BV32::new(0x0000_0001);
+#[flux_rs::constant(0x0000_0002)]
+pub const MPU_CTRL_HFNMIENA: BV32 = BV32::new(0x0000_0002);
+#[flux_rs::constant(0x0000_0004)]
+pub const MPU_CTRL_PRIVDEFENA: BV32 = BV32::new(0x0000_0004);
+
+// MPU_RNR.REGION
+#[flux_rs::constant(0x0000_00FF)]
+pub const MPU_RNR_REGION: BV32 = BV32::new(0x0000_00FF);
+
+// MPU_RBAR fields
+#[flux_rs::constant(0xFFFF_FFE0)]
+pub const MPU_RBAR_ADDR_MASK: BV32 = BV32::new(0xFFFF_FFE0);
+#[flux_rs::constant(0x0000_0010)]
+pub const MPU_RBAR_VALID: BV32 = BV32::new(0x0000_0010);
+#[flux_rs::constant(0x0000_000F)]
+pub const MPU_RBAR_REGION: BV32 = BV32::new(0x0000_000F);
+
+// MPU_RASR fields
+#[flux_rs::constant(0x0000_0001)]
+pub const MPU_RASR_ENABLE: BV32 = BV32::new(0x0000_0001);
+#[flux_rs::constant(0x1000_0000)]
+pub const MPU_RASR_XN: BV32 = BV32::new(0x1000_0000);
+
+// no region contains the address
+#[flux_rs::constant(0xFF)]
+pub const MPU_NO_REGION: BV32 = BV32::new(0xFF);
+
+// MPU_RASR.AP encodings Tock uses
+#[flux_rs::constant(0b010)]
+pub const MPU_AP_UNPRIV_RO: BV32 = BV32::new(0b010);
+#[flux_rs::constant(0b011)]
+pub const MPU_AP_FULL_ACCESS: BV32 = BV32::new(0b011);
+
+// Regions Tock programs for a process
+#[flux_rs::constant(0)]
+pub const TOCK_RAM_REGION: BV32 = BV32::new(0);
+#[flux_rs::constant(1)]
+pub const TOCK_FLASH_REGION: BV32 = BV32::new(1);
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_mpu_rbar_addr(addr)])]
+pub fn is_mpu_rbar_addr(address: BV32) -> bool {
+    let a1 = MPU_RBAR_ADDR;
+    let a2 = MPU_RBAR_A1_ADDR;
+    let a3 = MPU_RBAR_A2_ADDR;
+    let a4 = MPU_RBAR_A3_ADDR;
+    address == a1 || address == a2 || address == a3 || address == a4
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_mpu_rasr_addr(addr)])]
+pub fn is_mpu_rasr_addr(address: BV32) -> bool {
+    let a1 = MPU_RASR_ADDR;
+    let a2 = MPU_RASR_A1_ADDR;
+    let a3 = MPU_RASR_A2_ADDR;
+    let a4 = MPU_RASR_A3_ADDR;
+    address == a1 || address == a2 || address == a3 || address == a4
+}
+
+#[flux_rs::sig(fn (BV32[@region]) -> BV32[mpu_region_rbar_addr(region)])]
+pub fn mpu_region_rbar_addr(region: BV32) -> BV32 {
+    MPU_REGION_STATE_START + (region << BV32::from(3))
+}
+
+#[flux_rs::sig(fn (BV32[@region]) -> BV32[mpu_region_rasr_addr(region)])]
+pub fn mpu_region_rasr_addr(region: BV32) -> BV32 {
+    MPU_REGION_STATE_START + (region << BV32::from(3)) + BV32::from(4)
+}
+
+// AP encodings (see table B3-15 of the manual)
+#[flux_rs::sig(fn (BV32[@ap], bool[@privileged], bool[@write]) -> bool[mpu_ap_permits(ap, privileged, write)])]
+pub fn mpu_ap_permits(ap: BV32, privileged: bool, write: bool) -> bool {
+    if ap == BV32::from(1) {
+        // privileged RW
+        privileged
+    } else if ap == BV32::from(2) {
+        // privileged RW, unprivileged RO
+        privileged || !write
+    } else if ap == BV32::from(3) {
+        // full access
+        true
+    } else if ap == BV32::from(5) {
+        // privileged RO
+        privileged && !write
+    } else if ap == BV32::from(6) || ap == BV32::from(7) {
+        // RO
+        !write
+    } else {
+        // no access (0b000) or reserved (0b100)
+        false
+    }
+}
+
+impl Memory {
+    #[flux_rs::sig(fn (&Memory[@mem]) -> BV32[mpu_num_regions(mem)])]
+    pub fn mpu_num_regions(&self) -> BV32 {
+        let type_addr = MPU_TYPE_ADDR;
+        (self.mem.get_or_default(&type_addr) & MPU_TYPE_DREGION) >> BV32::from(8)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> BV32[mpu_rnr(mem)])]
+    fn mpu_rnr(&self) -> BV32 {
+        let rnr_addr = MPU_RNR_ADDR;
+        *self.mem.get(&rnr_addr).unwrap() & MPU_RNR_REGION
+    }
+
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr]) -> BV32[mpu_read(addr, mem)]
+            requires is_mpu_rbar_addr(addr) || is_mpu_rasr_addr(addr)
+    )]
+    pub(super) fn mpu_read(&self, address: BV32) -> BV32 {
+        let region = self.mpu_rnr();
+        if region >= self.mpu_num_regions() {
+            // RBAR & RASR are RAZ/WI when RNR selects a region past DREGION
+            BV32::from(0)
+        } else if is_mpu_rbar_addr(address) {
+            // VALID reads as 0 and REGION as the selected region
+            *self.mem.get(&mpu_region_rbar_addr(region)).unwrap() | (region & MPU_RBAR_REGION)
+        } else {
+            *self.mem.get(&mpu_region_rasr_addr(region)).unwrap()
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            requires is_mpu_rbar_addr(addr) || is_mpu_rasr_addr(addr)
+            ensures self: Memory { new_mem: new_mem == mpu_write(addr, old_mem, val) }
+    )]
+    pub(super) fn mpu_write(&mut self, address: BV32, value: BV32) {
+        if is_mpu_rbar_addr(address) {
+            if value & MPU_RBAR_VALID != BV32::from(0) {
+                // RBAR.REGION selects the region (and updates RNR)
+                let rnr_addr = MPU_RNR_ADDR;
+                let region = value & MPU_RBAR_REGION;
+                if region < self.mpu_num_regions() {
+                    self.mem.set(rnr_addr, region);
+                    self.mem
+                        .set(mpu_region_rbar_addr(region), value & MPU_RBAR_ADDR_MASK);
+                }
+            } else {
+                let region = self.mpu_rnr();
+                if region < self.mpu_num_regions() {
+                    self.mem
+                        .set(mpu_region_rbar_addr(region), value & MPU_RBAR_ADDR_MASK);
+                }
+            }
+        } else {
+            let region = self.mpu_rnr();
+            if region < self.mpu_num_regions() {
+                self.mem.set(mpu_region_rasr_addr(region), value);
+            }
+        }
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[mpu_enabled(mem)])]
+    pub fn mpu_enabled(&self) -> bool {
+        let ctrl_addr = MPU_CTRL_ADDR;
+        *self.mem.get(&ctrl_addr).unwrap() & MPU_CTRL_ENABLE != BV32::from(0)
+    }
+
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@region], BV32[@addr]) -> bool[mpu_region_matches(region, addr, mem)])]
+    fn mpu_region_matches(&self, region: BV32, address: BV32) -> bool {
+        if region >= self.mpu_num_regions() {
+            return false;
+        }
+        let rbar = *self.mem.get(&mpu_region_rbar_addr(region)).unwrap();
+        let rasr = *self.mem.get(&mpu_region_rasr_addr(region)).unwrap();
+        if rasr & MPU_RASR_ENABLE == BV32::from(0) {
+            return false;
+        }
+        let size_log2 = u32::from((rasr >> BV32::from(1)) & BV32::from(0x1F)) + 1;
+        // a 4GB region contains every address
+        let in_region =
+            size_log2 >= 32 || u32::from(address) >> size_log2 == u32::from(rbar) >> size_log2;
+        if !in_region {
+            return false;
+        }
+        if size_log2 < 8 {
+            // too small for subregions
+            return true;
+        }
+        let subregion = (u32::from(address) >> (size_log2 - 3)) & 0x7;
+        let srd = (u32::from(rasr) >> 8) & 0xFF;
+        (srd >> subregion) & 1 == 0
+    }
+
+    // The highest numbered region containing the address, or MPU_NO_REGION
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@addr]) -> BV32[mpu_highest_region(addr, mem)])]
+    fn mpu_highest_region(&self, address: BV32) -> BV32 {
+        (0..MPU_MAX_REGIONS)
+            .rev()
+            .map(|region| BV32::from(region as u32))
+            .find(|region| self.mpu_region_matches(*region, address))
+            .unwrap_or(MPU_NO_REGION)
+    }
+
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr], bool[@privileged], bool[@write])
+            -> bool[mpu_permits(addr, privileged, write, mem)]
+    )]
+    pub fn mpu_permits(&self, address: BV32, privileged: bool, write: bool) -> bool {
+        let ppb_start = PPB_START;
+        let no_region = MPU_NO_REGION;
+        if !self.mpu_enabled() || address >= ppb_start {
+            return true;
+        }
+        let region = self.mpu_highest_region(address);
+        if region == no_region {
+            // background region
+            let ctrl_addr = MPU_CTRL_ADDR;
+            let ctrl = *self.mem.get(&ctrl_addr).unwrap();
+            privileged && ctrl & MPU_CTRL_PRIVDEFENA != BV32::from(0)
+        } else {
+            let rasr = *self.mem.get(&mpu_region_rasr_addr(region)).unwrap();
+            mpu_ap_permits(
+                (rasr >> BV32::from(24)) & BV32::from(0x7),
+                privileged,
+                write,
+            )
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr], bool[@privileged])
+            -> bool[mpu_permits_execute(addr, privileged, mem)]
+    )]
+    pub fn mpu_permits_execute(&self, address: BV32, privileged: bool) -> bool {
+        let ppb_start = PPB_START;
+        let no_region = MPU_NO_REGION;
+        if !self.mpu_enabled() || address >= ppb_start {
+            return true;
+        }
+        let region = self.mpu_highest_region(address);
+        if region == no_region {
+            let ctrl_addr = MPU_CTRL_ADDR;
+            let ctrl = *self.mem.get(&ctrl_addr).unwrap();
+            privileged && ctrl & MPU_CTRL_PRIVDEFENA != BV32::from(0)
+        } else {
+            let rasr = *self.mem.get(&mpu_region_rasr_addr(region)).unwrap();
+            rasr & MPU_RASR_XN == BV32::from(0)
+                && mpu_ap_permits(
+                    (rasr >> BV32::from(24)) & BV32::from(0x7),
+                    privileged,
+                    false,
+                )
+        }
+    }
 }
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_mpu_write_addr(addr)])]
-pub fn is_valid_mpu_write_addr(address: BV32) -> bool {
-    // all address except MPU_TYPE are write
-    let a1 = MPU_CTRL_ADDR;
-    let a2 = MPU_RNR_ADDR;
-    let a3 = MPU_RBAR_ADDR;
-    let a4 = MPU_RASR_ADDR;
-    let a5 = MPU_RBAR_A1_ADDR;
-    let a6 = MPU_RASR_A1_ADDR;
-    let a7 = MPU_RBAR_A2_ADDR;
-    let a8 = MPU_RASR_A2_ADDR;
-    let a9 = MPU_RBAR_A3_ADDR;
-    let a10 = MPU_RASR_A3_ADDR;
-    address == a1
-        || address == a2
-        || address == a3
-        || address == a4
-        || address == a5
-        || address == a6
-        || address == a7
-        || address == a8
-        || address == a9
-        || address == a10
+// What the MPU lets an access to an address do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MpuPermissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Memory {
+    #[flux_rs::sig(
+        fn (&Memory[@mem], BV32[@addr], bool[@privileged]) -> MpuPermissions {p:
+            p.read == mpu_permits(addr, privileged, false, mem)
+            &&
+            p.write == mpu_permits(addr, privileged, true, mem)
+            &&
+            p.execute == mpu_permits_execute(addr, privileged, mem)
+        }
+    )]
+    pub fn mpu_permissions(&self, address: BV32, privileged: bool) -> MpuPermissions {
+        MpuPermissions {
+            read: self.mpu_permits(address, privileged, false),
+            write: self.mpu_permits(address, privileged, true),
+            execute: self.mpu_permits_execute(address, privileged),
+        }
+    }
+}
+
+// The regions Tock's Cortex-M MPU driver programs for a process:
+//
+// - the process' RAM is one region aligned to its (power of 2) size. The
+//   process owns the subregions below its app break and the kernel keeps
+//   the rest (grants) by disabling them.
+// - the process' flash is one read only region.
+//
+// Everything else (the kernel's stack & data, other processes) is left to
+// the background region which only privileged code can use.
+#[derive(Debug, Clone, Copy)]
+#[flux_rs::refined_by(
+    flash_start: BV32,
+    flash_size_log2: BV32,
+    ram_start: BV32,
+    ram_size_log2: BV32,
+    ram_subregions: BV32
+)]
+pub struct TockProcessRegions {
+    #[field(BV32[flash_start])]
+    pub flash_start: BV32,
+    #[field(BV32[flash_size_log2])]
+    pub flash_size_log2: BV32,
+    #[field(BV32[ram_start])]
+    pub ram_start: BV32,
+    #[field(BV32[ram_size_log2])]
+    pub ram_size_log2: BV32,
+    // number of RAM subregions (eighths) the process can access
+    #[field(BV32[ram_subregions])]
+    pub ram_subregions: BV32,
+}
+
+impl TockProcessRegions {
+    #[flux_rs::sig(fn (&TockProcessRegions[@regions]) -> BV32[tock_ram_rasr(regions)])]
+    fn ram_rasr(&self) -> BV32 {
+        let srd = (BV32::from(0xFF) << self.ram_subregions) & BV32::from(0xFF);
+        MPU_RASR_ENABLE
+            | ((self.ram_size_log2 - BV32::from(1)) << BV32::from(1))
+            | (srd << BV32::from(8))
+            | (MPU_AP_FULL_ACCESS << BV32::from(24))
+            | MPU_RASR_XN
+    }
+
+    #[flux_rs::sig(fn (&TockProcessRegions[@regions]) -> BV32[tock_flash_rasr(regions)])]
+    fn flash_rasr(&self) -> BV32 {
+        MPU_RASR_ENABLE
+            | ((self.flash_size_log2 - BV32::from(1)) << BV32::from(1))
+            | (MPU_AP_UNPRIV_RO << BV32::from(24))
+    }
+}
+
+impl Memory {
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@region])
+            ensures self: Memory { new_mem:
+                new_mem == update_mem(MPU_RASR_ADDR, update_mem(MPU_RBAR_ADDR, old_mem, region | MPU_RBAR_VALID), 0)
+            }
+    )]
+    fn mpu_disable_region(&mut self, region: BV32) {
+        let rbar_addr = MPU_RBAR_ADDR;
+        let rasr_addr = MPU_RASR_ADDR;
+        self.write(rbar_addr, region | MPU_RBAR_VALID);
+        self.write(rasr_addr, BV32::from(0));
+    }
+
+    // Programs the MPU for a process the way Tock does before switching to it
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], &TockProcessRegions[@regions])
+            requires tock_process_regions_valid(regions)
+            ensures self: Memory { new_mem: tock_process_mpu_configured(new_mem, regions) }
+    )]
+    pub fn configure_tock_process_mpu(&mut self, regions: &TockProcessRegions) {
+        let ctrl_addr = MPU_CTRL_ADDR;
+        let rbar_addr = MPU_RBAR_ADDR;
+        let rasr_addr = MPU_RASR_ADDR;
+        // the MPU is off while the regions change
+        self.write(ctrl_addr, BV32::from(0));
+        self.write(
+            rbar_addr,
+            regions.ram_start | MPU_RBAR_VALID | TOCK_RAM_REGION,
+        );
+        self.write(rasr_addr, regions.ram_rasr());
+        self.write(
+            rbar_addr,
+            regions.flash_start | MPU_RBAR_VALID | TOCK_FLASH_REGION,
+        );
+        self.write(rasr_addr, regions.flash_rasr());
+        // the other regions may still hold the previous process' layout
+        self.mpu_disable_region(BV32::from(2));
+        self.mpu_disable_region(BV32::from(3));
+        self.mpu_disable_region(BV32::from(4));
+        self.mpu_disable_region(BV32::from(5));
+        self.mpu_disable_region(BV32::from(6));
+        self.mpu_disable_region(BV32::from(7));
+        self.write(ctrl_addr, MPU_CTRL_ENABLE | MPU_CTRL_PRIVDEFENA);
+    }
 }
@@ -4,80 +4,136 @@ use flux_rs::bitvec::BV32;
 //
 // Some unimplemented blocks:
 //
-// 0xE000E380 -0xE000E3FC	-	-	-	Reserved
+// 0xE000E340 -0xE000E3FC	-	-	-	Reserved
 //
-// 0xE000E7F0 -0xE000ECFC	-	-	-	Reserved
+// 0xE000E5F0 -0xE000ECFC	-	-	-	Reserved
+//
+// A chip implements fewer than the 512 external interrupts the registers have
+// room for (see ICTR). The number it implements is kept in the reserved part
+// of the PPB next to the chip's regions (see memory_map.rs) - the set bits of
+// interrupts it does not implement are RAZ/WI.
 use super::flux_defs::nvic_defs::*;
+use super::sys_control::STIR_INTID;
+use super::Memory;
 
-#[flux_rs::constant(0xE000E100)]
-pub const ISER_START: BV32 = BV32::new(0xE000E100);
-#[flux_rs::constant(0xE000E13C)]
-pub const ISER_END: BV32 = BV32::new(0xE000E13C);
-//
-#[flux_rs::constant(0xE000E180)]
-pub const ICER_START: BV32 = BV32::new(0xE000E180);
-#[flux_rs::constant(0xE000E1BC)]
-pub const ICER_END: BV32 = BV32::new(0xE000E1BC);
-//
-#[flux_rs::constant(0xE000E200)]
-pub const ISPR_START: BV32 = BV32::new(0xE000E200);
-#[flux_rs::constant(0xE000E23C)]
-pub const ISPR_END: BV32 = BV32::new(0xE000E23C);
-//
-#[flux_rs::constant(0xE000E280)]
-pub const ICPR_START: BV32 = BV32::new(0xE000E280);
-#[flux_rs::constant(0xE000E2BC)]
-pub const ICPR_END: BV32 = BV32::new(0xE000E2BC);
-//
-#[flux_rs::constant(0xE000E300)]
-pub const IABR_START: BV32 = BV32::new(0xE000E300);
-#[flux_rs::constant(0xE000E37C)]
-pub const IABR_END: BV32 = BV32::new(0xE000E37C);
-//
-#[flux_rs::constant(0xE000E400)]
-pub const IPR_START: BV32 = BV32::new(0xE000E400);
-#[flux_rs::constant(0xE000E7EC)]
-pub const IPR_END: BV32 = BV32::new(0xE000E7EC);
+// The register addresses are generated from the SVD (see scs/nvic.rs)
+pub use super::scs::nvic::{
+    is_valid_nvic_read_addr, is_valid_nvic_write_addr, IABR_END, IABR_START, ICER_END, ICER_START,
+    ICPR_END, ICPR_START, IPR_END, IPR_START, ISER_END, ISER_START, ISPR_END, ISPR_START,
+};
+
+#[flux_rs::constant(0xE003_F01C)]
+pub const NVIC_NUM_IRQS_ADDR: BV32 = BV32::new(0xE003_F01C);
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_nvic_addr(addr)])]
-fn is_valid_nvic_addr(address: BV32) -> bool {
+// ISER/ICER and ISPR/ICPR alias the same enable and pending bits (see
+// nvic_defs in flux_defs.rs)
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_nvic_set_addr(addr)])]
+pub fn is_nvic_set_addr(address: BV32) -> bool {
     let iser_start = ISER_START;
     let iser_end = ISER_END;
-    let icer_start = ICER_START;
-    let icer_end = ICER_END;
     let ispr_start = ISPR_START;
     let ispr_end = ISPR_END;
+    (address >= iser_start && address <= iser_end) || (address >= ispr_start && address <= ispr_end)
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_nvic_clear_addr(addr)])]
+pub fn is_nvic_clear_addr(address: BV32) -> bool {
+    let icer_start = ICER_START;
+    let icer_end = ICER_END;
     let icpr_start = ICPR_START;
     let icpr_end = ICPR_END;
-    let iabr_start = IABR_START;
-    let iabr_end = IABR_END;
-    let ipr_start = IPR_START;
-    let ipr_end = IPR_END;
+    (address >= icer_start && address <= icer_end) || (address >= icpr_start && address <= icpr_end)
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> BV32[nvic_state_addr(addr)])]
+pub fn nvic_state_addr(address: BV32) -> BV32 {
+    if is_nvic_clear_addr(address) {
+        address - BV32::from(0x80)
+    } else {
+        address
+    }
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> BV32[nvic_set_offset(addr)])]
+pub fn nvic_set_offset(address: BV32) -> BV32 {
+    let iser_start = ISER_START;
+    let iser_end = ISER_END;
     if address >= iser_start && address <= iser_end {
-        (address - iser_start) % BV32::from(4) == BV32::from(0)
-    } else if address >= icer_start && address <= icer_end {
-        (address - icer_start) % BV32::from(4) == BV32::from(0)
-    } else if address >= ispr_start && address <= ispr_end {
-        (address - ispr_start) % BV32::from(4) == BV32::from(0)
-    } else if address >= icpr_start && address <= icpr_end {
-        (address - icpr_start) % BV32::from(4) == BV32::from(0)
-    } else if address >= iabr_start && address <= iabr_end {
-        (address - iabr_start) % BV32::from(4) == BV32::from(0)
-    } else if address >= ipr_start && address <= ipr_end {
-        (address - ipr_start) % BV32::from(4) == BV32::from(0)
+        address - iser_start
     } else {
-        false
+        address - ISPR_START
     }
 }
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_nvic_read_addr(addr)])]
-pub fn is_valid_nvic_read_addr(address: BV32) -> bool {
-    // all read
-    is_valid_nvic_addr(address)
+// IRQ n is bit n % 32 of word n / 32
+#[flux_rs::sig(fn (BV32[@irq]) -> BV32[nvic_irq_offset(irq)])]
+pub fn nvic_irq_offset(irq: BV32) -> BV32 {
+    (irq >> BV32::from(5)) << BV32::from(2)
 }
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_nvic_write_addr(addr)])]
-pub fn is_valid_nvic_write_addr(address: BV32) -> bool {
-    // all write
-    is_valid_nvic_addr(address)
+#[flux_rs::sig(fn (BV32[@irq]) -> BV32[nvic_irq_bit(irq)])]
+pub fn nvic_irq_bit(irq: BV32) -> BV32 {
+    BV32::from(1) << (irq & BV32::from(31))
+}
+
+impl Memory {
+    #[flux_rs::sig(fn (&Memory[@mem]) -> BV32[nvic_num_irqs(mem)])]
+    pub fn nvic_num_irqs(&self) -> BV32 {
+        let num_irqs_addr = NVIC_NUM_IRQS_ADDR;
+        self.mem.get_or_default(&num_irqs_addr)
+    }
+
+    // The bits of the set register word at offset that belong to implemented
+    // interrupts
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@offset]) -> BV32[nvic_implemented_bits(offset, mem)])]
+    pub(super) fn nvic_implemented_bits(&self, offset: BV32) -> BV32 {
+        let num_irqs = self.nvic_num_irqs();
+        let first_irq = offset << BV32::from(3);
+        if num_irqs >= first_irq + BV32::from(32) {
+            BV32::from(0xFFFF_FFFF)
+        } else if num_irqs <= first_irq {
+            BV32::from(0)
+        } else {
+            (BV32::from(1) << (num_irqs - first_irq)) - BV32::from(1)
+        }
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem], BV32[@irq]) -> bool[nvic_irq_implemented(irq, mem)])]
+    pub fn nvic_irq_implemented(&self, irq: BV32) -> bool {
+        irq < self.nvic_num_irqs()
+    }
+
+    // Writing to STIR pends the external interrupt in INTID (see Software
+    // Triggered Interrupt Register, STIR in the manual). An INTID past the
+    // interrupts the chip implements is ignored.
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@val])
+            ensures self: Memory { new_mem: new_mem == mem_post_stir_write(old_mem, val) }
+    )]
+    pub(super) fn stir_write(&mut self, value: BV32) {
+        let irq = value & STIR_INTID;
+        if !self.nvic_irq_implemented(irq) {
+            return;
+        }
+        let address = ISPR_START + nvic_irq_offset(irq);
+        let bits = *self.mem.get(&address).unwrap();
+        self.mem.set(address, bits | nvic_irq_bit(irq))
+    }
+
+    // Whether any external interrupt is pending - or only any enabled one
+    #[flux_rs::trusted]
+    pub fn nvic_irq_pending_any(&self, enabled_only: bool) -> bool {
+        let mut offset = BV32::from(0);
+        while ISPR_START + offset <= ISPR_END {
+            let pending = *self.mem.get(&(ISPR_START + offset)).unwrap();
+            let enabled = *self.mem.get(&(ISER_START + offset)).unwrap();
+            if (enabled_only && pending & enabled != BV32::from(0))
+                || (!enabled_only && pending != BV32::from(0))
+            {
+                return true;
+            }
+            offset = offset + BV32::from(4);
+        }
+        false
+    }
 }
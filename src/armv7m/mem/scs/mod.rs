@@ -0,0 +1,7 @@
+// Generated by svd2flux from svd/armv7m_scs.svd - do not edit by hand
+
+pub mod mpu;
+pub mod nvic;
+pub mod sys_control_block;
+pub mod sys_control_id_reg;
+pub mod sys_tick;
@@ -0,0 +1,133 @@
+// MPU - Memory Protection Unit (see table B3-11)
+//
+// Generated by svd2flux from svd/armv7m_scs.svd - do not edit by hand
+
+use flux_rs::bitvec::BV32;
+
+use flux_defs::*;
+
+// MPU_TYPE (RO) MPU Type Register
+#[flux_rs::constant(0xE000_ED90)]
+pub const MPU_TYPE_ADDR: BV32 = BV32::new(0xE000_ED90);
+// MPU_CTRL (RW) MPU Control Register
+#[flux_rs::constant(0xE000_ED94)]
+pub const MPU_CTRL_ADDR: BV32 = BV32::new(0xE000_ED94);
+#[flux_rs::constant(0x0000_0000)]
+pub const MPU_CTRL_RESET: BV32 = BV32::new(0x0000_0000);
+// MPU_RNR (RW) MPU Region Number Register
+#[flux_rs::constant(0xE000_ED98)]
+pub const MPU_RNR_ADDR: BV32 = BV32::new(0xE000_ED98);
+// MPU_RBAR (RW) MPU Region Base Address Register
+#[flux_rs::constant(0xE000_ED9C)]
+pub const MPU_RBAR_ADDR: BV32 = BV32::new(0xE000_ED9C);
+// MPU_RASR (RW) MPU Region Attribute and Size Register
+#[flux_rs::constant(0xE000_EDA0)]
+pub const MPU_RASR_ADDR: BV32 = BV32::new(0xE000_EDA0);
+// MPU_RBAR_A1 (RW) Alias of MPU_RBAR
+#[flux_rs::constant(0xE000_EDA4)]
+pub const MPU_RBAR_A1_ADDR: BV32 = BV32::new(0xE000_EDA4);
+// MPU_RBAR_A2 (RW) Alias of MPU_RBAR
+#[flux_rs::constant(0xE000_EDAC)]
+pub const MPU_RBAR_A2_ADDR: BV32 = BV32::new(0xE000_EDAC);
+// MPU_RBAR_A3 (RW) Alias of MPU_RBAR
+#[flux_rs::constant(0xE000_EDB4)]
+pub const MPU_RBAR_A3_ADDR: BV32 = BV32::new(0xE000_EDB4);
+// MPU_RASR_A1 (RW) Alias of MPU_RASR
+#[flux_rs::constant(0xE000_EDA8)]
+pub const MPU_RASR_A1_ADDR: BV32 = BV32::new(0xE000_EDA8);
+// MPU_RASR_A2 (RW) Alias of MPU_RASR
+#[flux_rs::constant(0xE000_EDB0)]
+pub const MPU_RASR_A2_ADDR: BV32 = BV32::new(0xE000_EDB0);
+// MPU_RASR_A3 (RW) Alias of MPU_RASR
+#[flux_rs::constant(0xE000_EDB8)]
+pub const MPU_RASR_A3_ADDR: BV32 = BV32::new(0xE000_EDB8);
+
+pub const RESET_VALUES: [(BV32, BV32); 1] = [(MPU_CTRL_ADDR, MPU_CTRL_RESET)];
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_mpu_read_addr(addr)])]
+pub fn is_valid_mpu_read_addr(address: BV32) -> bool {
+    let a1 = MPU_TYPE_ADDR;
+    let a2 = MPU_CTRL_ADDR;
+    let a3 = MPU_RNR_ADDR;
+    let a4 = MPU_RBAR_ADDR;
+    let a5 = MPU_RASR_ADDR;
+    let a6 = MPU_RBAR_A1_ADDR;
+    let a7 = MPU_RBAR_A2_ADDR;
+    let a8 = MPU_RBAR_A3_ADDR;
+    let a9 = MPU_RASR_A1_ADDR;
+    let a10 = MPU_RASR_A2_ADDR;
+    let a11 = MPU_RASR_A3_ADDR;
+    address == a1
+        || address == a2
+        || address == a3
+        || address == a4
+        || address == a5
+        || address == a6
+        || address == a7
+        || address == a8
+        || address == a9
+        || address == a10
+        || address == a11
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_mpu_write_addr(addr)])]
+pub fn is_valid_mpu_write_addr(address: BV32) -> bool {
+    let a1 = MPU_CTRL_ADDR;
+    let a2 = MPU_RNR_ADDR;
+    let a3 = MPU_RBAR_ADDR;
+    let a4 = MPU_RASR_ADDR;
+    let a5 = MPU_RBAR_A1_ADDR;
+    let a6 = MPU_RBAR_A2_ADDR;
+    let a7 = MPU_RBAR_A3_ADDR;
+    let a8 = MPU_RASR_A1_ADDR;
+    let a9 = MPU_RASR_A2_ADDR;
+    let a10 = MPU_RASR_A3_ADDR;
+    address == a1
+        || address == a2
+        || address == a3
+        || address == a4
+        || address == a5
+        || address == a6
+        || address == a7
+        || address == a8
+        || address == a9
+        || address == a10
+}
+
+pub mod flux_defs {
+    use super::{
+        MPU_CTRL_ADDR, MPU_RASR_A1_ADDR, MPU_RASR_A2_ADDR, MPU_RASR_A3_ADDR, MPU_RASR_ADDR,
+        MPU_RBAR_A1_ADDR, MPU_RBAR_A2_ADDR, MPU_RBAR_A3_ADDR, MPU_RBAR_ADDR, MPU_RNR_ADDR,
+        MPU_TYPE_ADDR,
+    };
+    use flux_rs::bitvec::BV32;
+
+    flux_rs::defs! {
+        fn is_valid_mpu_read_addr(address: BV32) -> bool {
+            address == MPU_TYPE_ADDR
+            || address == MPU_CTRL_ADDR
+            || address == MPU_RNR_ADDR
+            || address == MPU_RBAR_ADDR
+            || address == MPU_RASR_ADDR
+            || address == MPU_RBAR_A1_ADDR
+            || address == MPU_RBAR_A2_ADDR
+            || address == MPU_RBAR_A3_ADDR
+            || address == MPU_RASR_A1_ADDR
+            || address == MPU_RASR_A2_ADDR
+            || address == MPU_RASR_A3_ADDR
+        }
+
+        fn is_valid_mpu_write_addr(address: BV32) -> bool {
+            address == MPU_CTRL_ADDR
+            || address == MPU_RNR_ADDR
+            || address == MPU_RBAR_ADDR
+            || address == MPU_RASR_ADDR
+            || address == MPU_RBAR_A1_ADDR
+            || address == MPU_RBAR_A2_ADDR
+            || address == MPU_RBAR_A3_ADDR
+            || address == MPU_RASR_A1_ADDR
+            || address == MPU_RASR_A2_ADDR
+            || address == MPU_RASR_A3_ADDR
+        }
+    }
+}
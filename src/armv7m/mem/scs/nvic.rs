@@ -0,0 +1,123 @@
+// NVIC - Nested Vectored Interrupt Controller (see table B3-8)
+//
+// Generated by svd2flux from svd/armv7m_scs.svd - do not edit by hand
+
+use flux_rs::bitvec::BV32;
+
+use flux_defs::*;
+
+// ISER (RW) Interrupt Set-Enable Registers
+#[flux_rs::constant(0xE000_E100)]
+pub const ISER_START: BV32 = BV32::new(0xE000_E100);
+#[flux_rs::constant(0xE000_E13C)]
+pub const ISER_END: BV32 = BV32::new(0xE000_E13C);
+#[flux_rs::constant(0x0000_0000)]
+pub const ISER_RESET: BV32 = BV32::new(0x0000_0000);
+// ICER (RW) Interrupt Clear-Enable Registers
+#[flux_rs::constant(0xE000_E180)]
+pub const ICER_START: BV32 = BV32::new(0xE000_E180);
+#[flux_rs::constant(0xE000_E1BC)]
+pub const ICER_END: BV32 = BV32::new(0xE000_E1BC);
+// ISPR (RW) Interrupt Set-Pending Registers
+#[flux_rs::constant(0xE000_E200)]
+pub const ISPR_START: BV32 = BV32::new(0xE000_E200);
+#[flux_rs::constant(0xE000_E23C)]
+pub const ISPR_END: BV32 = BV32::new(0xE000_E23C);
+#[flux_rs::constant(0x0000_0000)]
+pub const ISPR_RESET: BV32 = BV32::new(0x0000_0000);
+// ICPR (RW) Interrupt Clear-Pending Registers
+#[flux_rs::constant(0xE000_E280)]
+pub const ICPR_START: BV32 = BV32::new(0xE000_E280);
+#[flux_rs::constant(0xE000_E2BC)]
+pub const ICPR_END: BV32 = BV32::new(0xE000_E2BC);
+// IABR (RO) Interrupt Active Bit Registers
+#[flux_rs::constant(0xE000_E300)]
+pub const IABR_START: BV32 = BV32::new(0xE000_E300);
+#[flux_rs::constant(0xE000_E33C)]
+pub const IABR_END: BV32 = BV32::new(0xE000_E33C);
+#[flux_rs::constant(0x0000_0000)]
+pub const IABR_RESET: BV32 = BV32::new(0x0000_0000);
+// IPR (RW) Interrupt Priority Registers
+#[flux_rs::constant(0xE000_E400)]
+pub const IPR_START: BV32 = BV32::new(0xE000_E400);
+#[flux_rs::constant(0xE000_E5EC)]
+pub const IPR_END: BV32 = BV32::new(0xE000_E5EC);
+#[flux_rs::constant(0x0000_0000)]
+pub const IPR_RESET: BV32 = BV32::new(0x0000_0000);
+
+pub const RESET_VALUES: [(BV32, BV32); 0] = [];
+
+pub const RESET_ARRAYS: [(BV32, BV32, BV32); 4] = [
+    (ISER_START, ISER_END, ISER_RESET),
+    (ISPR_START, ISPR_END, ISPR_RESET),
+    (IABR_START, IABR_END, IABR_RESET),
+    (IPR_START, IPR_END, IPR_RESET),
+];
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_nvic_read_addr(addr)])]
+pub fn is_valid_nvic_read_addr(address: BV32) -> bool {
+    let s1 = ISER_START;
+    let e1 = ISER_END;
+    let s2 = ICER_START;
+    let e2 = ICER_END;
+    let s3 = ISPR_START;
+    let e3 = ISPR_END;
+    let s4 = ICPR_START;
+    let e4 = ICPR_END;
+    let s5 = IABR_START;
+    let e5 = IABR_END;
+    let s6 = IPR_START;
+    let e6 = IPR_END;
+    (address >= s1 && address <= e1 && (address - s1) % BV32::from(4) == BV32::from(0))
+        || (address >= s2 && address <= e2 && (address - s2) % BV32::from(4) == BV32::from(0))
+        || (address >= s3 && address <= e3 && (address - s3) % BV32::from(4) == BV32::from(0))
+        || (address >= s4 && address <= e4 && (address - s4) % BV32::from(4) == BV32::from(0))
+        || (address >= s5 && address <= e5 && (address - s5) % BV32::from(4) == BV32::from(0))
+        || (address >= s6 && address <= e6 && (address - s6) % BV32::from(4) == BV32::from(0))
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_nvic_write_addr(addr)])]
+pub fn is_valid_nvic_write_addr(address: BV32) -> bool {
+    let s1 = ISER_START;
+    let e1 = ISER_END;
+    let s2 = ICER_START;
+    let e2 = ICER_END;
+    let s3 = ISPR_START;
+    let e3 = ISPR_END;
+    let s4 = ICPR_START;
+    let e4 = ICPR_END;
+    let s5 = IPR_START;
+    let e5 = IPR_END;
+    (address >= s1 && address <= e1 && (address - s1) % BV32::from(4) == BV32::from(0))
+        || (address >= s2 && address <= e2 && (address - s2) % BV32::from(4) == BV32::from(0))
+        || (address >= s3 && address <= e3 && (address - s3) % BV32::from(4) == BV32::from(0))
+        || (address >= s4 && address <= e4 && (address - s4) % BV32::from(4) == BV32::from(0))
+        || (address >= s5 && address <= e5 && (address - s5) % BV32::from(4) == BV32::from(0))
+}
+
+pub mod flux_defs {
+    use super::{
+        IABR_END, IABR_START, ICER_END, ICER_START, ICPR_END, ICPR_START, IPR_END, IPR_START,
+        ISER_END, ISER_START, ISPR_END, ISPR_START,
+    };
+    use flux_rs::bitvec::BV32;
+
+    flux_rs::defs! {
+        fn is_valid_nvic_read_addr(address: BV32) -> bool {
+            (address >= ISER_START && address <= ISER_END && bv_urem(address - ISER_START, 4) == 0)
+            || (address >= ICER_START && address <= ICER_END && bv_urem(address - ICER_START, 4) == 0)
+            || (address >= ISPR_START && address <= ISPR_END && bv_urem(address - ISPR_START, 4) == 0)
+            || (address >= ICPR_START && address <= ICPR_END && bv_urem(address - ICPR_START, 4) == 0)
+            || (address >= IABR_START && address <= IABR_END && bv_urem(address - IABR_START, 4) == 0)
+            || (address >= IPR_START && address <= IPR_END && bv_urem(address - IPR_START, 4) == 0)
+        }
+
+        fn is_valid_nvic_write_addr(address: BV32) -> bool {
+            (address >= ISER_START && address <= ISER_END && bv_urem(address - ISER_START, 4) == 0)
+            || (address >= ICER_START && address <= ICER_END && bv_urem(address - ICER_START, 4) == 0)
+            || (address >= ISPR_START && address <= ISPR_END && bv_urem(address - ISPR_START, 4) == 0)
+            || (address >= ICPR_START && address <= ICPR_END && bv_urem(address - ICPR_START, 4) == 0)
+            || (address >= IPR_START && address <= IPR_END && bv_urem(address - IPR_START, 4) == 0)
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// SYS_CONTROL_BLOCK - System Control Block (see table B3-4)
+//
+// Generated by svd2flux from svd/armv7m_scs.svd - do not edit by hand
+
+use flux_rs::bitvec::BV32;
+
+use flux_defs::*;
+
+// CPUID (RO) CPUID Base Register
+#[flux_rs::constant(0xE000_ED00)]
+pub const CPUID_ADDR: BV32 = BV32::new(0xE000_ED00);
+// ICSR (RW) Interrupt Control and State Register
+#[flux_rs::constant(0xE000_ED04)]
+pub const ICSR_ADDR: BV32 = BV32::new(0xE000_ED04);
+#[flux_rs::constant(0x0000_0000)]
+pub const ICSR_RESET: BV32 = BV32::new(0x0000_0000);
+// VTOR (RW) Vector Table Offset Register
+#[flux_rs::constant(0xE000_ED08)]
+pub const VTOR_ADDR: BV32 = BV32::new(0xE000_ED08);
+// AIRCR (RW) Application Interrupt and Reset Control Register
+#[flux_rs::constant(0xE000_ED0C)]
+pub const AIRCR_ADDR: BV32 = BV32::new(0xE000_ED0C);
+#[flux_rs::constant(0xFA05_0000)]
+pub const AIRCR_RESET: BV32 = BV32::new(0xFA05_0000);
+// SCR (RW) System Control Register
+#[flux_rs::constant(0xE000_ED10)]
+pub const SCR_ADDR: BV32 = BV32::new(0xE000_ED10);
+#[flux_rs::constant(0x0000_0000)]
+pub const SCR_RESET: BV32 = BV32::new(0x0000_0000);
+// CCR (RW) Configuration and Control Register
+#[flux_rs::constant(0xE000_ED14)]
+pub const CCR_ADDR: BV32 = BV32::new(0xE000_ED14);
+#[flux_rs::constant(0x0000_0200)]
+pub const CCR_RESET: BV32 = BV32::new(0x0000_0200);
+// SHPR1 (RW) System Handler Priority Register
+#[flux_rs::constant(0xE000_ED18)]
+pub const SHPR1_ADDR: BV32 = BV32::new(0xE000_ED18);
+#[flux_rs::constant(0x0000_0000)]
+pub const SHPR1_RESET: BV32 = BV32::new(0x0000_0000);
+// SHPR2 (RW) System Handler Priority Register
+#[flux_rs::constant(0xE000_ED1C)]
+pub const SHPR2_ADDR: BV32 = BV32::new(0xE000_ED1C);
+#[flux_rs::constant(0x0000_0000)]
+pub const SHPR2_RESET: BV32 = BV32::new(0x0000_0000);
+// SHPR3 (RW) System Handler Priority Register
+#[flux_rs::constant(0xE000_ED20)]
+pub const SHPR3_ADDR: BV32 = BV32::new(0xE000_ED20);
+#[flux_rs::constant(0x0000_0000)]
+pub const SHPR3_RESET: BV32 = BV32::new(0x0000_0000);
+// SHCSR (RW) System Handler Control and State Register
+#[flux_rs::constant(0xE000_ED24)]
+pub const SHCSR_ADDR: BV32 = BV32::new(0xE000_ED24);
+#[flux_rs::constant(0x0000_0000)]
+pub const SHCSR_RESET: BV32 = BV32::new(0x0000_0000);
+// CFSR (RW) Configurable Fault Status Register
+#[flux_rs::constant(0xE000_ED28)]
+pub const CFSR_ADDR: BV32 = BV32::new(0xE000_ED28);
+#[flux_rs::constant(0x0000_0000)]
+pub const CFSR_RESET: BV32 = BV32::new(0x0000_0000);
+// HFSR (RW) HardFault Status Register
+#[flux_rs::constant(0xE000_ED2C)]
+pub const HFSR_ADDR: BV32 = BV32::new(0xE000_ED2C);
+#[flux_rs::constant(0x0000_0000)]
+pub const HFSR_RESET: BV32 = BV32::new(0x0000_0000);
+// DFSR (RW) Debug Fault Status Register
+#[flux_rs::constant(0xE000_ED30)]
+pub const DFSR_ADDR: BV32 = BV32::new(0xE000_ED30);
+#[flux_rs::constant(0x0000_0000)]
+pub const DFSR_RESET: BV32 = BV32::new(0x0000_0000);
+// MMFAR (RW) MemManage Fault Address Register
+#[flux_rs::constant(0xE000_ED34)]
+pub const MMFAR_ADDR: BV32 = BV32::new(0xE000_ED34);
+// BFAR (RW) BusFault Address Register
+#[flux_rs::constant(0xE000_ED38)]
+pub const BFAR_ADDR: BV32 = BV32::new(0xE000_ED38);
+// AFSR (RW) Auxiliary Fault Status Register
+#[flux_rs::constant(0xE000_ED3C)]
+pub const AFSR_ADDR: BV32 = BV32::new(0xE000_ED3C);
+// CPACR (RW) Coprocessor Access Control Register
+#[flux_rs::constant(0xE000_ED88)]
+pub const CPACR_ADDR: BV32 = BV32::new(0xE000_ED88);
+
+pub const RESET_VALUES: [(BV32, BV32); 11] = [
+    (ICSR_ADDR, ICSR_RESET),
+    (AIRCR_ADDR, AIRCR_RESET),
+    (SCR_ADDR, SCR_RESET),
+    (CCR_ADDR, CCR_RESET),
+    (SHPR1_ADDR, SHPR1_RESET),
+    (SHPR2_ADDR, SHPR2_RESET),
+    (SHPR3_ADDR, SHPR3_RESET),
+    (SHCSR_ADDR, SHCSR_RESET),
+    (CFSR_ADDR, CFSR_RESET),
+    (HFSR_ADDR, HFSR_RESET),
+    (DFSR_ADDR, DFSR_RESET),
+];
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_block_read_addr(addr)])]
+pub fn is_valid_sys_control_block_read_addr(address: BV32) -> bool {
+    let a1 = CPUID_ADDR;
+    let a2 = ICSR_ADDR;
+    let a3 = VTOR_ADDR;
+    let a4 = AIRCR_ADDR;
+    let a5 = SCR_ADDR;
+    let a6 = CCR_ADDR;
+    let a7 = SHPR1_ADDR;
+    let a8 = SHPR2_ADDR;
+    let a9 = SHPR3_ADDR;
+    let a10 = SHCSR_ADDR;
+    let a11 = CFSR_ADDR;
+    let a12 = HFSR_ADDR;
+    let a13 = DFSR_ADDR;
+    let a14 = MMFAR_ADDR;
+    let a15 = BFAR_ADDR;
+    let a16 = AFSR_ADDR;
+    let a17 = CPACR_ADDR;
+    address == a1
+        || address == a2
+        || address == a3
+        || address == a4
+        || address == a5
+        || address == a6
+        || address == a7
+        || address == a8
+        || address == a9
+        || address == a10
+        || address == a11
+        || address == a12
+        || address == a13
+        || address == a14
+        || address == a15
+        || address == a16
+        || address == a17
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_block_write_addr(addr)])]
+pub fn is_valid_sys_control_block_write_addr(address: BV32) -> bool {
+    let a1 = ICSR_ADDR;
+    let a2 = VTOR_ADDR;
+    let a3 = AIRCR_ADDR;
+    let a4 = SCR_ADDR;
+    let a5 = CCR_ADDR;
+    let a6 = SHPR1_ADDR;
+    let a7 = SHPR2_ADDR;
+    let a8 = SHPR3_ADDR;
+    let a9 = SHCSR_ADDR;
+    let a10 = CFSR_ADDR;
+    let a11 = HFSR_ADDR;
+    let a12 = DFSR_ADDR;
+    let a13 = MMFAR_ADDR;
+    let a14 = BFAR_ADDR;
+    let a15 = AFSR_ADDR;
+    let a16 = CPACR_ADDR;
+    address == a1
+        || address == a2
+        || address == a3
+        || address == a4
+        || address == a5
+        || address == a6
+        || address == a7
+        || address == a8
+        || address == a9
+        || address == a10
+        || address == a11
+        || address == a12
+        || address == a13
+        || address == a14
+        || address == a15
+        || address == a16
+}
+
+pub mod flux_defs {
+    use super::{
+        AFSR_ADDR, AIRCR_ADDR, BFAR_ADDR, CCR_ADDR, CFSR_ADDR, CPACR_ADDR, CPUID_ADDR, DFSR_ADDR,
+        HFSR_ADDR, ICSR_ADDR, MMFAR_ADDR, SCR_ADDR, SHCSR_ADDR, SHPR1_ADDR, SHPR2_ADDR, SHPR3_ADDR,
+        VTOR_ADDR,
+    };
+    use flux_rs::bitvec::BV32;
+
+    flux_rs::defs! {
+        fn is_valid_sys_control_block_read_addr(address: BV32) -> bool {
+            address == CPUID_ADDR
+            || address == ICSR_ADDR
+            || address == VTOR_ADDR
+            || address == AIRCR_ADDR
+            || address == SCR_ADDR
+            || address == CCR_ADDR
+            || address == SHPR1_ADDR
+            || address == SHPR2_ADDR
+            || address == SHPR3_ADDR
+            || address == SHCSR_ADDR
+            || address == CFSR_ADDR
+            || address == HFSR_ADDR
+            || address == DFSR_ADDR
+            || address == MMFAR_ADDR
+            || address == BFAR_ADDR
+            || address == AFSR_ADDR
+            || address == CPACR_ADDR
+        }
+
+        fn is_valid_sys_control_block_write_addr(address: BV32) -> bool {
+            address == ICSR_ADDR
+            || address == VTOR_ADDR
+            || address == AIRCR_ADDR
+            || address == SCR_ADDR
+            || address == CCR_ADDR
+            || address == SHPR1_ADDR
+            || address == SHPR2_ADDR
+            || address == SHPR3_ADDR
+            || address == SHCSR_ADDR
+            || address == CFSR_ADDR
+            || address == HFSR_ADDR
+            || address == DFSR_ADDR
+            || address == MMFAR_ADDR
+            || address == BFAR_ADDR
+            || address == AFSR_ADDR
+            || address == CPACR_ADDR
+        }
+    }
+}
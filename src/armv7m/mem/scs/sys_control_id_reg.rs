@@ -0,0 +1,126 @@
+// SYS_CONTROL_ID_REG - System control and ID registers outside the SCB (see table B3-5)
+//
+// Generated by svd2flux from svd/armv7m_scs.svd - do not edit by hand
+
+use flux_rs::bitvec::BV32;
+
+use flux_defs::*;
+
+// ICTR (RO) Interrupt Controller Type Register
+#[flux_rs::constant(0xE000_E004)]
+pub const ICTR_ADDR: BV32 = BV32::new(0xE000_E004);
+// ACTLR (RW) Auxiliary Control Register
+#[flux_rs::constant(0xE000_E008)]
+pub const ACTLR_ADDR: BV32 = BV32::new(0xE000_E008);
+// STIR (WO) Software Triggered Interrupt Register
+#[flux_rs::constant(0xE000_EF00)]
+pub const STIR_ADDR: BV32 = BV32::new(0xE000_EF00);
+// PID4 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFD0)]
+pub const PID4_ADDR: BV32 = BV32::new(0xE000_EFD0);
+// PID5 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFD4)]
+pub const PID5_ADDR: BV32 = BV32::new(0xE000_EFD4);
+// PID6 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFD8)]
+pub const PID6_ADDR: BV32 = BV32::new(0xE000_EFD8);
+// PID7 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFDC)]
+pub const PID7_ADDR: BV32 = BV32::new(0xE000_EFDC);
+// PID0 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFE0)]
+pub const PID0_ADDR: BV32 = BV32::new(0xE000_EFE0);
+// PID1 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFE4)]
+pub const PID1_ADDR: BV32 = BV32::new(0xE000_EFE4);
+// PID2 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFE8)]
+pub const PID2_ADDR: BV32 = BV32::new(0xE000_EFE8);
+// PID3 (RO) Peripheral Identification Register
+#[flux_rs::constant(0xE000_EFEC)]
+pub const PID3_ADDR: BV32 = BV32::new(0xE000_EFEC);
+// CID0 (RO) Component Identification Register
+#[flux_rs::constant(0xE000_EFF0)]
+pub const CID0_ADDR: BV32 = BV32::new(0xE000_EFF0);
+// CID1 (RO) Component Identification Register
+#[flux_rs::constant(0xE000_EFF4)]
+pub const CID1_ADDR: BV32 = BV32::new(0xE000_EFF4);
+// CID2 (RO) Component Identification Register
+#[flux_rs::constant(0xE000_EFF8)]
+pub const CID2_ADDR: BV32 = BV32::new(0xE000_EFF8);
+// CID3 (RO) Component Identification Register
+#[flux_rs::constant(0xE000_EFFC)]
+pub const CID3_ADDR: BV32 = BV32::new(0xE000_EFFC);
+
+pub const RESET_VALUES: [(BV32, BV32); 0] = [];
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_id_reg_read_addr(addr)])]
+pub fn is_valid_sys_control_id_reg_read_addr(address: BV32) -> bool {
+    let a1 = ICTR_ADDR;
+    let a2 = ACTLR_ADDR;
+    let a3 = PID4_ADDR;
+    let a4 = PID5_ADDR;
+    let a5 = PID6_ADDR;
+    let a6 = PID7_ADDR;
+    let a7 = PID0_ADDR;
+    let a8 = PID1_ADDR;
+    let a9 = PID2_ADDR;
+    let a10 = PID3_ADDR;
+    let a11 = CID0_ADDR;
+    let a12 = CID1_ADDR;
+    let a13 = CID2_ADDR;
+    let a14 = CID3_ADDR;
+    address == a1
+        || address == a2
+        || address == a3
+        || address == a4
+        || address == a5
+        || address == a6
+        || address == a7
+        || address == a8
+        || address == a9
+        || address == a10
+        || address == a11
+        || address == a12
+        || address == a13
+        || address == a14
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_id_reg_write_addr(addr)])]
+pub fn is_valid_sys_control_id_reg_write_addr(address: BV32) -> bool {
+    let a1 = ACTLR_ADDR;
+    let a2 = STIR_ADDR;
+    address == a1 || address == a2
+}
+
+pub mod flux_defs {
+    use super::{
+        ACTLR_ADDR, CID0_ADDR, CID1_ADDR, CID2_ADDR, CID3_ADDR, ICTR_ADDR, PID0_ADDR, PID1_ADDR,
+        PID2_ADDR, PID3_ADDR, PID4_ADDR, PID5_ADDR, PID6_ADDR, PID7_ADDR, STIR_ADDR,
+    };
+    use flux_rs::bitvec::BV32;
+
+    flux_rs::defs! {
+        fn is_valid_sys_control_id_reg_read_addr(address: BV32) -> bool {
+            address == ICTR_ADDR
+            || address == ACTLR_ADDR
+            || address == PID4_ADDR
+            || address == PID5_ADDR
+            || address == PID6_ADDR
+            || address == PID7_ADDR
+            || address == PID0_ADDR
+            || address == PID1_ADDR
+            || address == PID2_ADDR
+            || address == PID3_ADDR
+            || address == CID0_ADDR
+            || address == CID1_ADDR
+            || address == CID2_ADDR
+            || address == CID3_ADDR
+        }
+
+        fn is_valid_sys_control_id_reg_write_addr(address: BV32) -> bool {
+            address == ACTLR_ADDR
+            || address == STIR_ADDR
+        }
+    }
+}
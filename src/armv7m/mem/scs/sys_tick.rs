@@ -0,0 +1,61 @@
+// SYS_TICK - The system timer, SysTick (see table B3-7)
+//
+// Generated by svd2flux from svd/armv7m_scs.svd - do not edit by hand
+
+use flux_rs::bitvec::BV32;
+
+use flux_defs::*;
+
+// SYST_CSR (RW) SysTick Control and Status Register
+#[flux_rs::constant(0xE000_E010)]
+pub const SYST_CSR_ADDR: BV32 = BV32::new(0xE000_E010);
+#[flux_rs::constant(0x0000_0000)]
+pub const SYST_CSR_RESET: BV32 = BV32::new(0x0000_0000);
+// SYST_RVR (RW) SysTick Reload Value Register
+#[flux_rs::constant(0xE000_E014)]
+pub const SYST_RVR_ADDR: BV32 = BV32::new(0xE000_E014);
+// SYST_CVR (RW) SysTick Current Value Register
+#[flux_rs::constant(0xE000_E018)]
+pub const SYST_CVR_ADDR: BV32 = BV32::new(0xE000_E018);
+// SYST_CALIB (RO) SysTick Calibration value Register
+#[flux_rs::constant(0xE000_E01C)]
+pub const SYST_CALIB_ADDR: BV32 = BV32::new(0xE000_E01C);
+
+pub const RESET_VALUES: [(BV32, BV32); 1] = [(SYST_CSR_ADDR, SYST_CSR_RESET)];
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_tick_read_addr(addr)])]
+pub fn is_valid_sys_tick_read_addr(address: BV32) -> bool {
+    let a1 = SYST_CSR_ADDR;
+    let a2 = SYST_RVR_ADDR;
+    let a3 = SYST_CVR_ADDR;
+    let a4 = SYST_CALIB_ADDR;
+    address == a1 || address == a2 || address == a3 || address == a4
+}
+
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_tick_write_addr(addr)])]
+pub fn is_valid_sys_tick_write_addr(address: BV32) -> bool {
+    let a1 = SYST_CSR_ADDR;
+    let a2 = SYST_RVR_ADDR;
+    let a3 = SYST_CVR_ADDR;
+    address == a1 || address == a2 || address == a3
+}
+
+pub mod flux_defs {
+    use super::{SYST_CALIB_ADDR, SYST_CSR_ADDR, SYST_CVR_ADDR, SYST_RVR_ADDR};
+    use flux_rs::bitvec::BV32;
+
+    flux_rs::defs! {
+        fn is_valid_sys_tick_read_addr(address: BV32) -> bool {
+            address == SYST_CSR_ADDR
+            || address == SYST_RVR_ADDR
+            || address == SYST_CVR_ADDR
+            || address == SYST_CALIB_ADDR
+        }
+
+        fn is_valid_sys_tick_write_addr(address: BV32) -> bool {
+            address == SYST_CSR_ADDR
+            || address == SYST_RVR_ADDR
+            || address == SYST_CVR_ADDR
+        }
+    }
+}
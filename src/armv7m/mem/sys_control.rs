@@ -22,192 +22,138 @@
 use flux_rs::bitvec::BV32;
 
 use super::flux_defs::sys_control_block_defs::*;
-use super::flux_defs::sys_control_id_reg_defs::*;
-
-// System Control Block Addresses
-#[flux_rs::constant(0xE000ED00)]
-pub const CPUID_ADDR: BV32 = BV32::new(0xE000ED00);
-#[flux_rs::constant(0xE000ED04)]
-pub const ICSR_ADDR: BV32 = BV32::new(0xE000ED04);
-#[flux_rs::constant(0xE000ED08)]
-pub const VTOR_ADDR: BV32 = BV32::new(0xE000ED08);
-#[flux_rs::constant(0xE000ED0C)]
-pub const AIRCR_ADDR: BV32 = BV32::new(0xE000ED0C);
-#[flux_rs::constant(0xE000ED10)]
-pub const SCR_ADDR: BV32 = BV32::new(0xE000ED10);
-#[flux_rs::constant(0xE000ED14)]
-pub const CCR_ADDR: BV32 = BV32::new(0xE000ED14);
-#[flux_rs::constant(0xE000ED18)]
-pub const SHPR1_ADDR: BV32 = BV32::new(0xE000ED18);
-#[flux_rs::constant(0xE000ED1C)]
-pub const SHPR2_ADDR: BV32 = BV32::new(0xE000ED1C);
-#[flux_rs::constant(0xE000ED20)]
-pub const SHPR3_ADDR: BV32 = BV32::new(0xE000ED20);
-#[flux_rs::constant(0xE000ED24)]
-pub const SHCSR_ADDR: BV32 = BV32::new(0xE000ED24);
-#[flux_rs::constant(0xE000ED28)]
-pub const CFSR_ADDR: BV32 = BV32::new(0xE000ED28);
-#[flux_rs::constant(0xE000ED2C)]
-pub const HFSR_ADDR: BV32 = BV32::new(0xE000ED2C);
-#[flux_rs::constant(0xE000ED30)]
-pub const DFSR_ADDR: BV32 = BV32::new(0xE000ED30);
-#[flux_rs::constant(0xE000ED34)]
-pub const MMFAR_ADDR: BV32 = BV32::new(0xE000ED34);
-#[flux_rs::constant(0xE000ED34)]
-pub const BFAR_ADDR: BV32 = BV32::new(0xE000ED38);
-#[flux_rs::constant(0xE000ED3C)]
-pub const AFSR_ADDR: BV32 = BV32::new(0xE000ED3C);
-#[flux_rs::constant(0xE000ED88)]
-pub const CPACR_ADDR: BV32 = BV32::new(0xE000ED88);
-
-// ID Reg
-#[flux_rs::constant(0xE000E004)]
-pub const ICTR_ADDR: BV32 = BV32::new(0xE000E004);
-#[flux_rs::constant(0xE000E004)]
-pub const ACTLR_ADDR: BV32 = BV32::new(0xE000E008);
-#[flux_rs::constant(0xE000EF00)]
-pub const STIR_ADDR: BV32 = BV32::new(0xE000EF00);
-#[flux_rs::constant(0xE000EFD0)]
-pub const PID4_ADDR: BV32 = BV32::new(0xE000EFD0);
-#[flux_rs::constant(0xE000EFD4)]
-pub const PID5_ADDR: BV32 = BV32::new(0xE000EFD4);
-#[flux_rs::constant(0xE000EFD8)]
-pub const PID6_ADDR: BV32 = BV32::new(0xE000EFD8);
-#[flux_rs::constant(0xE000EFDC)]
-pub const PID7_ADDR: BV32 = BV32::new(0xE000EFDC);
-#[flux_rs::constant(0xE000EFE0)]
-pub const PID0_ADDR: BV32 = BV32::new(0xE000EFE0);
-#[flux_rs::constant(0xE000EFE4)]
-pub const PID1_ADDR: BV32 = BV32::new(0xE000EFE4);
-#[flux_rs::constant(0xE000EFE8)]
-pub const PID2_ADDR: BV32 = BV32::new(0xE000EFE8);
-#[flux_rs::constant(0xE000EFEC)]
-pub const PID3_ADDR: BV32 = BV32::new(0xE000EFEC);
-#[flux_rs::constant(0xE000EFF0)]
-pub const CID0_ADDR: BV32 = BV32::new(0xE000EFF0);
-#[flux_rs::constant(0xE000EFF4)]
-pub const CID1_ADDR: BV32 = BV32::new(0xE000EFF4);
-#[flux_rs::constant(0xE000EFF8)]
-pub const CID2_ADDR: BV32 = BV32::new(0xE000EFF8);
-#[flux_rs::constant(0xE000EFFC)]
-pub const CID3_ADDR: BV32 = BV32::new(0xE000EFFC);
-
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_block_read_addr(addr)])]
-fn is_valid_sys_control_block_read_addr(address: BV32) -> bool {
-    // all addresses are read
-    let a1 = CPUID_ADDR;
-    let a2 = ICSR_ADDR;
-    let a3 = VTOR_ADDR;
-    let a4 = AIRCR_ADDR;
-    let a5 = SCR_ADDR;
-    let a6 = CCR_ADDR;
-    let a7 = SHPR1_ADDR;
-    let a8 = SHPR2_ADDR;
-    let a9 = SHPR3_ADDR;
-    let a10 = SHCSR_ADDR;
-    let a11 = CFSR_ADDR;
-    let a12 = HFSR_ADDR;
-    let a13 = DFSR_ADDR;
-    let a14 = MMFAR_ADDR;
-    let a15 = BFAR_ADDR;
-    let a16 = AFSR_ADDR;
-    let a17 = CPACR_ADDR;
-
-    address == a1
-        || address == a2
-        || address == a3
-        || address == a4
-        || address == a5
-        || address == a6
-        || address == a7
-        || address == a8
-        || address == a9
-        || address == a10
-        || address == a11
-        || address == a12
-        || address == a13
-        || address == a14
-        || address == a15
-        || address == a16
-        || address == a17
-}
+use super::Memory;
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_block_write_addr(addr)])]
-fn is_valid_sys_control_block_write_addr(address: BV32) -> bool {
-    // all addresses but CPUID are write
-    let a2 = ICSR_ADDR;
-    let a3 = VTOR_ADDR;
-    let a4 = AIRCR_ADDR;
-    let a5 = SCR_ADDR;
-    let a6 = CCR_ADDR;
-    let a7 = SHPR1_ADDR;
-    let a8 = SHPR2_ADDR;
-    let a9 = SHPR3_ADDR;
-    let a10 = SHCSR_ADDR;
-    let a11 = CFSR_ADDR;
-    let a12 = HFSR_ADDR;
-    let a13 = DFSR_ADDR;
-    let a14 = MMFAR_ADDR;
-    let a15 = BFAR_ADDR;
-    let a16 = AFSR_ADDR;
-    let a17 = CPACR_ADDR;
-    address == a2
-        || address == a3
-        || address == a4
-        || address == a5
-        || address == a6
-        || address == a7
-        || address == a8
-        || address == a9
-        || address == a10
-        || address == a11
-        || address == a12
-        || address == a13
-        || address == a14
-        || address == a15
-        || address == a16
-        || address == a17
-}
+// The register addresses & reset values are generated from the SVD (see
+// scs/sys_control_block.rs). Registers whose reset value is UNKNOWN are reset
+// to 0 (see Memory::reset_ppb). CPUID and VTOR are implementation defined so
+// reset leaves them alone.
+pub use super::scs::sys_control_block::{
+    is_valid_sys_control_block_read_addr, is_valid_sys_control_block_write_addr, AFSR_ADDR,
+    AIRCR_ADDR, AIRCR_RESET, BFAR_ADDR, CCR_ADDR, CCR_RESET, CFSR_ADDR, CPACR_ADDR, CPUID_ADDR,
+    DFSR_ADDR, HFSR_ADDR, ICSR_ADDR, MMFAR_ADDR, SCR_ADDR, SHCSR_ADDR, SHPR1_ADDR, SHPR2_ADDR,
+    SHPR3_ADDR, VTOR_ADDR,
+};
+pub use super::scs::sys_control_id_reg::{
+    is_valid_sys_control_id_reg_read_addr, is_valid_sys_control_id_reg_write_addr, ACTLR_ADDR,
+    CID0_ADDR, CID1_ADDR, CID2_ADDR, CID3_ADDR, ICTR_ADDR, PID0_ADDR, PID1_ADDR, PID2_ADDR,
+    PID3_ADDR, PID4_ADDR, PID5_ADDR, PID6_ADDR, PID7_ADDR, STIR_ADDR,
+};
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_id_reg_read_addr(addr)])]
-fn is_valid_sys_control_id_reg_read_addr(address: BV32) -> bool {
-    // all but STIR are read
-    let a1 = ICTR_ADDR;
-    let a2 = ACTLR_ADDR;
-    let a3 = PID4_ADDR;
-    let a4 = PID5_ADDR;
-    let a5 = PID6_ADDR;
-    let a6 = PID7_ADDR;
-    let a7 = PID0_ADDR;
-    let a8 = PID1_ADDR;
-    let a9 = PID2_ADDR;
-    let a10 = PID3_ADDR;
-    let a11 = CID0_ADDR;
-    let a12 = CID1_ADDR;
-    let a13 = CID2_ADDR;
-    let a14 = CID3_ADDR;
-    address == a1
-        || address == a2
-        || address == a3
-        || address == a4
-        || address == a5
-        || address == a6
-        || address == a7
-        || address == a8
-        || address == a9
-        || address == a10
-        || address == a11
-        || address == a12
-        || address == a13
-        || address == a14
-}
+// AIRCR (see Application Interrupt and Reset Control Register, AIRCR in the
+// manual)
+//
+// Writes are ignored unless VECTKEY (bits 31:16) is 0x05FA and reads return
+// VECTKEYSTAT (0xFA05) in its place. ENDIANNESS (bit 15) is read only and 0
+// since the model is little endian. VECTRESET & VECTCLRACTIVE are for
+// debuggers and are not modelled.
+#[flux_rs::constant(0xFFFF_0000)]
+pub const AIRCR_VECTKEY_MASK: BV32 = BV32::new(0xFFFF_0000);
+#[flux_rs::constant(0x05FA_0000)]
+pub const AIRCR_VECTKEY: BV32 = BV32::new(0x05FA_0000);
+#[flux_rs::constant(0xFA05_0000)]
+pub const AIRCR_VECTKEYSTAT: BV32 = BV32::new(0xFA05_0000);
+// SYSRESETREQ (bit 2) stays set until the reset it requests is taken (see
+// Armv7m::take_pending_reset)
+#[flux_rs::constant(0x0000_0004)]
+pub const AIRCR_SYSRESETREQ: BV32 = BV32::new(0x0000_0004);
+// PRIGROUP (bits 10:8) - reads back what was written but has no effect as
+// we do not model exception priorities
+#[flux_rs::constant(0x0000_0700)]
+pub const AIRCR_PRIGROUP: BV32 = BV32::new(0x0000_0700);
+
+// SCR bits (see System Control Register, SCR in the manual)
+#[flux_rs::constant(0x0000_0002)]
+pub const SCR_SLEEPONEXIT: BV32 = BV32::new(0x0000_0002);
+#[flux_rs::constant(0x0000_0004)]
+pub const SCR_SLEEPDEEP: BV32 = BV32::new(0x0000_0004);
+#[flux_rs::constant(0x0000_0010)]
+pub const SCR_SEVONPEND: BV32 = BV32::new(0x0000_0010);
+// every other bit is reserved
+#[flux_rs::constant(0x0000_0016)]
+pub const SCR_WRITE_MASK: BV32 = BV32::new(0x0000_0016);
+
+// CCR bits (see Configuration and Control Register, CCR in the manual)
+//
+// NONBASETHRDENA (bit 0), DIV_0_TRP (bit 4) and BFHFNMIGN (bit 8) read back
+// what was written but have no effect: we do not track which exceptions are
+// active, there is no SDIV or UDIV and bus faults are never ignored.
+//
+// USERSETMPEND - allow unprivileged writes to STIR
+#[flux_rs::constant(0x0000_0002)]
+pub const CCR_USERSETMPEND: BV32 = BV32::new(0x0000_0002);
+// UNALIGN_TRP - trap unaligned halfword and word accesses
+#[flux_rs::constant(0x0000_0008)]
+pub const CCR_UNALIGN_TRP: BV32 = BV32::new(0x0000_0008);
+// STKALIGN - 8 byte align the stack frame on exception entry
+#[flux_rs::constant(0x0000_0200)]
+pub const CCR_STKALIGN: BV32 = BV32::new(0x0000_0200);
+// every other bit is reserved
+#[flux_rs::constant(0x0000_031B)]
+pub const CCR_WRITE_MASK: BV32 = BV32::new(0x0000_031B);
+
+// SHCSR fault enable bits
+#[flux_rs::constant(16)]
+pub const SHCSR_MEMFAULTENA_BIT: BV32 = BV32::new(16);
+#[flux_rs::constant(17)]
+pub const SHCSR_BUSFAULTENA_BIT: BV32 = BV32::new(17);
+#[flux_rs::constant(18)]
+pub const SHCSR_USGFAULTENA_BIT: BV32 = BV32::new(18);
+
+// ICSR.PENDSTSET (bit 26) & ICSR.PENDSTCLR (bit 25) - SysTick pending state.
+// Writing a 1 to PENDSTSET pends SysTick and writing a 1 to PENDSTCLR clears
+// it. The other writable bits pend NMI & PendSV, which we do not model, so
+// they are ignored.
+#[flux_rs::constant(0x0400_0000)]
+pub const ICSR_PENDSTSET: BV32 = BV32::new(0x0400_0000);
+#[flux_rs::constant(0x0200_0000)]
+pub const ICSR_PENDSTCLR: BV32 = BV32::new(0x0200_0000);
+
+// HFSR.FORCED - set when a configurable fault escalates to HardFault
+#[flux_rs::constant(0x4000_0000)]
+pub const HFSR_FORCED: BV32 = BV32::new(0x4000_0000);
+
+// MMFSR access violation bits - IACCVIOL (bit 0), DACCVIOL (bit 1) and
+// MMARVALID (bit 7) which says MMFAR holds the faulting address
+#[flux_rs::constant(0x0000_0001)]
+pub const CFSR_IACCVIOL: BV32 = BV32::new(0x0000_0001);
+#[flux_rs::constant(0x0000_0002)]
+pub const CFSR_DACCVIOL: BV32 = BV32::new(0x0000_0002);
+#[flux_rs::constant(0x0000_0080)]
+pub const CFSR_MMARVALID: BV32 = BV32::new(0x0000_0080);
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_id_reg_write_addr(addr)])]
-fn is_valid_sys_control_id_reg_write_addr(address: BV32) -> bool {
-    // only actlr && stir are write
-    let actlr_addr = ACTLR_ADDR;
-    let stir_addr = STIR_ADDR;
-    address == actlr_addr || address == stir_addr
+// BFSR precise bus error bits - PRECISERR (bit 9) and BFARVALID (bit 15)
+// which says BFAR holds the faulting address
+#[flux_rs::constant(0x0000_0200)]
+pub const CFSR_PRECISERR: BV32 = BV32::new(0x0000_0200);
+#[flux_rs::constant(0x0000_8000)]
+pub const CFSR_BFARVALID: BV32 = BV32::new(0x0000_8000);
+
+// CFSR stacking error bits - MMFSR.MSTKERR (bit 4) and BFSR.STKERR (bit 12)
+#[flux_rs::constant(0x0000_0010)]
+pub const CFSR_MSTKERR: BV32 = BV32::new(0x0000_0010);
+#[flux_rs::constant(0x0000_1000)]
+pub const CFSR_STKERR: BV32 = BV32::new(0x0000_1000);
+// UFSR.INVPC (bit 18) - an exception return with a reserved EXC_RETURN
+#[flux_rs::constant(0x0004_0000)]
+pub const CFSR_INVPC: BV32 = BV32::new(0x0004_0000);
+// UFSR.UNALIGNED (bit 24)
+#[flux_rs::constant(0x0100_0000)]
+pub const CFSR_UNALIGNED: BV32 = BV32::new(0x0100_0000);
+
+// STIR.INTID (bits 8:0) - the external interrupt a write pends
+#[flux_rs::constant(0x0000_01FF)]
+pub const STIR_INTID: BV32 = BV32::new(0x0000_01FF);
+
+// ICSR, AIRCR, SCR & CCR have write semantics of their own (see
+// Memory::scb_write)
+#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_scb_control_addr(addr)])]
+pub fn is_scb_control_addr(address: BV32) -> bool {
+    let icsr_addr = ICSR_ADDR;
+    let aircr_addr = AIRCR_ADDR;
+    let scr_addr = SCR_ADDR;
+    let ccr_addr = CCR_ADDR;
+    address == icsr_addr || address == aircr_addr || address == scr_addr || address == ccr_addr
 }
 
 #[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_control_space_read_addr(addr)])]
@@ -220,3 +166,74 @@ pub fn is_valid_sys_control_space_write_addr(address: BV32) -> bool {
     is_valid_sys_control_block_write_addr(address)
         || is_valid_sys_control_id_reg_write_addr(address)
 }
+
+impl Memory {
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[sys_reset_requested(mem)])]
+    pub fn sys_reset_requested(&self) -> bool {
+        let aircr_addr = AIRCR_ADDR;
+        *self.mem.get(&aircr_addr).unwrap() & AIRCR_SYSRESETREQ != BV32::from(0)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[scr_sleep_on_exit(mem)])]
+    pub fn sleep_on_exit(&self) -> bool {
+        let scr_addr = SCR_ADDR;
+        *self.mem.get(&scr_addr).unwrap() & SCR_SLEEPONEXIT != BV32::from(0)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[scr_sleep_deep(mem)])]
+    pub fn sleep_deep(&self) -> bool {
+        let scr_addr = SCR_ADDR;
+        *self.mem.get(&scr_addr).unwrap() & SCR_SLEEPDEEP != BV32::from(0)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[scr_sev_on_pend(mem)])]
+    pub fn sev_on_pend(&self) -> bool {
+        let scr_addr = SCR_ADDR;
+        *self.mem.get(&scr_addr).unwrap() & SCR_SEVONPEND != BV32::from(0)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[ccr_stkalign(mem)])]
+    pub fn stack_align_enabled(&self) -> bool {
+        let ccr_addr = CCR_ADDR;
+        *self.mem.get(&ccr_addr).unwrap() & CCR_STKALIGN != BV32::from(0)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[ccr_usersetmpend(mem)])]
+    pub fn user_set_pending_enabled(&self) -> bool {
+        let ccr_addr = CCR_ADDR;
+        *self.mem.get(&ccr_addr).unwrap() & CCR_USERSETMPEND != BV32::from(0)
+    }
+
+    // Write semantics of ICSR, AIRCR, SCR & CCR - see update_mem
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@val])
+            requires is_scb_control_addr(addr)
+            ensures self: Memory { new_mem: new_mem == mem_post_scb_write(addr, old_mem, val) }
+    )]
+    pub(super) fn scb_write(&mut self, address: BV32, value: BV32) {
+        let icsr_addr = ICSR_ADDR;
+        let aircr_addr = AIRCR_ADDR;
+        let scr_addr = SCR_ADDR;
+        if address == icsr_addr {
+            let icsr = *self.mem.get(&icsr_addr).unwrap();
+            // writing both is UNPREDICTABLE - we clear
+            if value & ICSR_PENDSTCLR != BV32::from(0) {
+                self.mem.set(address, icsr & !ICSR_PENDSTSET)
+            } else if value & ICSR_PENDSTSET != BV32::from(0) {
+                self.mem.set(address, icsr | ICSR_PENDSTSET)
+            }
+        } else if address == aircr_addr {
+            let key = AIRCR_VECTKEY;
+            if value & AIRCR_VECTKEY_MASK == key {
+                self.mem.set(
+                    address,
+                    AIRCR_VECTKEYSTAT | (value & (AIRCR_PRIGROUP | AIRCR_SYSRESETREQ)),
+                )
+            }
+        } else if address == scr_addr {
+            self.mem.set(address, value & SCR_WRITE_MASK)
+        } else {
+            self.mem.set(address, value & CCR_WRITE_MASK)
+        }
+    }
+}
@@ -1,38 +1,112 @@
-// Sys Tick
+// Sys Tick (see The system timer, SysTick on p. B3-676 of the manual)
 //
 // Here are some unimplemented blocks:
 //
 // 0xE000E020- 0xE000E0FC	-	-	-	Reserved
 //
+// SysTick is a 24 bit down counter. Every clock while SYST_CSR.ENABLE is set:
+//
+// - SYST_CVR counts down by 1
+// - when it goes from 1 to 0 SYST_CSR.COUNTFLAG is set and SysTick is pended
+//   (ICSR.PENDSTSET) if SYST_CSR.TICKINT is set
+// - on the clock after it hits 0 it is reloaded from SYST_RVR
+//
+// Reading SYST_CSR clears COUNTFLAG and writing SYST_CVR clears both the
+// counter and COUNTFLAG. Software can pend or clear SysTick through
+// ICSR.PENDSTSET & ICSR.PENDSTCLR (see Memory::scb_write).
 
 use flux_rs::bitvec::BV32;
 
 use super::flux_defs::sys_tick_defs::*;
+use super::sys_control::{ICSR_ADDR, ICSR_PENDSTSET};
+use super::Memory;
 
-#[flux_rs::constant(0xE000E010)]
-pub const SYST_CSR_ADDR: BV32 = BV32::new(0xE000E010);
-#[flux_rs::constant(0xE000E014)]
-pub const SYST_RVR_ADDR: BV32 = BV32::new(0xE000E014);
-#[flux_rs::constant(0xE000E018)]
-pub const SYST_CVR_ADDR: BV32 = BV32::new(0xE000E018);
-#[flux_rs::constant(0xE000E01C)]
-pub const SYST_CALIB_ADDR: BV32 = BV32::new(0xE000E01C);
-
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_tick_read_addr(addr)])]
-pub fn is_valid_sys_tick_read_addr(address: BV32) -> bool {
-    // all addresses are read
-    let a1 = SYST_CSR_ADDR;
-    let a2 = SYST_RVR_ADDR;
-    let a3 = SYST_CVR_ADDR;
-    let a4 = SYST_CALIB_ADDR;
-    address == a1 || address == a2 || address == a3 || address == a4
-}
+// The register addresses are generated from the SVD (see scs/sys_tick.rs)
+pub use super::scs::sys_tick::{
+    is_valid_sys_tick_read_addr, is_valid_sys_tick_write_addr, SYST_CALIB_ADDR, SYST_CSR_ADDR,
+    SYST_CVR_ADDR, SYST_RVR_ADDR,
+};
+
+// SYST_CSR bits
+#[flux_rs::constant(0x0000_0001)]
+pub const SYST_CSR_ENABLE: BV32 = BV32::new(0x0000_0001);
+#[flux_rs::constant(0x0000_0002)]
+pub const SYST_CSR_TICKINT: BV32 = BV32::new(0x0000_0002);
+#[flux_rs::constant(0x0000_0004)]
+pub const SYST_CSR_CLKSOURCE: BV32 = BV32::new(0x0000_0004);
+#[flux_rs::constant(0x0001_0000)]
+pub const SYST_CSR_COUNTFLAG: BV32 = BV32::new(0x0001_0000);
+
+// SYST_RVR.RELOAD - the counter is 24 bits wide
+#[flux_rs::constant(0x00FF_FFFF)]
+pub const SYST_RVR_RELOAD_MASK: BV32 = BV32::new(0x00FF_FFFF);
+
+impl Memory {
+    // One SysTick clock
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem])
+            ensures self: Memory { new_mem: new_mem == mem_post_sys_tick(old_mem) }
+    )]
+    pub fn sys_tick(&mut self) {
+        let csr_addr = SYST_CSR_ADDR;
+        let cvr_addr = SYST_CVR_ADDR;
+        let rvr_addr = SYST_RVR_ADDR;
+        let csr = *self.mem.get(&csr_addr).unwrap();
+        if csr & SYST_CSR_ENABLE == BV32::from(0) {
+            return;
+        }
+        let current = *self.mem.get(&cvr_addr).unwrap();
+        if current == BV32::from(0) {
+            let reload = *self.mem.get(&rvr_addr).unwrap() & SYST_RVR_RELOAD_MASK;
+            self.mem.set(cvr_addr, reload);
+        } else if current == BV32::from(1) {
+            self.sys_tick_wrap();
+        } else {
+            self.mem.set(cvr_addr, current - BV32::from(1));
+        }
+    }
+
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem])
+            ensures self: Memory { new_mem: new_mem == mem_post_sys_tick_wrap(old_mem) }
+    )]
+    fn sys_tick_wrap(&mut self) {
+        let csr_addr = SYST_CSR_ADDR;
+        let cvr_addr = SYST_CVR_ADDR;
+        let icsr_addr = ICSR_ADDR;
+        let csr = *self.mem.get(&csr_addr).unwrap();
+        let icsr = *self.mem.get(&icsr_addr).unwrap();
+        self.mem.set(cvr_addr, BV32::from(0));
+        self.mem.set(csr_addr, csr | SYST_CSR_COUNTFLAG);
+        if csr & SYST_CSR_TICKINT != BV32::from(0) {
+            self.mem.set(icsr_addr, icsr | ICSR_PENDSTSET);
+        }
+    }
+
+    // Reads SYST_CSR the way software does - clearing COUNTFLAG (see
+    // Memory::read_word)
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem]) -> BV32[sys_tick_csr(old_mem)]
+            ensures self: Memory { new_mem: new_mem == mem_post_sys_tick_csr_read(old_mem) }
+    )]
+    pub fn read_sys_tick_csr(&mut self) -> BV32 {
+        let csr_addr = SYST_CSR_ADDR;
+        self.read_word(csr_addr)
+    }
+
+    #[flux_rs::sig(fn (&Memory[@mem]) -> bool[sys_tick_pending(mem)])]
+    pub fn sys_tick_pending(&self) -> bool {
+        let icsr_addr = ICSR_ADDR;
+        *self.mem.get(&icsr_addr).unwrap() & ICSR_PENDSTSET != BV32::from(0)
+    }
 
-#[flux_rs::sig(fn (BV32[@addr]) -> bool[is_valid_sys_tick_write_addr(addr)])]
-pub fn is_valid_sys_tick_write_addr(address: BV32) -> bool {
-    // all addresses but SYS_CALIB are write
-    let a1 = SYST_CSR_ADDR;
-    let a2 = SYST_RVR_ADDR;
-    let a3 = SYST_CVR_ADDR;
-    address == a1 || address == a2 || address == a3
+    #[flux_rs::sig(
+        fn (self: &strg Memory[@old_mem])
+            ensures self: Memory { new_mem: new_mem == mem_post_clear_sys_tick_pending(old_mem) }
+    )]
+    pub fn clear_sys_tick_pending(&mut self) {
+        let icsr_addr = ICSR_ADDR;
+        let icsr = *self.mem.get(&icsr_addr).unwrap();
+        self.mem.set(icsr_addr, icsr & !ICSR_PENDSTSET);
+    }
 }
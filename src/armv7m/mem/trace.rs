@@ -0,0 +1,129 @@
+// Memory access tracing & watchpoints
+//
+// Tracing is off by default. Once enabled every access that goes through
+// Memory::read / write (and the sized accesses built on them) is logged with
+// the instruction that made it - whoever drives the cpu says which
+// instruction is running (see Armv7m::step). Sub-word writes log just the
+// write: the read of the word they update is not an access the program made.
+//
+// A watchpoint stops Armv7m::run_until after the step that touched an
+// address in its range.
+//
+// NOTE: none of this is part of the flux refinement - it only exists to look
+// at concrete runs.
+
+use flux_rs::bitvec::BV32;
+
+use super::Memory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+// What made an access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Origin {
+    pub instruction: &'static str,
+    pub pc: BV32,
+    pub privileged: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    pub kind: AccessKind,
+    pub address: BV32,
+    pub value: BV32,
+    // in bytes
+    pub width: u8,
+    // None for accesses made outside Armv7m::step (e.g. by a test harness)
+    pub origin: Option<Origin>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    // inclusive bounds
+    pub start: BV32,
+    pub end: BV32,
+    // None watches both reads & writes
+    pub kind: Option<AccessKind>,
+}
+
+impl Watchpoint {
+    fn matches(&self, access: &Access) -> bool {
+        // an access hits the range if any byte of it does
+        let last = access.address + BV32::from(access.width as u32 - 1);
+        access.address <= self.end
+            && last >= self.start
+            && self.kind.is_none_or(|kind| kind == access.kind)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Trace {
+    enabled: bool,
+    origin: Option<Origin>,
+    log: Vec<Access>,
+    watchpoints: Vec<Watchpoint>,
+    // the first access to hit a watchpoint since the last take_watchpoint_hit
+    hit: Option<Access>,
+}
+
+impl Memory {
+    pub fn enable_tracing(&mut self) {
+        self.trace.get_mut().enabled = true;
+    }
+
+    pub fn disable_tracing(&mut self) {
+        self.trace.get_mut().enabled = false;
+    }
+
+    // Every access logged so far - clears the log
+    pub fn take_trace(&mut self) -> Vec<Access> {
+        std::mem::take(&mut self.trace.get_mut().log)
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.trace.get_mut().watchpoints.push(watchpoint);
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.trace.get_mut().watchpoints.clear();
+    }
+
+    pub fn take_watchpoint_hit(&mut self) -> Option<Access> {
+        self.trace.get_mut().hit.take()
+    }
+
+    // Accesses from now on were made by origin
+    pub fn set_access_origin(&mut self, origin: Option<Origin>) {
+        self.trace.get_mut().origin = origin;
+    }
+
+    #[flux_rs::trusted]
+    pub(super) fn trace_access(&self, kind: AccessKind, address: BV32, value: BV32, width: u8) {
+        let mut trace = self.trace.borrow_mut();
+        if !trace.enabled && trace.watchpoints.is_empty() {
+            return;
+        }
+        let access = Access {
+            kind,
+            address,
+            value,
+            width,
+            origin: trace.origin,
+        };
+        if trace.hit.is_none()
+            && trace
+                .watchpoints
+                .iter()
+                .any(|watchpoint| watchpoint.matches(&access))
+        {
+            trace.hit = Some(access);
+        }
+        if trace.enabled {
+            trace.log.push(access);
+        }
+    }
+}
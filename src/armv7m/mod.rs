@@ -2,6 +2,8 @@
 // This defines a tiny subset of the Thumb instruction set and the architecture of Armv7M
 //
 // Based on https://developer.arm.com/documentation/ddi0403/ee/?lang=en
+pub mod chip;
 pub mod cpu;
+pub mod devices;
 pub mod lang;
 pub(crate) mod mem;
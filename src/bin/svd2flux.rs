@@ -0,0 +1,714 @@
+// Generates register address constants, reset values and flux validity
+// predicates from a CMSIS-SVD file.
+//
+// Usage:
+//
+//      svd2flux <file.svd> <out dir>   write <out dir>/<peripheral>.rs for every peripheral
+//                                      and a mod.rs declaring them
+//      svd2flux --check <file.rs>...   check every #[flux_rs::constant(..)] matches the
+//                                      value of the constant it annotates
+//
+// The flux constant and the rust value are printed from the same number so
+// they can not drift apart the way hand written tables do.
+//
+// NOTE: this only understands the subset of SVD we need - peripherals (with
+// derivedFrom), registers (with dim lists and arrays), access and resetValue.
+// Clusters are skipped.
+//
+// A dim list (NAME%s) becomes one NAMEn_ADDR constant per register. A dim
+// array (NAME[%s]) becomes NAME_START & NAME_END and the predicates accept
+// every word in between - the NVIC has hundreds of these.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// A (very) small XML reader - SVD files only use elements, attributes, text,
+// comments and the <?xml ?> prolog
+#[derive(Debug, Default, Clone)]
+struct Element {
+    name: String,
+    attrs: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim())
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Parser { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_until(&mut self, pat: &str) -> Result<(), String> {
+        match self.rest().find(pat) {
+            Some(idx) => {
+                self.pos += idx + pat.len();
+                Ok(())
+            }
+            None => Err(format!("unterminated markup, expected {}", pat)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    // skips the prolog, comments and doctype in front of the next element
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if self.rest().starts_with("<!") {
+                self.skip_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_name(&mut self) -> String {
+        let end = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or(self.rest().len());
+        let name = self.rest()[..end].to_string();
+        self.pos += end;
+        name
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        self.skip_misc()?;
+        if !self.rest().starts_with('<') {
+            return Err(format!("expected an element at byte {}", self.pos));
+        }
+        self.pos += 1;
+        let mut element = Element {
+            name: self.read_name(),
+            ..Element::default()
+        };
+
+        // attributes
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.read_name();
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(format!("attribute {} has no value", key));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = self.rest().chars().next().ok_or("unexpected end of file")?;
+            if quote != '"' && quote != '\'' {
+                return Err(format!("attribute {} is not quoted", key));
+            }
+            self.pos += 1;
+            let end = self
+                .rest()
+                .find(quote)
+                .ok_or("unterminated attribute value")?;
+            element.attrs.insert(key, unescape(&self.rest()[..end]));
+            self.pos += end + 1;
+        }
+
+        // content
+        loop {
+            if self.rest().starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if self.rest().starts_with("</") {
+                self.pos += 2;
+                let name = self.read_name();
+                if name != element.name {
+                    return Err(format!("<{}> closed by </{}>", element.name, name));
+                }
+                self.skip_until(">")?;
+                return Ok(element);
+            } else if self.rest().starts_with('<') {
+                element.children.push(self.element()?);
+            } else {
+                let end = self.rest().find('<').ok_or("unexpected end of file")?;
+                element.text.push_str(&unescape(&self.rest()[..end]));
+                self.pos += end;
+            }
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// SVD numbers are decimal, 0x hex or # binary
+fn parse_number(text: &str) -> Result<u64, String> {
+    let text = text.trim().replace('_', "");
+    let parsed = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(bin) = text.strip_prefix('#') {
+        u64::from_str_radix(bin, 2)
+    } else {
+        text.parse::<u64>()
+    };
+    parsed.map_err(|_| format!("bad number {:?}", text))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl Access {
+    fn parse(text: &str) -> Result<Access, String> {
+        match text {
+            "read-only" => Ok(Access::ReadOnly),
+            "write-only" | "writeOnce" => Ok(Access::WriteOnly),
+            "read-write" | "read-writeOnce" => Ok(Access::ReadWrite),
+            _ => Err(format!("unknown access {:?}", text)),
+        }
+    }
+
+    fn readable(self) -> bool {
+        self != Access::WriteOnly
+    }
+
+    fn writable(self) -> bool {
+        self != Access::ReadOnly
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Register {
+    name: String,
+    description: String,
+    address: u32,
+    // the address of the last register of a dim array
+    end: Option<u32>,
+    access: Access,
+    reset_value: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct Peripheral {
+    name: String,
+    description: String,
+    registers: Vec<Register>,
+}
+
+// Register properties that are inherited from the enclosing element
+#[derive(Debug, Clone, Copy)]
+struct Defaults {
+    access: Access,
+    reset_value: Option<u32>,
+}
+
+impl Defaults {
+    fn inherit(&self, element: &Element) -> Result<Defaults, String> {
+        Ok(Defaults {
+            access: match element.child_text("access") {
+                Some(access) => Access::parse(access)?,
+                None => self.access,
+            },
+            reset_value: match element.child_text("resetValue") {
+                Some(reset) => Some(parse_number(reset)? as u32),
+                None => self.reset_value,
+            },
+        })
+    }
+}
+
+fn dim_indices(register: &Element, dim: u64) -> Result<Vec<String>, String> {
+    match register.child_text("dimIndex") {
+        None => Ok((0..dim).map(|i| i.to_string()).collect()),
+        Some(index) => {
+            if let Some((start, end)) = index.split_once('-') {
+                let (start, end) = (parse_number(start)?, parse_number(end)?);
+                Ok((start..=end).map(|i| i.to_string()).collect())
+            } else {
+                Ok(index.split(',').map(|i| i.trim().to_string()).collect())
+            }
+        }
+    }
+}
+
+fn registers(peripheral: &Element, base: u32, defaults: Defaults) -> Result<Vec<Register>, String> {
+    let mut registers = Vec::new();
+    let Some(list) = peripheral.child("registers") else {
+        return Ok(registers);
+    };
+    for cluster in list.children_named("cluster") {
+        eprintln!(
+            "warning: skipping cluster {}",
+            cluster.child_text("name").unwrap_or("?")
+        );
+    }
+    for register in list.children_named("register") {
+        let name = register
+            .child_text("name")
+            .ok_or("register without a name")?;
+        let offset = parse_number(
+            register
+                .child_text("addressOffset")
+                .ok_or(format!("register {} has no addressOffset", name))?,
+        )? as u32;
+        let description = register.child_text("description").unwrap_or("");
+        let defaults = defaults.inherit(register)?;
+        let instances = match register.child_text("dim") {
+            None => vec![(name.to_string(), offset, None)],
+            Some(dim) => {
+                let dim = parse_number(dim)?;
+                let increment = parse_number(
+                    register
+                        .child_text("dimIncrement")
+                        .ok_or(format!("register array {} has no dimIncrement", name))?,
+                )? as u32;
+                if let Some(name) = name.strip_suffix("[%s]") {
+                    // the predicates check arrays a word at a time
+                    if increment != 4 {
+                        return Err(format!("register array {} is not word contiguous", name));
+                    }
+                    let end = offset + increment * (dim as u32 - 1);
+                    vec![(name.to_string(), offset, Some(end))]
+                } else {
+                    dim_indices(register, dim)?
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, index)| {
+                            (
+                                name.replace("%s", &index),
+                                offset + increment * i as u32,
+                                None,
+                            )
+                        })
+                        .collect()
+                }
+            }
+        };
+        for (name, offset, end) in instances {
+            registers.push(Register {
+                name: name.to_uppercase(),
+                description: description.split_whitespace().collect::<Vec<_>>().join(" "),
+                address: base.wrapping_add(offset),
+                end: end.map(|end| base.wrapping_add(end)),
+                access: defaults.access,
+                reset_value: defaults.reset_value,
+            });
+        }
+    }
+    Ok(registers)
+}
+
+fn peripherals(device: &Element) -> Result<Vec<Peripheral>, String> {
+    let device_defaults = Defaults {
+        access: Access::ReadWrite,
+        reset_value: None,
+    }
+    .inherit(device)?;
+    let list = device
+        .child("peripherals")
+        .ok_or("device has no peripherals")?;
+    let by_name: HashMap<&str, &Element> = list
+        .children_named("peripheral")
+        .filter_map(|p| p.child_text("name").map(|name| (name, p)))
+        .collect();
+
+    let mut peripherals = Vec::new();
+    for peripheral in list.children_named("peripheral") {
+        let name = peripheral
+            .child_text("name")
+            .ok_or("peripheral without a name")?;
+        let base = parse_number(
+            peripheral
+                .child_text("baseAddress")
+                .ok_or(format!("peripheral {} has no baseAddress", name))?,
+        )? as u32;
+        // derived peripherals reuse the registers of another one at a new base
+        let source = match peripheral.attrs.get("derivedFrom") {
+            Some(parent) => by_name
+                .get(parent.as_str())
+                .ok_or(format!("{} is derived from unknown {}", name, parent))?,
+            None => peripheral,
+        };
+        let defaults = device_defaults.inherit(source)?.inherit(peripheral)?;
+        let description = peripheral
+            .child_text("description")
+            .or(source.child_text("description"))
+            .unwrap_or("");
+        peripherals.push(Peripheral {
+            name: name.to_string(),
+            description: description.split_whitespace().collect::<Vec<_>>().join(" "),
+            registers: registers(source, base, defaults)?,
+        });
+    }
+    Ok(peripherals)
+}
+
+fn snake_case(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn hex(value: u32) -> String {
+    format!("0x{:04X}_{:04X}", value >> 16, value & 0xFFFF)
+}
+
+// Emits the rust version of a predicate - the same shape as the hand written
+// ones so flux can check it against the definition
+fn predicate_fn(out: &mut String, name: &str, registers: &[&Register]) {
+    writeln!(
+        out,
+        "#[flux_rs::sig(fn (BV32[@addr]) -> bool[{}(addr)])]",
+        name
+    )
+    .unwrap();
+    writeln!(out, "pub fn {}(address: BV32) -> bool {{", name).unwrap();
+    if registers.is_empty() {
+        writeln!(out, "    false").unwrap();
+    } else {
+        let mut checks = Vec::new();
+        for (i, register) in registers.iter().enumerate() {
+            if register.end.is_some() {
+                writeln!(out, "    let s{} = {}_START;", i + 1, register.name).unwrap();
+                writeln!(out, "    let e{} = {}_END;", i + 1, register.name).unwrap();
+                checks.push(format!(
+                    "(address >= s{i} && address <= e{i} && (address - s{i}) % BV32::from(4) == BV32::from(0))",
+                    i = i + 1
+                ));
+            } else {
+                writeln!(out, "    let a{} = {}_ADDR;", i + 1, register.name).unwrap();
+                checks.push(format!("address == a{}", i + 1));
+            }
+        }
+        writeln!(out, "    {}", checks.join("\n        || ")).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn predicate_def(out: &mut String, name: &str, registers: &[&Register]) {
+    writeln!(out, "        fn {}(address: BV32) -> bool {{", name).unwrap();
+    if registers.is_empty() {
+        writeln!(out, "            false").unwrap();
+    } else {
+        let checks: Vec<String> = registers
+            .iter()
+            .map(|register| match register.end {
+                Some(_) => format!(
+                    "(address >= {n}_START && address <= {n}_END && bv_urem(address - {n}_START, 4) == 0)",
+                    n = register.name
+                ),
+                None => format!("address == {}_ADDR", register.name),
+            })
+            .collect();
+        writeln!(out, "            {}", checks.join("\n            || ")).unwrap();
+    }
+    writeln!(out, "        }}").unwrap();
+}
+
+// The names a register is known by in the generated code
+fn constant_names(register: &Register) -> Vec<String> {
+    match register.end {
+        Some(_) => vec![
+            format!("{}_START", register.name),
+            format!("{}_END", register.name),
+        ],
+        None => vec![format!("{}_ADDR", register.name)],
+    }
+}
+
+fn generate(source: &str, peripheral: &Peripheral) -> String {
+    let mut out = String::new();
+    let module = snake_case(&peripheral.name);
+    writeln!(out, "// {} - {}", peripheral.name, peripheral.description).unwrap();
+    writeln!(out, "//").unwrap();
+    writeln!(
+        out,
+        "// Generated by svd2flux from {} - do not edit by hand",
+        source
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use flux_rs::bitvec::BV32;").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use flux_defs::*;").unwrap();
+    writeln!(out).unwrap();
+
+    for register in &peripheral.registers {
+        let access = match register.access {
+            Access::ReadOnly => "RO",
+            Access::WriteOnly => "WO",
+            Access::ReadWrite => "RW",
+        };
+        writeln!(
+            out,
+            "// {} ({}) {}",
+            register.name, access, register.description
+        )
+        .unwrap();
+        let mut addresses = vec![(constant_names(register)[0].clone(), register.address)];
+        if let Some(end) = register.end {
+            addresses.push((format!("{}_END", register.name), end));
+        }
+        for (constant, address) in addresses {
+            writeln!(out, "#[flux_rs::constant({})]", hex(address)).unwrap();
+            writeln!(
+                out,
+                "pub const {}: BV32 = BV32::new({});",
+                constant,
+                hex(address)
+            )
+            .unwrap();
+        }
+        if let Some(reset) = register.reset_value {
+            writeln!(out, "#[flux_rs::constant({})]", hex(reset)).unwrap();
+            writeln!(
+                out,
+                "pub const {}_RESET: BV32 = BV32::new({});",
+                register.name,
+                hex(reset)
+            )
+            .unwrap();
+        }
+    }
+    writeln!(out).unwrap();
+
+    // (address, reset value) for every register with a known reset value
+    let with_reset: Vec<&Register> = peripheral
+        .registers
+        .iter()
+        .filter(|register| register.reset_value.is_some() && register.end.is_none())
+        .collect();
+    writeln!(
+        out,
+        "pub const RESET_VALUES: [(BV32, BV32); {}] = [",
+        with_reset.len()
+    )
+    .unwrap();
+    for register in &with_reset {
+        writeln!(
+            out,
+            "    ({}_ADDR, {}_RESET),",
+            register.name, register.name
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    writeln!(out).unwrap();
+
+    // and (first, last, reset value) for the arrays - the registers of an
+    // array are a word apart
+    let arrays_with_reset: Vec<&Register> = peripheral
+        .registers
+        .iter()
+        .filter(|register| register.reset_value.is_some() && register.end.is_some())
+        .collect();
+    if !arrays_with_reset.is_empty() {
+        writeln!(
+            out,
+            "pub const RESET_ARRAYS: [(BV32, BV32, BV32); {}] = [",
+            arrays_with_reset.len()
+        )
+        .unwrap();
+        for register in &arrays_with_reset {
+            writeln!(
+                out,
+                "    ({n}_START, {n}_END, {n}_RESET),",
+                n = register.name
+            )
+            .unwrap();
+        }
+        writeln!(out, "];").unwrap();
+        writeln!(out).unwrap();
+    }
+
+    let readable: Vec<&Register> = peripheral
+        .registers
+        .iter()
+        .filter(|register| register.access.readable())
+        .collect();
+    let writable: Vec<&Register> = peripheral
+        .registers
+        .iter()
+        .filter(|register| register.access.writable())
+        .collect();
+    let read_name = format!("is_valid_{}_read_addr", module);
+    let write_name = format!("is_valid_{}_write_addr", module);
+
+    predicate_fn(&mut out, &read_name, &readable);
+    writeln!(out).unwrap();
+    predicate_fn(&mut out, &write_name, &writable);
+    writeln!(out).unwrap();
+
+    writeln!(out, "pub mod flux_defs {{").unwrap();
+    let names: Vec<String> = peripheral
+        .registers
+        .iter()
+        .flat_map(constant_names)
+        .collect();
+    if !names.is_empty() {
+        writeln!(out, "    use super::{{{}}};", names.join(", ")).unwrap();
+    }
+    writeln!(out, "    use flux_rs::bitvec::BV32;").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    flux_rs::defs! {{").unwrap();
+    predicate_def(&mut out, &read_name, &readable);
+    writeln!(out).unwrap();
+    predicate_def(&mut out, &write_name, &writable);
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+fn generate_mod(source: &str, modules: &[std::path::PathBuf]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by svd2flux from {} - do not edit by hand",
+        source
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    for module in modules {
+        let module = module.file_stem().unwrap().to_string_lossy();
+        writeln!(out, "pub mod {};", module).unwrap();
+    }
+    out
+}
+
+// The generated files are checked in so they get the same formatting as the
+// rest of the tree
+fn format(file: &std::path::Path) {
+    let formatted = std::process::Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .arg(file)
+        .status()
+        .map_or(false, |status| status.success());
+    if !formatted {
+        eprintln!("warning: could not rustfmt {}", file.display());
+    }
+}
+
+// Checks that every #[flux_rs::constant(X)] annotates a constant whose value
+// is X. Returns the mismatches as (line, flux value, rust value).
+fn check_constants(source: &str) -> Vec<(usize, String, String)> {
+    let mut mismatches = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if let Some(value) = line
+            .strip_prefix("#[flux_rs::constant(")
+            .and_then(|rest| rest.strip_suffix(")]"))
+        {
+            pending = Some((i + 1, value.to_string()));
+        } else if let Some((line_number, flux_value)) = pending.take() {
+            let rust_value = line
+                .split_once("::new(")
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .map(|(value, _)| value)
+                .or_else(|| {
+                    line.split_once('=')
+                        .map(|(_, value)| value.trim().trim_end_matches(';'))
+                });
+            let Some(rust_value) = rust_value else {
+                continue;
+            };
+            if parse_number(&flux_value) != parse_number(rust_value) {
+                mismatches.push((line_number, flux_value, rust_value.to_string()));
+            }
+        }
+    }
+    mismatches
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--check") => {
+            let mut failed = false;
+            for path in &args[1..] {
+                let source = std::fs::read_to_string(path).expect("Failed to read file");
+                for (line, flux_value, rust_value) in check_constants(&source) {
+                    println!(
+                        "{}:{}: flux constant {} does not match value {}",
+                        path, line, flux_value, rust_value
+                    );
+                    failed = true;
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        Some(path) if args.len() == 2 => {
+            let out_dir = std::path::Path::new(&args[1]);
+            let source = std::fs::read_to_string(path).expect("Failed to read svd file");
+            let device = Parser::new(&source)
+                .element()
+                .unwrap_or_else(|err| panic!("Failed to parse {}: {}", path, err));
+            let peripherals = peripherals(&device)
+                .unwrap_or_else(|err| panic!("Failed to read {}: {}", path, err));
+            std::fs::create_dir_all(out_dir).expect("Failed to create output directory");
+            let mut modules = Vec::new();
+            for peripheral in peripherals {
+                let file = out_dir.join(format!("{}.rs", snake_case(&peripheral.name)));
+                std::fs::write(&file, generate(path, &peripheral))
+                    .expect("Failed to write generated file");
+                modules.push(file);
+            }
+            let file = out_dir.join("mod.rs");
+            std::fs::write(&file, generate_mod(path, &modules))
+                .expect("Failed to write generated file");
+            modules.push(file);
+            for file in modules {
+                format(&file);
+                println!("wrote {}", file.display());
+            }
+        }
+        _ => {
+            eprintln!("usage: svd2flux <file.svd> <out dir> | svd2flux --check <file.rs>...");
+            std::process::exit(2);
+        }
+    }
+}
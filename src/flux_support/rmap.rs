@@ -16,6 +16,25 @@ pub struct Regs<K, V> {
 }
 
 impl<K, V> Regs<K, V> {
+    #[flux_rs::trusted]
+    pub fn new() -> Self {
+        Regs {
+            inner: std::collections::HashMap::new(),
+        }
+    }
+
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn(&Regs<K, V>[@m]) -> Regs<K, V>[m])]
+    pub fn duplicate(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        Regs {
+            inner: self.inner.clone(),
+        }
+    }
+
     #[flux_rs::trusted]
     #[flux_rs::sig(fn(self: &strg Regs<K,V>[@m], k: K, v: V) ensures self: Regs<K,V>[map_set(m.vals, k, v)])]
     pub fn set(&mut self, k: K, v: V)
@@ -33,4 +52,15 @@ impl<K, V> Regs<K, V> {
     {
         self.inner.get(k)
     }
+
+    // Keys that were never set read as the default value (0 for memory)
+    #[flux_rs::trusted]
+    #[flux_rs::sig(fn(&Regs<K, V>[@m], &K[@k]) -> V[map_get(m.vals, k)])]
+    pub fn get_or_default(&self, k: &K) -> V
+    where
+        K: Eq + Hash,
+        V: Copy + Default,
+    {
+        self.inner.get(k).copied().unwrap_or_default()
+    }
 }
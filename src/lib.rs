@@ -3,7 +3,8 @@
 use armv7m::{
     cpu::Armv7m,
     cpu::SP,
-    lang::{SpecialRegister, GPR},
+    lang::{DsbOpt, ExceptionNumber, IsbOpt, Reg, SysReg, GPR},
+    mem::{mpu::TockProcessRegions, Memory},
 };
 use flux_rs::bitvec::BV32;
 
@@ -30,14 +31,14 @@ fn switch_to_user_part1_save_clobbers(armv7m: &mut Armv7m) {
         GPR::r5(),
         GPR::r6(),
         GPR::r7(),
-        SpecialRegister::lr(),
+        Reg::lr(),
     );
 
     // add imm - WTF is this even doing here
-    // armv7m.add_imm(GPR::r7(), SpecialRegister::sp(), BV32::from(12)); // sp - 0x18 + 0xc
+    // armv7m.add_imm(Reg::gpr(GPR::r7()), Reg::sp(), BV32::from(12)); // sp - 0x18 + 0xc
 
     // stmdb
-    armv7m.stmdb_wback(SpecialRegister::sp(), GPR::r8(), GPR::r10(), GPR::r11());
+    armv7m.stmdb_wback(Reg::sp(), GPR::r8(), GPR::r10(), GPR::r11());
 }
 
 #[flux_rs::sig(
@@ -51,17 +52,17 @@ pub fn switch_to_user_part1_reg_restores(armv7m: &mut Armv7m) {
     // clobbers directly using rust's register interface but since r6, r7
     // are callee saved registers in ARM and we use them
     // the compiler saves them on the stack anyway (see push_gpr in clobber saving)
-    armv7m.mov(GPR::r2(), GPR::r6());
-    armv7m.mov(GPR::r3(), GPR::r7());
+    armv7m.mov(Reg::gpr(GPR::r2()), Reg::gpr(GPR::r6()));
+    armv7m.mov(Reg::gpr(GPR::r3()), Reg::gpr(GPR::r7()));
     // note ip is intraprocedure scratch register - r12
-    armv7m.mov(GPR::r12(), GPR::r9());
+    armv7m.mov(Reg::gpr(GPR::r12()), Reg::gpr(GPR::r9()));
 
     // msr
-    armv7m.msr(SpecialRegister::psp(), GPR::r0());
+    armv7m.msr(SysReg::Psp, GPR::r0());
 
     // ldmia
     armv7m.ldmia_w(
-        GPR::r1(),
+        Reg::gpr(GPR::r1()),
         GPR::r4(),
         GPR::r5(),
         GPR::r6(),
@@ -101,7 +102,7 @@ pub fn switch_to_user_part1(armv7m: &mut Armv7m) {
 )]
 pub fn switch_to_user_part2_save_registers(armv7m: &mut Armv7m) {
     armv7m.stmia_w(
-        GPR::r1(),
+        Reg::gpr(GPR::r1()),
         GPR::r4(),
         GPR::r5(),
         GPR::r6(),
@@ -119,17 +120,17 @@ pub fn switch_to_user_part2_save_registers(armv7m: &mut Armv7m) {
         ensures self: Armv7m { new_cpu: new_cpu == cpu_post_switch_to_user_pt2_restore_clobbers(old_cpu) }
 )]
 pub fn switch_to_user_part2_restore_clobbers(armv7m: &mut Armv7m) {
-    armv7m.mrs(GPR::r0(), SpecialRegister::psp());
-    armv7m.mov(GPR::r6(), GPR::r2());
-    armv7m.mov(GPR::r7(), GPR::r3());
-    armv7m.mov(GPR::r9(), GPR::r12());
-    armv7m.ldmia_w_special(SpecialRegister::sp(), GPR::r8(), GPR::r10(), GPR::r11());
+    armv7m.mrs(GPR::r0(), SysReg::Psp);
+    armv7m.mov(Reg::gpr(GPR::r6()), Reg::gpr(GPR::r2()));
+    armv7m.mov(Reg::gpr(GPR::r7()), Reg::gpr(GPR::r3()));
+    armv7m.mov(Reg::gpr(GPR::r9()), Reg::gpr(GPR::r12()));
+    armv7m.ldmia_w_wback(Reg::sp(), GPR::r8(), GPR::r10(), GPR::r11());
     armv7m.pop(
         GPR::r4(),
         GPR::r5(),
         GPR::r6(),
         GPR::r7(),
-        SpecialRegister::pc(),
+        Reg::pc(),
     );
 }
 
@@ -166,6 +167,11 @@ pub fn switch_to_user_part2(armv7m: &mut Armv7m) {
         register_frame_preserved(sp_main(new_cpu.sp), old_cpu, new_cpu)
         &&
         sp_can_handle_exception_entry(new_cpu)
+        &&
+        chip_unchanged(old_cpu.mem, new_cpu.mem)
+        &&
+        // the MPU registers are in the PPB so the process can not touch them
+        (mpu_permits_privileged(old_cpu.mem) => mpu_permits_privileged(new_cpu.mem))
     }
 )]
 fn process(armv7m: &mut Armv7m) {}
@@ -176,9 +182,11 @@ fn get_r1(armv7m: &Armv7m) -> BV32 {
 }
 
 #[flux_rs::sig(
-    fn (self: &strg Armv7m[@old_cpu], u8[@exception_num])
+    fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@exception_num])
        requires
-            (exception_num == 11 || exception_num >= 15)
+            exception_has_isr(exception_num)
+            &&
+            exception_implemented(exception_num, old_cpu.mem)
             &&
             mode_is_thread_privileged(old_cpu.mode, old_cpu.control)
             &&
@@ -187,6 +195,15 @@ fn get_r1(armv7m: &Armv7m) -> BV32 {
             get_gpr(r1(), old_cpu) == 0x7000_0020
             &&
             sp_main(old_cpu.sp) == 0x6050_0000
+            &&
+            // the addresses above are RAM
+            is_armv7m_default_map(old_cpu.mem)
+            &&
+            // which the MPU lets the kernel use
+            mpu_permits_privileged(old_cpu.mem)
+            &&
+            // and switch_to_user returns to code the kernel can fetch
+            mpu_permits_execute(old_cpu.lr & 0xFFFF_FFFE, true, old_cpu.mem)
        ensures self: Armv7m { new_cpu:
            // r0, r2, r3, and r12 are clobbered but are caller saved
            get_gpr(r1(), new_cpu) == get_gpr(r1(), old_cpu)
@@ -207,16 +224,16 @@ fn get_r1(armv7m: &Armv7m) -> BV32 {
            &&
            get_gpr(r11(), new_cpu) == get_gpr(r11(), old_cpu)
            &&
-           get_special_reg(lr(), new_cpu) == get_special_reg(lr(), old_cpu)
+           get_reg(lr(), new_cpu) == get_reg(lr(), old_cpu)
            &&
            get_special_reg(psr(), new_cpu) == get_special_reg(psr(), old_cpu)
            &&
-           new_cpu.pc == old_cpu.lr
+           new_cpu.pc == old_cpu.lr & 0xFFFF_FFFE
            &&
            mode_is_thread_privileged(new_cpu.mode, new_cpu.control)
        }
 )]
-pub fn tock_control_flow_kernel_to_kernel(armv7m: &mut Armv7m, exception_num: u8) {
+pub fn tock_control_flow_kernel_to_kernel(armv7m: &mut Armv7m, exception_num: ExceptionNumber) {
     // get r1 at the beginning of this so we can assert some facts with it later
     let original_r1 = get_r1(armv7m);
 
@@ -238,6 +255,47 @@ pub fn tock_control_flow_kernel_to_kernel(armv7m: &mut Armv7m, exception_num: u8
     switch_to_user_part2(armv7m);
 }
 
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@exception_num])
+       requires
+            exception_has_isr(exception_num)
+            &&
+            exception_implemented(exception_num, old_cpu.mem)
+            &&
+            mode_is_thread_privileged(old_cpu.mode, old_cpu.control)
+            &&
+            get_gpr(r0(), old_cpu) == 0x8FFF_FFFF
+            &&
+            get_gpr(r1(), old_cpu) == 0x7000_0020
+            &&
+            sp_main(old_cpu.sp) == 0x6050_0000
+            &&
+            is_armv7m_default_map(old_cpu.mem)
+            &&
+            mpu_permits_privileged(old_cpu.mem)
+            &&
+            old_cpu.pc & 0x1 == 0
+            &&
+            // the kernel can fetch switch_to_user and the code after the bl
+            mpu_permits_execute((old_cpu.pc + 0x104) & 0xFFFF_FFFE, true, old_cpu.mem)
+            &&
+            mpu_permits_execute(old_cpu.pc + 0x4, true, old_cpu.mem)
+       ensures self: Armv7m { new_cpu:
+           // switch_to_user returns to the instruction after the bl
+           new_cpu.pc == old_cpu.pc + 0x4
+           &&
+           new_cpu.lr == (old_cpu.pc + 0x4) | 0x1
+           &&
+           mode_is_thread_privileged(new_cpu.mode, new_cpu.control)
+       }
+)]
+pub fn tock_bl_switch_to_user_returns_to_caller(armv7m: &mut Armv7m, exception_num: ExceptionNumber) {
+    // bl switch_to_user
+    armv7m.bl(BV32::from(0x100));
+    // the body of switch_to_user up to its pop {r4, r5, r6, r7, pc}
+    tock_control_flow_kernel_to_kernel(armv7m, exception_num);
+}
+
 #[flux_rs::trusted]
 #[flux_rs::sig(
     fn (self: &strg Armv7m[@old_cpu])
@@ -264,14 +322,21 @@ pub fn tock_control_flow_kernel_to_kernel(armv7m: &mut Armv7m, exception_num: u8
             // and we need to preserve the hardware stacked process registers stack frame
             &&
             register_frame_preserved(sp_process(old_cpu.sp), old_cpu, new_cpu)
+            &&
+            chip_unchanged(old_cpu.mem, new_cpu.mem)
+            &&
+            // and leaves the MPU letting the kernel at its own memory
+            mpu_permits_privileged(new_cpu.mem)
         }
 )]
 fn kernel(armv7m: &mut Armv7m) {}
 
 #[flux_rs::sig(
-    fn (self: &strg Armv7m[@old_cpu], u8[@exception_num])
+    fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@exception_num])
         requires
-            (exception_num == 11 || exception_num >= 15)
+            exception_has_isr(exception_num)
+            &&
+            exception_implemented(exception_num, old_cpu.mem)
             &&
             mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
             &&
@@ -283,6 +348,15 @@ fn kernel(armv7m: &mut Armv7m) {}
             sp_process(old_cpu.sp) == 0x8FFF_DDDD
             &&
             sp_main(old_cpu.sp) == 0x6050_0000
+            &&
+            // the addresses above are RAM
+            is_armv7m_default_map(old_cpu.mem)
+            &&
+            // which the MPU lets the kernel use
+            mpu_permits_privileged(old_cpu.mem)
+            &&
+            // switch_to_user returns to code the kernel can fetch
+            mpu_permits_execute(get_mem_addr(sp_main(old_cpu.sp) + 0x1c, old_cpu.mem) & 0xFFFF_FFFE, true, old_cpu.mem)
         ensures self: Armv7m { new_cpu:
             sp_process(old_cpu.sp) == sp_process(new_cpu.sp)
             &&
@@ -312,14 +386,14 @@ fn kernel(armv7m: &mut Armv7m) {}
             &&
             get_gpr(r12(), new_cpu) == get_gpr(r12(), old_cpu)
             &&
-            get_special_reg(lr(), new_cpu) == get_special_reg(lr(), old_cpu)
+            get_reg(lr(), new_cpu) == get_reg(lr(), old_cpu)
             &&
             get_special_reg(psr(), new_cpu) == get_special_reg(psr(), old_cpu)
             &&
             mode_is_thread_unprivileged(new_cpu.mode, new_cpu.control)
         }
 )]
-pub fn tock_control_flow_process_to_process(armv7m: &mut Armv7m, exception_num: u8) {
+pub fn tock_control_flow_process_to_process(armv7m: &mut Armv7m, exception_num: ExceptionNumber) {
     // arbitrary code executing gets preempted
     armv7m.preempt(exception_num);
 
@@ -333,11 +407,550 @@ pub fn tock_control_flow_process_to_process(armv7m: &mut Armv7m, exception_num:
     switch_to_user_part1(armv7m);
 }
 
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            !old_cpu.lockup
+            &&
+            old_cpu.faultmask == 0
+            &&
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+            &&
+            // Tock does not enable MemManage faults in SHCSR
+            !configurable_fault_enabled(4, old_cpu.mem)
+            &&
+            sp_can_handle_exception_entry(old_cpu)
+        ensures self: Armv7m { new_cpu:
+            // a fault in a process can always be taken
+            !new_cpu.lockup
+            &&
+            // and it is taken as a HardFault so the kernel's hard fault
+            // handler runs
+            mode_is_handler(new_cpu.mode)
+            &&
+            get_special_reg(ipsr(), new_cpu) == 3
+        }
+)]
+pub fn tock_control_flow_process_hard_fault(armv7m: &mut Armv7m) {
+    // a process touches memory its MPU regions do not cover - it runs at
+    // priority 0 in thread mode so the fault can always be taken
+    armv7m.take_fault(ExceptionNumber::MemManage);
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@fault])
+        requires
+            // the kernel's hard fault handler is running
+            mode_is_handler(old_cpu.mode)
+            &&
+            get_special_reg(ipsr(), old_cpu) == 3
+        ensures self: Armv7m { new_cpu: new_cpu.lockup && new_cpu.pc == 0xFFFF_FFFE }
+)]
+pub fn tock_control_flow_hard_fault_handler_fault_locks_up(
+    armv7m: &mut Armv7m,
+    fault: ExceptionNumber,
+) {
+    // HardFault runs at priority -1 so a fault inside its handler can not be
+    // taken - whatever the fault is, the core locks up
+    armv7m.take_fault(fault);
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@fault])
+        requires
+            !old_cpu.lockup
+            &&
+            old_cpu.faultmask == 0
+            &&
+            // the kernel is running
+            mode_is_thread_privileged(old_cpu.mode, old_cpu.control)
+            &&
+            !configurable_fault_enabled(4, old_cpu.mem)
+            &&
+            sp_can_handle_exception_entry(old_cpu)
+        ensures self: Armv7m { new_cpu: new_cpu.lockup && new_cpu.pc == 0xFFFF_FFFE }
+)]
+pub fn tock_control_flow_kernel_hard_fault_locks_up(armv7m: &mut Armv7m, fault: ExceptionNumber) {
+    // the kernel touches memory it should not - the fault is taken as a
+    // HardFault from the main stack
+    armv7m.take_fault(ExceptionNumber::MemManage);
+    // so the handler takes its kernel path, which panics instead of
+    // returning (see Armv7m::hard_fault_isr)
+    assert(armv7m.lr & BV32::from(0x4) == BV32::from(0));
+    // and a fault while it panics locks the core up
+    armv7m.take_fault(fault);
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@exception_num])
+        requires
+            !old_cpu.lockup
+            &&
+            old_cpu.faultmask == 0
+            &&
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+            &&
+            // the process has pointed psp somewhere we can not stack to
+            // (outside RAM or outside its MPU regions)
+            !sp_can_handle_exception_entry(old_cpu)
+            &&
+            // and traps into the kernel (an SVC, an interrupt, a fault...)
+            exception_num != 2 && exception_num != 3
+        ensures self: Armv7m { new_cpu:
+            // stacking faults but a process can not lock the core up
+            !new_cpu.lockup
+            &&
+            // the kernel gets to handle the stacking fault
+            mode_is_handler(new_cpu.mode)
+            &&
+            get_psp(new_cpu.sp) == get_psp(old_cpu.sp) - 0x20
+        }
+)]
+pub fn tock_control_flow_process_bad_stack(armv7m: &mut Armv7m, exception_number: ExceptionNumber) {
+    armv7m.exception_entry(exception_number);
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], BV32[@addr], BV32[@val]) -> bool[false]
+        requires
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+            &&
+            // the kernel enabled the MPU
+            mpu_enabled(old_cpu.mem)
+            &&
+            // and no region it set up for the process covers addr (which
+            // could be the kernel's RAM or another process' RAM)
+            !mpu_region_covers(addr, old_cpu.mem)
+            &&
+            is_valid_ram_addr(addr, old_cpu.mem)
+            &&
+            is_valid_write_access(addr, 4, old_cpu.mem)
+)]
+pub fn tock_process_cannot_write_outside_its_regions(
+    armv7m: &mut Armv7m,
+    addr: BV32,
+    val: BV32,
+) -> bool {
+    armv7m.write32_checked(addr, val)
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], BV32[@val]) -> bool[false]
+        requires mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+)]
+pub fn tock_process_cannot_disable_the_mpu(armv7m: &mut Armv7m, val: BV32) -> bool {
+    // MPU_CTRL is in the PPB so the write raises a BusFault
+    armv7m.write32_checked(BV32::from(0xE000_ED94), val)
+}
+
+#[flux_rs::sig(
+    fn (&Memory[@old_mem], &TockProcessRegions[@regions]) -> Memory{new_mem: tock_process_mpu_configured(new_mem, regions)}
+        requires tock_process_regions_valid(regions)
+)]
+fn tock_configure_process_mpu(mem: &Memory, regions: &TockProcessRegions) -> Memory {
+    let mut mem = mem.duplicate();
+    mem.configure_tock_process_mpu(regions);
+    mem
+}
+
+#[flux_rs::sig(
+    fn (&Memory[@mem], &TockProcessRegions[@regions], BV32[@addr]) -> bool[true]
+        requires
+            tock_process_regions_valid(regions)
+            &&
+            tock_process_mpu_configured(mem, regions)
+            &&
+            // addr is neither the process' flash nor its RAM below the app
+            // break - the kernel's stack, grants and other processes' memory
+            // all live out here
+            !tock_process_flash_contains(addr, regions)
+            &&
+            !tock_process_ram_contains(addr, regions)
+            &&
+            // the PPB is protected by privilege checks rather than the MPU
+            addr < 0xE000_0000
+)]
+fn tock_process_isolated(mem: &Memory, regions: &TockProcessRegions, addr: BV32) -> bool {
+    let permissions = mem.mpu_permissions(addr, false);
+    !permissions.read && !permissions.write && !permissions.execute
+}
+
+#[flux_rs::sig(
+    fn (&Memory[@mem], &TockProcessRegions[@regions], BV32[@addr]) -> bool[true]
+        requires
+            tock_process_regions_valid(regions)
+            &&
+            tock_process_mpu_configured(mem, regions)
+            &&
+            tock_process_ram_contains(addr, regions)
+            &&
+            // flash is the higher region so it wins if the two overlap
+            !tock_process_flash_contains(addr, regions)
+)]
+fn tock_process_owns_its_ram(mem: &Memory, regions: &TockProcessRegions, addr: BV32) -> bool {
+    // the process can read & write its RAM but not execute from it
+    let permissions = mem.mpu_permissions(addr, false);
+    permissions.read && permissions.write && !permissions.execute
+}
+
+#[flux_rs::sig(
+    fn (&Memory[@mem], &TockProcessRegions[@regions], BV32[@addr]) -> bool[true]
+        requires
+            tock_process_regions_valid(regions)
+            &&
+            tock_process_mpu_configured(mem, regions)
+            &&
+            tock_process_flash_contains(addr, regions)
+)]
+fn tock_process_flash_is_read_only(
+    mem: &Memory,
+    regions: &TockProcessRegions,
+    addr: BV32,
+) -> bool {
+    // the process can run its code but not rewrite it
+    let permissions = mem.mpu_permissions(addr, false);
+    permissions.read && !permissions.write && permissions.execute
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+            &&
+            kernel_statics_in_ram(old_cpu.mem)
+            &&
+            sp_can_handle_exception_entry(old_cpu)
+            &&
+            sp_can_handle_preempt_exception_exit(old_cpu, 15)
+            &&
+            // the kernel armed SysTick for the process' timeslice
+            sys_tick_enabled(old_cpu.mem)
+            &&
+            sys_tick_int_enabled(old_cpu.mem)
+            &&
+            // and the timeslice is about to run out
+            sys_tick_current(old_cpu.mem) == 1
+        ensures self: Armv7m { new_cpu:
+            // we end up back in the kernel
+            mode_is_thread_privileged(new_cpu.mode, new_cpu.control)
+            &&
+            // which sees the timeslice expired
+            sys_tick_count_flag(new_cpu.mem)
+            &&
+            !sys_tick_pending(new_cpu.mem)
+        }
+)]
+pub fn tock_control_flow_process_timeslice_expired(armv7m: &mut Armv7m) {
+    // the process runs until the counter hits 0
+    armv7m.tick();
+    // and SysTick preempts it
+    armv7m.take_pending_sys_tick();
+}
+
+// The proof above on a real chip - the nRF52840 has no external RAM so the
+// kernel's statics are in its SRAM
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            is_nrf52840_map(old_cpu.mem)
+            &&
+            // the MPU lets the kernel use its SRAM
+            mpu_permits_privileged(old_cpu.mem)
+            &&
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+            &&
+            sp_can_handle_exception_entry(old_cpu)
+            &&
+            sp_can_handle_preempt_exception_exit(old_cpu, 15)
+            &&
+            sys_tick_enabled(old_cpu.mem)
+            &&
+            sys_tick_int_enabled(old_cpu.mem)
+            &&
+            sys_tick_current(old_cpu.mem) == 1
+        ensures self: Armv7m { new_cpu:
+            mode_is_thread_privileged(new_cpu.mode, new_cpu.control)
+            &&
+            sys_tick_count_flag(new_cpu.mem)
+            &&
+            !sys_tick_pending(new_cpu.mem)
+        }
+)]
+pub fn tock_control_flow_process_timeslice_expired_nrf52840(armv7m: &mut Armv7m) {
+    tock_control_flow_process_timeslice_expired(armv7m);
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], BV32[@other_irq])
+        requires
+            get_special_reg(ipsr(), old_cpu) >= 16
+            &&
+            mode_is_handler(old_cpu.mode)
+            &&
+            // only an interrupt the chip implements can be active
+            nvic_irq_implemented(generic_isr_irq(old_cpu), old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            // the interrupt is disabled and left pending for the kernel to
+            // service once it is back in thread mode
+            !nvic_irq_enabled(generic_isr_irq(old_cpu), new_cpu.mem)
+            &&
+            nvic_irq_pending(generic_isr_irq(old_cpu), new_cpu.mem)
+            &&
+            // every other interrupt is left alone
+            (other_irq != generic_isr_irq(old_cpu) => nvic_irq_unchanged(other_irq, old_cpu.mem, new_cpu.mem))
+        }
+)]
+pub fn tock_generic_isr_disables_and_pends_irq(armv7m: &mut Armv7m, other_irq: BV32) {
+    armv7m.generic_isr();
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            reset_precondition(old_cpu.mem)
+            &&
+            // the vector table is in flash
+            is_valid_code_addr(reset_vector_table(old_cpu.mem), old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            // the core comes back up in privileged thread mode on the reset
+            // vector's stack
+            mode_is_thread_privileged(new_cpu.mode, new_cpu.control)
+            &&
+            sp_main(new_cpu.sp) == reset_sp_main(old_cpu.mem)
+            &&
+            new_cpu.pc == reset_start(old_cpu.mem) & 0xFFFF_FFFE
+        }
+)]
+pub fn tock_reset_through_aircr(armv7m: &mut Armv7m) {
+    // what cortexm::support::reset does - VECTKEY | SYSRESETREQ to AIRCR
+    armv7m
+        .mem
+        .write(BV32::from(0xE000_ED0C), BV32::from(0x05FA_0004));
+    armv7m.take_pending_reset();
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Memory[@old_mem], BV32[@addr], BV32[@other])
+        requires is_valid_bit_band_alias(addr, old_mem)
+        ensures self: Memory { new_mem:
+            // the flag reads back as set
+            get_mem_addr(addr, new_mem) == 1
+            &&
+            // the rest of the word holding it is untouched
+            map_get(new_mem, bit_band_word_addr(addr)) & bv_not(1 << bit_band_bit(addr))
+                == map_get(old_mem, bit_band_word_addr(addr)) & bv_not(1 << bit_band_bit(addr))
+            &&
+            // and so is every other word
+            (other != bit_band_word_addr(addr) => map_get(new_mem, other) == map_get(old_mem, other))
+        }
+)]
+pub fn tock_bit_band_sets_one_flag(mem: &mut Memory, addr: BV32, other: BV32) {
+    mem.write(addr, BV32::from(1));
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            is_valid_write_addr(get_gpr(r4(), old_cpu), old_cpu.mem)
+            &&
+            word_aligned(get_gpr(r4(), old_cpu))
+            &&
+            // the kernel runs it so the MPU lets both accesses through
+            mode_is_privileged(old_cpu.mode, old_cpu.control)
+            &&
+            mpu_permits_privileged(old_cpu.mem)
+        ensures self: Armv7m { new_cpu:
+            // nothing got in between so the store goes through
+            get_gpr(r2(), new_cpu) == 0
+            &&
+            get_mem_addr(get_gpr(r4(), old_cpu), new_cpu.mem)
+                == get_mem_addr(get_gpr(r4(), old_cpu), old_cpu.mem) - 1
+        }
+)]
+pub fn tock_atomic_decrement(armv7m: &mut Armv7m) {
+    // ldrex r1, [r4]
+    armv7m.ldrex(GPR::r1(), GPR::r4(), BV32::from(0));
+    // subw r1, r1, #1
+    armv7m.subw_imm(GPR::r1(), GPR::r1(), BV32::from(1));
+    // strex r2, r1, [r4]
+    armv7m.strex(GPR::r2(), GPR::r1(), GPR::r4(), BV32::from(0));
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            is_valid_write_addr(get_gpr(r4(), old_cpu), old_cpu.mem)
+            &&
+            word_aligned(get_gpr(r4(), old_cpu))
+            &&
+            // the kernel runs it so the MPU lets both accesses through
+            mode_is_privileged(old_cpu.mode, old_cpu.control)
+            &&
+            mpu_permits_privileged(old_cpu.mem)
+            &&
+            kernel_statics_in_ram(old_cpu.mem)
+            &&
+            sp_can_handle_exception_entry(old_cpu)
+            &&
+            sp_can_handle_preempt_exception_exit(old_cpu, 15)
+        ensures self: Armv7m { new_cpu:
+            // the handler could have written the word so the store must fail
+            // and the sequence be retried
+            get_gpr(r2(), new_cpu) == 1
+            &&
+            !new_cpu.monitor.exclusive
+        }
+)]
+pub fn tock_atomic_decrement_fails_across_preemption(armv7m: &mut Armv7m) {
+    // ldrex r1, [r4]
+    armv7m.ldrex(GPR::r1(), GPR::r4(), BV32::from(0));
+    // subw r1, r1, #1
+    armv7m.subw_imm(GPR::r1(), GPR::r1(), BV32::from(1));
+    // SysTick fires between the load and the store
+    armv7m.preempt(ExceptionNumber::SysTick);
+    // strex r2, r1, [r4]
+    armv7m.strex(GPR::r2(), GPR::r1(), GPR::r4(), BV32::from(0));
+}
+
+// Without the isb this does not verify - until then whether the core has
+// dropped privilege is UNKNOWN (see Armv7m::msr)
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            mode_is_thread_privileged(old_cpu.mode, old_cpu.control)
+            &&
+            mode_is_thread_privileged(old_cpu.mode, old_cpu.pending_control)
+        ensures self: Armv7m { new_cpu:
+            !mode_is_privileged(new_cpu.mode, new_cpu.control)
+        }
+)]
+pub fn tock_control_write_with_isb_drops_privilege(armv7m: &mut Armv7m) {
+    // movw r0, #1
+    armv7m.movw_imm(GPR::r0(), BV32::from(1));
+    // msr CONTROL, r0
+    armv7m.msr(SysReg::Control, GPR::r0());
+    // isb
+    armv7m.isb(Some(IsbOpt::Sys));
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        ensures self: Armv7m { new_cpu:
+            // the kernel's idle loop waits for an interrupt
+            new_cpu.sleep.sleeping
+            &&
+            !new_cpu.sleep.on_event
+        }
+)]
+pub fn tock_idle_sleeps(armv7m: &mut Armv7m) {
+    // dsb
+    armv7m.dsb(Some(DsbOpt::Sys));
+    // wfi
+    armv7m.wfi();
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires !old_cpu.sleep.sleeping
+        ensures self: Armv7m { new_cpu:
+            // the event sent beforehand is consumed instead of sleeping
+            !new_cpu.sleep.sleeping
+            &&
+            !new_cpu.event
+        }
+)]
+pub fn tock_sev_keeps_wfe_awake(armv7m: &mut Armv7m) {
+    // sev
+    armv7m.sev();
+    // wfe
+    armv7m.wfe();
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu], SysReg[@sysm], GPR[@rn])
+        requires
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+            &&
+            mode_is_thread_unprivileged(old_cpu.mode, old_cpu.pending_control)
+        ensures self: Armv7m { new_cpu:
+            // whatever a process writes to whichever special register it
+            // keeps running unprivileged with the kernel's masks & stack
+            mode_is_thread_unprivileged(new_cpu.mode, new_cpu.control)
+            &&
+            sp_main(new_cpu.sp) == sp_main(old_cpu.sp)
+            &&
+            new_cpu.primask == old_cpu.primask
+            &&
+            new_cpu.basepri == old_cpu.basepri
+            &&
+            new_cpu.faultmask == old_cpu.faultmask
+        }
+)]
+pub fn tock_process_cannot_raise_privilege(armv7m: &mut Armv7m, sysm: SysReg, rn: GPR) {
+    // msr <sysm>, rn
+    armv7m.msr(sysm, rn);
+    // isb
+    armv7m.isb(Some(IsbOpt::Sys));
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires mode_is_thread_unprivileged(old_cpu.mode, old_cpu.control)
+        ensures self: Armv7m { new_cpu: get_gpr(r0(), new_cpu) == 0 }
+)]
+pub fn tock_process_cannot_read_msp(armv7m: &mut Armv7m) {
+    // mrs r0, MSP
+    armv7m.mrs(GPR::r0(), SysReg::Msp);
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires
+            mode_is_handler(old_cpu.mode)
+            &&
+            mpu_permits_privileged(old_cpu.mem)
+            &&
+            is_valid_read_addr(sp_main(old_cpu.sp) + 0x4, old_cpu.mem)
+            &&
+            word_aligned(sp_main(old_cpu.sp) + 0x4)
+        ensures self: Armv7m { new_cpu:
+            // r1 as the hardware stacked it on exception entry
+            get_gpr(r1(), new_cpu) == get_mem_addr(sp_main(old_cpu.sp) + 0x4, old_cpu.mem)
+            &&
+            new_cpu.sp == old_cpu.sp
+        }
+)]
+pub fn tock_v6m_generic_isr_loads_stacked_r1(armv7m: &mut Armv7m) {
+    // mov r1, sp
+    armv7m.mov(Reg::gpr(GPR::r1()), Reg::sp());
+    // ldr r1, [r1, #4]
+    armv7m.ldr_imm(Reg::gpr(GPR::r1()), Reg::gpr(GPR::r1()), BV32::from(4));
+}
+
+#[flux_rs::sig(
+    fn (self: &strg Armv7m[@old_cpu])
+        requires mode_is_thread_privileged(old_cpu.mode, old_cpu.control)
+        ensures self: Armv7m { new_cpu:
+            // the epilogue gives back exactly the frame the prologue took
+            new_cpu.sp == old_cpu.sp
+            &&
+            get_gpr(r7(), new_cpu) == sp_main(old_cpu.sp) - 0x8
+        }
+)]
+pub fn tock_prologue_epilogue_balance_sp(armv7m: &mut Armv7m) {
+    // sub sp, #8
+    armv7m.sub_imm(Reg::sp(), Reg::sp(), BV32::from(8));
+    // mov r7, sp
+    armv7m.mov(Reg::gpr(GPR::r7()), Reg::sp());
+    // add sp, #8
+    armv7m.add_imm(Reg::sp(), Reg::sp(), BV32::from(8));
+}
+
 mod arm_test {
     use crate::{
         armv7m::{
             cpu::{Armv7m, SP},
-            lang::{SpecialRegister, GPR},
+            lang::{ExceptionNumber, SpecialRegister, GPR},
             mem::{flux_defs, Memory},
         },
         assert,
@@ -359,14 +972,18 @@ mod arm_test {
             register_frame_preserved(sp_main(new_cpu.sp), old_cpu, new_cpu)
             &&
             sp_can_handle_exception_entry(new_cpu)
+            &&
+            chip_unchanged(old_cpu.mem, new_cpu.mem)
         }
     )]
     fn process(armv7m: &mut Armv7m) {}
 
     #[flux_rs::sig(
-        fn (self: &strg Armv7m[@old_cpu], u8[@exception_num])
+        fn (self: &strg Armv7m[@old_cpu], ExceptionNumber[@exception_num])
            requires
-               (exception_num == 11 || exception_num >= 15)
+               exception_has_isr(exception_num)
+               &&
+               exception_implemented(exception_num, old_cpu.mem)
                &&
                mode_is_thread_privileged(old_cpu.mode, old_cpu.control)
                &&
@@ -389,14 +1006,16 @@ mod arm_test {
                    // sp_process(old_cpu.sp) < sp_main(old_cpu.sp) - 0x20
                )
                && sp_can_handle_preempt_exception_exit(old_cpu, exception_num)
+               // the addresses above are RAM
+               && is_armv7m_default_map(old_cpu.mem)
            ensures self: Armv7m { new_cpu:
                sp_main(new_cpu.sp) == sp_main(old_cpu.sp) && get_gpr(r0(), new_cpu) == 10
             }
     )]
-    fn full_circle(armv7m: &mut Armv7m, exception_number: u8) {
+    fn full_circle(armv7m: &mut Armv7m, exception_number: ExceptionNumber) {
         // executes some kernel logic
         armv7m.movw_imm(GPR::r0(), BV32::from(10));
-        armv7m.preempt(11);
+        armv7m.preempt(ExceptionNumber::SVCall);
         // process that havocs all state except the main sp and the fact it's in thread mode unprivileged
         process(armv7m);
         // fake sys call